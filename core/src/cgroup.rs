@@ -112,6 +112,74 @@ impl CGroup {
         Ok(())
     }
 
+    /// Returns the controllers, which the parent cgroup enabled for this one
+    pub fn controllers(&self) -> anyhow::Result<Vec<String>> {
+        if !is_cgroup(&self.path)? {
+            bail!("{} is not a valid cgroup", self.path.display());
+        }
+
+        let controllers = fs::read_to_string(self.path.join("cgroup.controllers"))?;
+        Ok(controllers.split_whitespace().map(String::from).collect())
+    }
+
+    /// Enables controllers, e.g. "memory" or "pids", for the sub-cgroups of
+    /// this one (does nothing if already enabled)
+    ///
+    /// The cgroup must not contain any processes itself.
+    pub fn enable_controllers(&self, controllers: &[&str]) -> anyhow::Result<()> {
+        trace!(
+            "Enable controllers {controllers:?} below {}",
+            self.get_path().display()
+        );
+        if !is_cgroup(&self.path)? {
+            bail!("{} is not a valid cgroup", self.path.display());
+        }
+
+        let enable = controllers.iter().map(|c| format!("+{c}")).join(" ");
+        fs::write(self.path.join("cgroup.subtree_control"), enable)?;
+        Ok(())
+    }
+
+    /// Sets the hard memory limit in bytes, above which the OOM killer is
+    /// invoked (`None` removes the limit)
+    pub fn set_memory_max(&self, bytes: Option<u64>) -> anyhow::Result<()> {
+        self.set_limit("memory.max", bytes)
+    }
+
+    /// Sets the memory limit in bytes, above which the processes are
+    /// throttled (`None` removes the limit)
+    pub fn set_memory_high(&self, bytes: Option<u64>) -> anyhow::Result<()> {
+        self.set_limit("memory.high", bytes)
+    }
+
+    /// Sets the maximum number of processes and threads (`None` removes the
+    /// limit)
+    pub fn set_pids_max(&self, max: Option<u64>) -> anyhow::Result<()> {
+        self.set_limit("pids.max", max)
+    }
+
+    fn set_limit(&self, file: &str, limit: Option<u64>) -> anyhow::Result<()> {
+        trace!("Set {file} of {} to {limit:?}", self.get_path().display());
+        if !is_cgroup(&self.path)? {
+            bail!("{} is not a valid cgroup", self.path.display());
+        }
+
+        let limit = limit.map_or_else(|| "max".to_string(), |l| l.to_string());
+        fs::write(self.path.join(file), limit)?;
+        Ok(())
+    }
+
+    /// Returns the number of processes in this cgroup and its sub-cgroups,
+    /// which were killed by the OOM killer
+    pub fn oom_kills(&self) -> anyhow::Result<u64> {
+        if !is_cgroup(&self.path)? {
+            bail!("{} is not a valid cgroup", self.path.display());
+        }
+
+        let events = fs::read_to_string(self.path.join("memory.events"))?;
+        Ok(event_count(&events, "oom_kill").unwrap_or(0))
+    }
+
     /// Returns all PIDs associated with this cgroup
     pub fn get_pids(&self) -> anyhow::Result<Vec<Pid>> {
         if !is_cgroup(&self.path)? {
@@ -297,6 +365,16 @@ pub fn current_cgroup() -> anyhow::Result<PathBuf> {
     Ok(PathBuf::from(path))
 }
 
+/// Returns the counter of `key` in the content of an events file, e.g.
+/// `memory.events`
+fn event_count(events: &str, key: &str) -> Option<u64> {
+    events
+        .lines()
+        .filter_map(|line| line.split_once(' '))
+        .find(|(k, _)| *k == key)
+        .and_then(|(_, count)| count.trim().parse().ok())
+}
+
 /// Checks if path is a valid cgroup by comparing the device id
 fn is_cgroup(path: &Path) -> anyhow::Result<bool> {
     let st = statfs::statfs(path)?;
@@ -358,6 +436,16 @@ mod tests {
         assert!(!path_cg1.exists());
     }
 
+    #[test]
+    fn controllers() {
+        let cg = CGroup::new_root(get_path(), &gen_name()).unwrap();
+        let delegated = fs::read_to_string(get_path().join("cgroup.subtree_control")).unwrap();
+        let delegated = delegated.split_whitespace().collect_vec();
+        assert_eq!(cg.controllers().unwrap(), delegated);
+
+        cg.rm().unwrap();
+    }
+
     #[test]
     fn mv() {
         let mut proc = spawn_proc().unwrap();
@@ -474,6 +562,14 @@ mod tests {
         assert!(!super::is_cgroup(Path::new("/tmp")).unwrap());
     }
 
+    #[test]
+    fn event_count() {
+        let events = "low 0\nhigh 12\nmax 3\noom 2\noom_kill 1\noom_group_kill 0\n";
+        assert_eq!(super::event_count(events, "oom_kill"), Some(1));
+        assert_eq!(super::event_count(events, "oom"), Some(2));
+        assert_eq!(super::event_count(events, "missing"), None);
+    }
+
     /// Spawns a child process of sleep(1)
     fn spawn_proc() -> io::Result<process::Child> {
         process::Command::new("sleep")
@@ -37,6 +37,8 @@ pub enum SystemError {
     FloatingPoint,
     #[error("cgroup related error")]
     CGroup,
+    #[error("Memory limit of partition was exceeded")]
+    OutOfMemory,
 }
 
 /// The time window in which the error has occurred
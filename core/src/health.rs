@@ -1,4 +1,5 @@
 //! Health control types
+use a653rs::bindings::ErrorCode;
 use serde::{Deserialize, Serialize};
 
 use crate::error::SystemError;
@@ -32,9 +33,17 @@ pub struct PartitionHMTable {
     pub panic: RecoveryAction,
     pub floating_point_error: RecoveryAction,
     pub cgroup: RecoveryAction,
+    /// Action once the OOM killer killed a process of the partition, because
+    /// its memory limit was exceeded
+    #[serde(default = "PartitionHMTable::default_out_of_memory")]
+    pub out_of_memory: RecoveryAction,
 }
 
 impl PartitionHMTable {
+    fn default_out_of_memory() -> RecoveryAction {
+        RecoveryAction::Partition(PartitionRecoveryAction::ColdStart)
+    }
+
     pub fn try_action(&self, err: SystemError) -> Option<RecoveryAction> {
         match err {
             SystemError::PartitionInit => Some(self.partition_init),
@@ -44,6 +53,7 @@ impl PartitionHMTable {
             SystemError::Panic => Some(self.panic),
             SystemError::FloatingPoint => Some(self.floating_point_error),
             SystemError::CGroup => Some(self.cgroup),
+            SystemError::OutOfMemory => Some(self.out_of_memory),
             _ => None,
         }
     }
@@ -60,6 +70,63 @@ impl Default for PartitionHMTable {
             panic: RecoveryAction::Partition(PartitionRecoveryAction::WarmStart),
             application_error: RecoveryAction::Partition(PartitionRecoveryAction::WarmStart),
             cgroup: RecoveryAction::Partition(PartitionRecoveryAction::WarmStart),
+            out_of_memory: Self::default_out_of_memory(),
+        }
+    }
+}
+
+/// Level at which the health monitor handles an error of a process
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum HMLevel {
+    /// The error is delivered to the error handler of the partition. Without
+    /// an error handler, the error is handled at partition level instead.
+    Process,
+    /// The error is handled according to the [PartitionHMTable]
+    Partition,
+    /// The error is handled according to the [ModuleRunHMTable]
+    Module,
+}
+
+/// Levels at which the errors of the processes of a partition are handled, by
+/// their error code
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ProcessHMTable {
+    pub deadline_missed: HMLevel,
+    pub application_error: HMLevel,
+    pub numeric_error: HMLevel,
+    pub illegal_request: HMLevel,
+    pub stack_overflow: HMLevel,
+    pub memory_violation: HMLevel,
+    pub hardware_fault: HMLevel,
+    pub power_fail: HMLevel,
+}
+
+impl ProcessHMTable {
+    pub fn level(&self, code: ErrorCode) -> HMLevel {
+        match code {
+            ErrorCode::DeadlineMissed => self.deadline_missed,
+            ErrorCode::ApplicationError => self.application_error,
+            ErrorCode::NumericError => self.numeric_error,
+            ErrorCode::IllegalRequest => self.illegal_request,
+            ErrorCode::StackOverflow => self.stack_overflow,
+            ErrorCode::MemoryViolation => self.memory_violation,
+            ErrorCode::HardwareFault => self.hardware_fault,
+            ErrorCode::PowerFail => self.power_fail,
+        }
+    }
+}
+
+impl Default for ProcessHMTable {
+    fn default() -> Self {
+        Self {
+            deadline_missed: HMLevel::Process,
+            application_error: HMLevel::Process,
+            numeric_error: HMLevel::Process,
+            illegal_request: HMLevel::Process,
+            stack_overflow: HMLevel::Partition,
+            memory_violation: HMLevel::Partition,
+            hardware_fault: HMLevel::Module,
+            power_fail: HMLevel::Module,
         }
     }
 }
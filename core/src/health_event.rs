@@ -17,31 +17,55 @@ pub enum PartitionCall {
 }
 
 impl PartitionCall {
-    /// Prints debugs, warnings, traces and errors to their accompanying streams
-    // TODO: Somehow comment what is going on inside this beast
-    pub fn print_partition_log(&self, name: &str) {
-        let name = &format!("Partition: {name}");
+    /// Returns the level and the message with which this call is logged
+    pub fn log_record(&self) -> (Level, String) {
         match self {
-            PartitionCall::Error(e) => error!(target: name, "{e:?}"),
+            PartitionCall::Error(e) => (Level::Error, format!("{e:?}")),
             PartitionCall::Message(msg) => {
+                // Messages are prefixed by the number of their level
                 let mut msg_chars = msg.chars();
                 if let Some(level) = msg_chars.next() {
-                    let msg = msg_chars.collect::<String>();
                     if let Ok(level) = level.to_string().parse::<usize>() {
-                        return match level {
-                            l if l == Level::Debug as usize => debug!(target: name, "{msg}"),
-                            l if l == Level::Warn as usize => warn!(target: name, "{msg}"),
-                            l if l == Level::Trace as usize => trace!(target: name, "{msg}"),
-                            l if l == Level::Error as usize => error!(target: name, "{msg}"),
-                            _ => info!(target: name, "{msg}"),
+                        let level = match level {
+                            l if l == Level::Debug as usize => Level::Debug,
+                            l if l == Level::Warn as usize => Level::Warn,
+                            l if l == Level::Trace as usize => Level::Trace,
+                            l if l == Level::Error as usize => Level::Error,
+                            _ => Level::Info,
                         };
+                        return (level, msg_chars.collect());
                     }
                 }
-                info!(target: name, "{msg}")
-            }
-            PartitionCall::Transition(mode) => {
-                debug!(target: name, "Received Transition Request: {mode:?}")
+                (Level::Info, msg.clone())
             }
+            PartitionCall::Transition(mode) => (
+                Level::Debug,
+                format!("Received Transition Request: {mode:?}"),
+            ),
         }
     }
+
+    /// Prints debugs, warnings, traces and errors to their accompanying streams
+    pub fn print_partition_log(&self, name: &str) {
+        let name = &format!("Partition: {name}");
+        let (level, msg) = self.log_record();
+        log!(target: name, level, "{msg}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn log_record() {
+        let message = |msg: &str| PartitionCall::Message(msg.to_string()).log_record();
+        assert_eq!(message("2careful"), (Level::Warn, "careful".to_string()));
+        assert_eq!(message("9unknown"), (Level::Info, "unknown".to_string()));
+        assert_eq!(message("plain"), (Level::Info, "plain".to_string()));
+        assert_eq!(
+            PartitionCall::Error(SystemError::Panic).log_record().0,
+            Level::Error
+        );
+    }
 }
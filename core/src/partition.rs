@@ -3,7 +3,7 @@ use std::io::{Read, Write};
 use std::os::unix::prelude::{IntoRawFd, RawFd};
 use std::time::Duration;
 
-use a653rs::bindings::PortDirection;
+use a653rs::bindings::{MutexId, PortDirection};
 use a653rs::prelude::{PartitionId, StartCondition};
 use memfd::{FileSeal, MemfdOptions};
 use serde::{Deserialize, Serialize};
@@ -22,8 +22,11 @@ pub struct PartitionConstants {
 
     // A UNIX domain socket, that is used to send file descriptors to the partition.
     pub io_fd: RawFd,
+    /// Memory retained across warm starts of the partition
+    pub persistent_memory_fd: Option<RawFd>,
 
     pub sampling: Vec<SamplingConstant>,
+    pub queuing: Vec<QueuingConstant>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -34,12 +37,27 @@ pub struct SamplingConstant {
     pub fd: RawFd,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct QueuingConstant {
+    pub name: String,
+    pub dir: PortDirection,
+    pub msg_size: usize,
+    pub msg_num: usize,
+    pub fd: RawFd,
+}
+
 impl PartitionConstants {
     pub const PARTITION_CONSTANTS_FD: &'static str = "PARTITION_CONSTANTS_FD";
     pub const PROCESSES_CGROUP: &'static str = "processes";
     pub const MAIN_PROCESS_CGROUP: &'static str = "main";
-    pub const APERIODIC_PROCESS_CGROUP: &'static str = "aperiodic";
-    pub const PERIODIC_PROCESS_CGROUP: &'static str = "periodic";
+    /// Frozen cgroup holding the threads of stopped processes
+    pub const STOPPED_PROCESS_CGROUP: &'static str = "stopped";
+    /// Maximum number of processes per partition
+    pub const MAX_PROCESSES: usize = 128;
+    /// Mutex id of processes, which own no mutex
+    pub const NO_MUTEX_OWNED: MutexId = -2;
+    /// Name of the error handler process of a partition
+    pub const ERROR_HANDLER_NAME: &'static str = "ERROR_HANDLER";
     pub const IPC_SENDER: &'static str = "/.inner/ipc";
 
     pub fn open() -> TypedResult<Self> {
@@ -1,23 +1,605 @@
-//#[derive(Debug, Clone, Copy)]
-//struct Datagram<const SIZE: usize> {
-//    len: usize,
-//    data: [u8; SIZE],
-//}
-//
-//struct RingBuffer<const MSG_SIZE: usize, const MSGS: usize>{
-//  reader_index: usize,
-//  writer_index: usize,
-//  written: usize,
-//
-//}
-//
-//#[derive(Debug)]
-//pub struct Queuing<const MSG_SIZE: usize, const MSGS: usize> {
-//
-//}
-//
-//#[derive(Debug)]
-//pub struct QueuingDestination {}
-//
-//#[derive(Debug)]
-//pub struct QueuingSource {}
+//! Implementation of queuing channels
+//!
+//! A queuing channel consists of two queues in shared memory. The source queue
+//! is filled by the sending partition, while the destination queue is drained
+//! by the receiving partition. The hypervisor moves the messages from the
+//! source to the destination queue after each time frame of the sending
+//! partition, as far as the destination queue has room for them.
+use std::os::fd::{AsFd, BorrowedFd};
+use std::os::unix::prelude::{AsRawFd, OwnedFd};
+
+use a653rs::bindings::PortDirection;
+use anyhow::{anyhow, bail};
+use byteorder::{ByteOrder, NativeEndian};
+use memfd::{FileSeal, Memfd, MemfdOptions};
+use memmap2::MmapMut;
+
+use crate::channel::{PortConfig, QueuingChannelConfig};
+use crate::error::{ResultExt, SystemError, TypedError, TypedResult};
+use crate::partition::QueuingConstant;
+
+/// Ring buffer of messages on top of a shared memory region
+///
+/// The memory is laid out as follows:
+/// ```text
+/// first: u32     index of the oldest message
+/// len: u32       number of stored messages
+/// overflow: u32  whether messages were discarded when clearing a corrupted queue
+/// slots: [(len: u32, data: [u8; msg_size]); msg_num]
+/// ```
+#[derive(Debug)]
+struct MessageQueue<'a> {
+    mem: &'a mut [u8],
+    msg_size: usize,
+    msg_num: usize,
+}
+
+impl<'a> MessageQueue<'a> {
+    const FIRST: usize = 0;
+    const LEN: usize = 4;
+    const OVERFLOW: usize = 8;
+    const HEADER_BYTES: usize = 12;
+
+    const fn slot_size(msg_size: usize) -> usize {
+        std::mem::size_of::<u32>() + msg_size
+    }
+
+    const fn size(msg_size: usize, msg_num: usize) -> usize {
+        Self::HEADER_BYTES + msg_num * Self::slot_size(msg_size)
+    }
+
+    fn new(mem: &'a mut [u8], msg_size: usize, msg_num: usize) -> Self {
+        assert!(mem.len() >= Self::size(msg_size, msg_num));
+        Self {
+            mem,
+            msg_size,
+            msg_num,
+        }
+    }
+
+    fn get(&self, offset: usize) -> usize {
+        NativeEndian::read_u32(&self.mem[offset..offset + 4]) as usize
+    }
+
+    fn set(&mut self, offset: usize, val: usize) {
+        NativeEndian::write_u32(&mut self.mem[offset..offset + 4], val as u32)
+    }
+
+    fn slot(&self, index: usize) -> usize {
+        Self::HEADER_BYTES + index * Self::slot_size(self.msg_size)
+    }
+
+    /// Reads the index of the oldest message and the number of stored
+    /// messages, which must lie within the queue, as the memory is shared with
+    /// a partition
+    fn header(&self) -> anyhow::Result<(usize, usize)> {
+        let (first, len) = (self.get(Self::FIRST), self.get(Self::LEN));
+        if first >= self.msg_num || len > self.msg_num {
+            bail!(
+                "corrupted queue: first message {first}, {len} messages, capacity {}",
+                self.msg_num
+            );
+        }
+        Ok((first, len))
+    }
+
+    /// Number of stored messages, clamped to the capacity of the queue
+    fn len(&self) -> usize {
+        std::cmp::min(self.get(Self::LEN), self.msg_num)
+    }
+
+    fn overflow(&self) -> bool {
+        self.get(Self::OVERFLOW) != 0
+    }
+
+    fn set_overflow(&mut self, overflow: bool) {
+        self.set(Self::OVERFLOW, overflow as usize)
+    }
+
+    /// Appends a message to the queue. Returns false if the queue is full.
+    fn push(&mut self, data: &[u8]) -> anyhow::Result<bool> {
+        let (first, len) = self.header()?;
+        if len == self.msg_num {
+            return Ok(false);
+        }
+
+        let offset = self.slot((first + len) % self.msg_num);
+        let data_len = std::cmp::min(data.len(), self.msg_size);
+        self.set(offset, data_len);
+        let start = offset + std::mem::size_of::<u32>();
+        self.mem[start..start + data_len].copy_from_slice(&data[..data_len]);
+
+        self.set(Self::LEN, len + 1);
+        Ok(true)
+    }
+
+    /// Removes the oldest message from the queue and copies it into `buf`.
+    /// Returns the length of the copied message.
+    fn pop(&mut self, buf: &mut [u8]) -> anyhow::Result<Option<usize>> {
+        let (first, len) = self.header()?;
+        if len == 0 {
+            return Ok(None);
+        }
+
+        let offset = self.slot(first);
+        let data_len = std::cmp::min(self.get(offset), std::cmp::min(self.msg_size, buf.len()));
+        let start = offset + std::mem::size_of::<u32>();
+        buf[..data_len].copy_from_slice(&self.mem[start..start + data_len]);
+
+        self.set(Self::FIRST, (first + 1) % self.msg_num);
+        self.set(Self::LEN, len - 1);
+        Ok(Some(data_len))
+    }
+
+    fn clear(&mut self) {
+        self.set(Self::FIRST, 0);
+        self.set(Self::LEN, 0);
+        self.set_overflow(false);
+    }
+}
+
+#[derive(Debug)]
+pub struct Queuing {
+    msg_size: usize,
+    msg_num: usize,
+    source_receiver: MmapMut,
+    source: OwnedFd,
+    source_port: PortConfig,
+    destination_sender: MmapMut,
+    destination: OwnedFd,
+    destination_port: PortConfig,
+    /// Number of messages moved from the source to the destination queue
+    transferred: usize,
+}
+
+impl TryFrom<QueuingChannelConfig> for Queuing {
+    type Error = TypedError;
+
+    fn try_from(config: QueuingChannelConfig) -> TypedResult<Self> {
+        let msg_size = config.msg_size.as_u64() as usize;
+        let msg_num = config.msg_num;
+        if msg_num == 0 {
+            return Err(anyhow!(
+                "Queuing channel {} must hold at least one message",
+                config.source.name()
+            ))
+            .typ(SystemError::Config);
+        }
+
+        let source_port_name = config.source.name();
+        let (source_receiver, source) = Self::queue(
+            format!("queuing_{source_port_name}_source"),
+            msg_size,
+            msg_num,
+        )?;
+        let (destination_sender, destination) = Self::queue(
+            format!("queuing_{source_port_name}_destination"),
+            msg_size,
+            msg_num,
+        )?;
+
+        Ok(Self {
+            msg_size,
+            msg_num,
+            source_receiver,
+            source,
+            source_port: config.source,
+            destination_sender,
+            destination,
+            destination_port: config.destination,
+            transferred: 0,
+        })
+    }
+}
+
+impl Queuing {
+    pub fn constant<T: AsRef<str>>(&self, part: T) -> Option<QueuingConstant> {
+        let (dir, fd, port) = if self.source_port.partition.eq(part.as_ref()) {
+            (
+                PortDirection::Source,
+                self.source_fd().as_raw_fd(),
+                &self.source_port.port,
+            )
+        } else if self.destination_port.partition.eq(part.as_ref()) {
+            (
+                PortDirection::Destination,
+                self.destination_fd().as_raw_fd(),
+                &self.destination_port.port,
+            )
+        } else {
+            return None;
+        };
+
+        Some(QueuingConstant {
+            name: port.clone(),
+            dir,
+            msg_size: self.msg_size,
+            msg_num: self.msg_num,
+            fd,
+        })
+    }
+
+    pub fn name(&self) -> String {
+        format!("{}:{}", &self.source_port.partition, &self.source_port.port)
+    }
+
+    pub fn source_partition(&self) -> &str {
+        &self.source_port.partition
+    }
+
+    fn queue<T: AsRef<str>>(
+        name: T,
+        msg_size: usize,
+        msg_num: usize,
+    ) -> TypedResult<(MmapMut, OwnedFd)> {
+        let mem: Memfd = MemfdOptions::default()
+            .close_on_exec(false)
+            .allow_sealing(true)
+            .create(name)
+            .typ(SystemError::Panic)?;
+        mem.as_file()
+            .set_len(MessageQueue::size(msg_size, msg_num) as u64)
+            .typ(SystemError::Panic)?;
+        mem.add_seals(&[FileSeal::SealShrink, FileSeal::SealGrow, FileSeal::SealSeal])
+            .typ(SystemError::Panic)?;
+
+        let mmap = unsafe { MmapMut::map_mut(mem.as_raw_fd()).typ(SystemError::Panic)? };
+
+        Ok((mmap, mem.into_file().into()))
+    }
+
+    /// Moves messages from the source to the destination queue, until either
+    /// the source is empty or the destination is full. Messages not fitting
+    /// into the destination queue remain in the source queue.
+    ///
+    /// Returns the number of transferred messages. Fails with the name of the
+    /// partition, which corrupted its queue. The corrupted queue is cleared.
+    pub fn swap(&mut self) -> Result<usize, (String, TypedError)> {
+        let mut buf = vec![0; self.msg_size];
+        let mut source = MessageQueue::new(&mut self.source_receiver, self.msg_size, self.msg_num);
+        let mut destination =
+            MessageQueue::new(&mut self.destination_sender, self.msg_size, self.msg_num);
+
+        let mut transferred = 0;
+        let res = loop {
+            match destination.header() {
+                Ok((_, len)) if len == self.msg_num => break Ok(()),
+                Ok(_) => {}
+                Err(e) => {
+                    destination.clear();
+                    destination.set_overflow(true);
+                    break Err((self.destination_port.partition.clone(), e));
+                }
+            }
+            match source.pop(&mut buf) {
+                Ok(Some(len)) => {
+                    // There is room, as checked above
+                    let _ = destination.push(&buf[..len]);
+                    transferred += 1;
+                }
+                Ok(None) => break Ok(()),
+                Err(e) => {
+                    source.clear();
+                    break Err((self.source_port.partition.clone(), e));
+                }
+            }
+        };
+        self.transferred += transferred;
+
+        res.map(|_| transferred)
+            .map_err(|(partition, e)| (partition, TypedError::new(SystemError::Segmentation, e)))
+    }
+
+    /// Returns the number of messages waiting in the source and in the
+    /// destination queue
+    pub fn queued(&mut self) -> (usize, usize) {
+        (
+            MessageQueue::new(&mut self.source_receiver, self.msg_size, self.msg_num).len(),
+            MessageQueue::new(&mut self.destination_sender, self.msg_size, self.msg_num).len(),
+        )
+    }
+
+    /// Returns the number of messages transferred since the channel was
+    /// created
+    pub fn transferred(&self) -> usize {
+        self.transferred
+    }
+
+    pub fn msg_size(&self) -> usize {
+        self.msg_size
+    }
+
+    pub fn msg_num(&self) -> usize {
+        self.msg_num
+    }
+
+    /// Discards all messages of the channel
+    pub fn clear(&mut self) {
+        MessageQueue::new(&mut self.source_receiver, self.msg_size, self.msg_num).clear();
+        MessageQueue::new(&mut self.destination_sender, self.msg_size, self.msg_num).clear();
+    }
+
+    /// Copies all messages of a previous instance of this channel, e.g. after a
+    /// module reset
+    pub fn restore(&mut self, previous: &Queuing) {
+        self.source_receiver
+            .copy_from_slice(previous.source_receiver.as_ref());
+        self.destination_sender
+            .copy_from_slice(previous.destination_sender.as_ref());
+    }
+
+    pub fn source_fd(&self) -> BorrowedFd<'_> {
+        self.source.as_fd()
+    }
+
+    pub fn destination_fd(&self) -> BorrowedFd<'_> {
+        self.destination.as_fd()
+    }
+}
+
+#[derive(Debug)]
+pub struct QueuingSource {
+    mmap: MmapMut,
+    msg_size: usize,
+    msg_num: usize,
+}
+
+impl QueuingSource {
+    fn queue(&mut self) -> MessageQueue<'_> {
+        MessageQueue::new(&mut self.mmap, self.msg_size, self.msg_num)
+    }
+
+    /// Appends a message to the source queue. Returns false if the queue is
+    /// full.
+    pub fn push(&mut self, data: &[u8]) -> TypedResult<bool> {
+        self.queue().push(data).typ(SystemError::Segmentation)
+    }
+
+    pub fn len(&mut self) -> usize {
+        self.queue().len()
+    }
+
+    pub fn is_empty(&mut self) -> bool {
+        self.len() == 0
+    }
+
+    /// Number of messages, which still fit into the source queue
+    pub fn free(&mut self) -> usize {
+        self.msg_num - self.len()
+    }
+}
+
+impl TryFrom<&QueuingConstant> for QueuingSource {
+    type Error = TypedError;
+
+    fn try_from(constant: &QueuingConstant) -> Result<Self, Self::Error> {
+        let mmap = unsafe { MmapMut::map_mut(constant.fd).typ(SystemError::Panic)? };
+
+        Ok(Self {
+            mmap,
+            msg_size: constant.msg_size,
+            msg_num: constant.msg_num,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct QueuingDestination {
+    mmap: MmapMut,
+    msg_size: usize,
+    msg_num: usize,
+}
+
+impl QueuingDestination {
+    fn queue(&mut self) -> MessageQueue<'_> {
+        MessageQueue::new(&mut self.mmap, self.msg_size, self.msg_num)
+    }
+
+    /// Removes the oldest message from the destination queue.
+    ///
+    /// Returns the length of the message and whether messages were discarded
+    /// since the last successful pop. The overflow flag is reset afterwards.
+    pub fn pop(&mut self, data: &mut [u8]) -> TypedResult<Option<(usize, bool)>> {
+        let mut queue = self.queue();
+        let Some(len) = queue.pop(data).typ(SystemError::Segmentation)? else {
+            return Ok(None);
+        };
+        let overflow = queue.overflow();
+        queue.set_overflow(false);
+        Ok(Some((len, overflow)))
+    }
+
+    pub fn len(&mut self) -> usize {
+        self.queue().len()
+    }
+
+    pub fn is_empty(&mut self) -> bool {
+        self.len() == 0
+    }
+
+    /// Discards all messages and resets the overflow flag
+    pub fn clear(&mut self) {
+        self.queue().clear()
+    }
+}
+
+impl TryFrom<&QueuingConstant> for QueuingDestination {
+    type Error = TypedError;
+
+    fn try_from(constant: &QueuingConstant) -> Result<Self, Self::Error> {
+        let mmap = unsafe { MmapMut::map_mut(constant.fd).typ(SystemError::Panic)? };
+
+        Ok(Self {
+            mmap,
+            msg_size: constant.msg_size,
+            msg_num: constant.msg_num,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytesize::ByteSize;
+
+    use super::*;
+
+    fn port(partition: &str, port: &str) -> PortConfig {
+        PortConfig {
+            partition: partition.to_string(),
+            port: port.to_string(),
+        }
+    }
+
+    #[test]
+    fn message_queue() {
+        let mut mem = vec![0; MessageQueue::size(4, 3)];
+        let mut queue = MessageQueue::new(&mut mem, 4, 3);
+        let mut buf = [0; 4];
+
+        assert_eq!(queue.pop(&mut buf).unwrap(), None);
+        assert!(queue.push(&[1]).unwrap());
+        assert!(queue.push(&[2, 2]).unwrap());
+        assert!(queue.push(&[3, 3, 3, 3, 3]).unwrap());
+        assert!(!queue.push(&[4]).unwrap());
+        assert_eq!(queue.len(), 3);
+
+        assert_eq!(queue.pop(&mut buf).unwrap(), Some(1));
+        assert_eq!(buf[..1], [1]);
+        // Wrap around the end of the ring buffer
+        assert!(queue.push(&[4]).unwrap());
+        assert_eq!(queue.pop(&mut buf).unwrap(), Some(2));
+        assert_eq!(buf[..2], [2, 2]);
+        assert_eq!(queue.pop(&mut buf).unwrap(), Some(4));
+        assert_eq!(buf, [3, 3, 3, 3]);
+        assert_eq!(queue.pop(&mut buf).unwrap(), Some(1));
+        assert_eq!(buf[..1], [4]);
+        assert_eq!(queue.pop(&mut buf).unwrap(), None);
+
+        queue.push(&[5]).unwrap();
+        queue.set_overflow(true);
+        queue.clear();
+        assert_eq!(queue.len(), 0);
+        assert!(!queue.overflow());
+    }
+
+    #[test]
+    fn corrupted_queue() {
+        let mut mem = vec![0; MessageQueue::size(4, 3)];
+        let mut queue = MessageQueue::new(&mut mem, 4, 3);
+        let mut buf = [0; 4];
+
+        queue.set(MessageQueue::FIRST, 3);
+        queue.set(MessageQueue::LEN, 1);
+        assert!(queue.pop(&mut buf).is_err());
+        assert!(queue.push(&[1]).is_err());
+
+        queue.set(MessageQueue::FIRST, 0);
+        queue.set(MessageQueue::LEN, u32::MAX as usize);
+        assert!(queue.pop(&mut buf).is_err());
+        assert!(queue.push(&[1]).is_err());
+        assert_eq!(queue.len(), 3);
+    }
+
+    #[test]
+    fn queuing_channel() {
+        let mut channel = Queuing::try_from(QueuingChannelConfig {
+            msg_size: ByteSize::b(8),
+            msg_num: 2,
+            source: port("Foo", "Send"),
+            destination: port("Bar", "Receive"),
+        })
+        .unwrap();
+
+        let source = channel.constant("Foo").unwrap();
+        assert_eq!(source.dir, PortDirection::Source);
+        assert_eq!(source.name, "Send");
+        let destination = channel.constant("Bar").unwrap();
+        assert_eq!(destination.dir, PortDirection::Destination);
+        assert!(channel.constant("Baz").is_none());
+
+        let mut source = QueuingSource::try_from(&source).unwrap();
+        let mut destination = QueuingDestination::try_from(&destination).unwrap();
+        let mut buf = [0; 8];
+
+        assert!(source.push(b"first").unwrap());
+        assert_eq!(source.free(), 1);
+        assert!(source.push(b"second").unwrap());
+        assert!(!source.push(b"third").unwrap());
+        assert_eq!(source.free(), 0);
+        assert_eq!(channel.swap().unwrap(), 2);
+        assert!(source.is_empty());
+
+        // Messages remain in the source queue, while the destination is full
+        assert!(source.push(b"third").unwrap());
+        assert_eq!(channel.swap().unwrap(), 0);
+        assert_eq!(destination.len(), 2);
+        assert_eq!(channel.queued(), (1, 2));
+        assert_eq!(channel.transferred(), 2);
+
+        assert_eq!(destination.pop(&mut buf).unwrap(), Some((5, false)));
+        assert_eq!(&buf[..5], b"first");
+        assert_eq!(channel.swap().unwrap(), 1);
+        assert_eq!(destination.pop(&mut buf).unwrap(), Some((6, false)));
+        assert_eq!(&buf[..6], b"second");
+        assert_eq!(destination.pop(&mut buf).unwrap(), Some((5, false)));
+        assert_eq!(&buf[..5], b"third");
+        assert_eq!(destination.pop(&mut buf).unwrap(), None);
+    }
+
+    #[test]
+    fn corrupted_channel() {
+        let mut channel = Queuing::try_from(QueuingChannelConfig {
+            msg_size: ByteSize::b(8),
+            msg_num: 2,
+            source: port("Foo", "Send"),
+            destination: port("Bar", "Receive"),
+        })
+        .unwrap();
+        let mut source = QueuingSource::try_from(&channel.constant("Foo").unwrap()).unwrap();
+        let mut destination =
+            QueuingDestination::try_from(&channel.constant("Bar").unwrap()).unwrap();
+        let mut buf = [0; 8];
+
+        source.queue().set(MessageQueue::FIRST, 7);
+        let (partition, err) = channel.swap().unwrap_err();
+        assert_eq!(partition, "Foo");
+        assert!(matches!(err.err(), SystemError::Segmentation));
+        // The corrupted queue is cleared
+        assert!(source.push(b"first").unwrap());
+
+        destination.queue().set(MessageQueue::LEN, 3);
+        let (partition, _) = channel.swap().unwrap_err();
+        assert_eq!(partition, "Bar");
+        assert_eq!(channel.swap().unwrap(), 1);
+        // Messages discarded while clearing are reported as an overflow
+        assert_eq!(destination.pop(&mut buf).unwrap(), Some((5, true)));
+    }
+
+    #[test]
+    fn restore() {
+        let config = QueuingChannelConfig {
+            msg_size: ByteSize::b(8),
+            msg_num: 2,
+            source: port("Foo", "Send"),
+            destination: port("Bar", "Receive"),
+        };
+        let mut previous = Queuing::try_from(config.clone()).unwrap();
+        let mut source = QueuingSource::try_from(&previous.constant("Foo").unwrap()).unwrap();
+        assert!(source.push(b"first").unwrap());
+        assert_eq!(previous.swap().unwrap(), 1);
+        assert!(source.push(b"second").unwrap());
+
+        let mut channel = Queuing::try_from(config).unwrap();
+        channel.restore(&previous);
+        drop(previous);
+
+        let mut destination =
+            QueuingDestination::try_from(&channel.constant("Bar").unwrap()).unwrap();
+        let mut buf = [0; 8];
+        assert_eq!(destination.pop(&mut buf).unwrap(), Some((5, false)));
+        assert_eq!(&buf[..5], b"first");
+        assert_eq!(channel.swap().unwrap(), 1);
+        assert_eq!(destination.pop(&mut buf).unwrap(), Some((6, false)));
+        assert_eq!(&buf[..6], b"second");
+    }
+}
@@ -1,6 +1,8 @@
 use std::collections::HashSet;
 use std::convert::AsRef;
+use std::fs::File;
 use std::os::fd::{AsFd, BorrowedFd};
+use std::os::unix::fs::FileExt;
 use std::os::unix::prelude::{AsRawFd, OwnedFd, RawFd};
 use std::time::Instant;
 
@@ -76,6 +78,8 @@ pub struct Sampling {
     destination_sender: MmapMut,
     destination: OwnedFd,
     destination_ports: HashSet<PortConfig>,
+    /// Number of messages copied from the source to the destination
+    transferred: usize,
 }
 
 impl TryFrom<SamplingChannelConfig> for Sampling {
@@ -98,6 +102,7 @@ impl TryFrom<SamplingChannelConfig> for Sampling {
             destination,
             destination_sender,
             destination_ports: config.destination,
+            transferred: 0,
         })
     }
 }
@@ -183,9 +188,20 @@ impl Sampling {
         self.last = read.copied;
 
         Datagram::write(&mut self.destination_sender, read.data);
+        self.transferred += 1;
         true
     }
 
+    /// Returns the number of messages transferred since the channel was
+    /// created
+    pub fn transferred(&self) -> usize {
+        self.transferred
+    }
+
+    pub fn msg_size(&self) -> usize {
+        self.msg_size
+    }
+
     pub fn replace_source(&mut self) -> TypedResult<()> {
         let (source_receiver, source) = Self::source(
             format!("sampling_{}_source", self.source_port.port),
@@ -198,6 +214,20 @@ impl Sampling {
         Ok(())
     }
 
+    /// Copies the last messages of a previous instance of this channel, e.g.
+    /// after a module reset
+    pub fn restore(&mut self, previous: &Sampling) -> TypedResult<()> {
+        // The source is only mapped read-only by the hypervisor
+        File::from(self.source.try_clone().typ(SystemError::Panic)?)
+            .write_all_at(previous.source_receiver.as_ref(), 0)
+            .typ(SystemError::Panic)?;
+        self.destination_sender
+            .copy_from_slice(previous.destination_sender.as_ref());
+        self.last = previous.last;
+
+        Ok(())
+    }
+
     pub fn source_fd(&self) -> BorrowedFd {
         self.source.as_fd()
     }
@@ -1,13 +1,14 @@
 //! Common definitions for the execution of system calls
 
-use anyhow::{anyhow, Result};
+use a653rs::bindings::{Deadline, ErrorCode, ErrorReturnCode, ProcessState, QueuingDiscipline};
+use anyhow::{anyhow, bail, Result};
 use byteorder::{NativeEndian, ReadBytesExt, WriteBytesExt};
 use enum_primitive::FromPrimitive;
 
 pub const SYSCALL_SOCKET_PATH: &str = "/syscall-a653";
 
 enum_from_primitive! {
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum ApexSyscall {
     /// P1-5 3.2.2.1 - GET_PARTITION_STATUS
     GetPartitionStatus = 6530,
@@ -148,19 +149,19 @@ pub enum ApexSyscall {
     /////////////////////
     //* 10 Free Spaces */
     /////////////////////
-    /// P1-5 3.7.2.1.1 - CREATE_MUTEX
+    /// P1-5 3.7.2.5.1 - CREATE_MUTEX
     CreateMutex = 6676,
-    /// P1-5 3.7.2.1.2 - ACQUIRE_MUTEX
+    /// P1-5 3.7.2.5.2 - ACQUIRE_MUTEX
     AcquireMutex = 6677,
-    /// P1-5 3.7.2.1.3 - RELEASE_MUTEX
+    /// P1-5 3.7.2.5.3 - RELEASE_MUTEX
     ReleaseMutex = 6678,
-    /// P1-5 3.7.2.1.4 - RESET_MUTEX
+    /// P1-5 3.7.2.5.4 - RESET_MUTEX
     ResetMutex = 6679,
-    /// P1-5 3.7.2.1.5 - GET_MUTEX_ID
+    /// P1-5 3.7.2.5.5 - GET_MUTEX_ID
     GetMutexId = 6680,
-    /// P1-5 3.7.2.1.6 - GET_MUTEX_STATUS
+    /// P1-5 3.7.2.5.6 - GET_MUTEX_STATUS
     GetMutexStatus = 6681,
-    /// P1-5 3.7.2.1.7 - GET_PROCIESS_MUTEX_STATE
+    /// P1-5 3.7.2.5.7 - GET_PROCESS_MUTEX_STATE
     GetProciessMutexState = 6682,
     /////////////////////
     //* 10 Free Spaces */
@@ -175,19 +176,151 @@ pub enum ApexSyscall {
     RaiseApplicationError = 6696,
     /// P1-5 3.8.2.5 - CONFIGURE_ERROR_HANDLER
     ConfigureErrorHandler = 6697,
+    /////////////////////
+    //* 10 Free Spaces */
+    /////////////////////
+    /// P2-4 3.7.2.1 - SET_MODULE_SCHEDULE
+    SetModuleSchedule = 6708,
+    /// P2-4 3.7.2.2 - GET_MODULE_SCHEDULE_STATUS
+    GetModuleScheduleStatus = 6709,
+    /// P2-4 3.7.2.3 - GET_MODULE_SCHEDULE_ID
+    GetModuleScheduleId = 6710,
 }
 }
 
+/// Status of a successfully executed system call
+pub const SYSCALL_SUCCESS: u64 = 0;
+
 #[derive(Debug, PartialEq)]
 pub struct SyscallRequest {
     pub id: ApexSyscall,
     pub params: Vec<u64>,
+    /// Variable-sized input of the system call, such as a message
+    pub data: Vec<u8>,
 }
 
 #[derive(Debug, PartialEq)]
 pub struct SyscallResponse {
     pub id: ApexSyscall,
     pub status: u64,
+    /// Scalar output parameters of the system call
+    pub values: Vec<u64>,
+    /// Variable-sized output of the system call, such as a message
+    pub data: Vec<u8>,
+}
+
+/// Converts an [ErrorReturnCode] into the status of a [SyscallResponse]
+pub fn status_from_error(err: ErrorReturnCode) -> u64 {
+    match err {
+        ErrorReturnCode::NoAction => 1,
+        ErrorReturnCode::NotAvailable => 2,
+        ErrorReturnCode::InvalidParam => 3,
+        ErrorReturnCode::InvalidConfig => 4,
+        ErrorReturnCode::InvalidMode => 5,
+        ErrorReturnCode::TimedOut => 6,
+    }
+}
+
+/// Converts the status of a [SyscallResponse] back into an [ErrorReturnCode]
+///
+/// Returns `Ok(None)` if the status indicates success.
+pub fn error_from_status(status: u64) -> Result<Option<ErrorReturnCode>> {
+    Ok(Some(match status {
+        SYSCALL_SUCCESS => return Ok(None),
+        1 => ErrorReturnCode::NoAction,
+        2 => ErrorReturnCode::NotAvailable,
+        3 => ErrorReturnCode::InvalidParam,
+        4 => ErrorReturnCode::InvalidConfig,
+        5 => ErrorReturnCode::InvalidMode,
+        6 => ErrorReturnCode::TimedOut,
+        _ => bail!("unknown system call status {status}"),
+    }))
+}
+
+/// Converts a [ProcessState] into a system call value
+pub fn process_state_to_u64(state: ProcessState) -> u64 {
+    match state {
+        ProcessState::Dormant => 0,
+        ProcessState::Ready => 1,
+        ProcessState::Running => 2,
+        ProcessState::Waiting => 3,
+        ProcessState::Faulted => 4,
+    }
+}
+
+/// Converts a system call value back into a [ProcessState]
+pub fn process_state_from_u64(value: u64) -> Result<ProcessState> {
+    Ok(match value {
+        0 => ProcessState::Dormant,
+        1 => ProcessState::Ready,
+        2 => ProcessState::Running,
+        3 => ProcessState::Waiting,
+        4 => ProcessState::Faulted,
+        _ => bail!("unknown process state {value}"),
+    })
+}
+
+/// Converts a [Deadline] into a system call value
+pub fn deadline_to_u64(deadline: Deadline) -> u64 {
+    match deadline {
+        Deadline::Soft => 0,
+        Deadline::Hard => 1,
+    }
+}
+
+/// Converts a system call value back into a [Deadline]
+pub fn deadline_from_u64(value: u64) -> Result<Deadline> {
+    Ok(match value {
+        0 => Deadline::Soft,
+        1 => Deadline::Hard,
+        _ => bail!("unknown deadline {value}"),
+    })
+}
+
+/// Converts a [QueuingDiscipline] into a system call value
+pub fn queuing_discipline_to_u64(discipline: QueuingDiscipline) -> u64 {
+    match discipline {
+        QueuingDiscipline::Fifo => 0,
+        QueuingDiscipline::Priority => 1,
+    }
+}
+
+/// Converts a system call value back into a [QueuingDiscipline]
+pub fn queuing_discipline_from_u64(value: u64) -> Result<QueuingDiscipline> {
+    Ok(match value {
+        0 => QueuingDiscipline::Fifo,
+        1 => QueuingDiscipline::Priority,
+        _ => bail!("unknown queuing discipline {value}"),
+    })
+}
+
+/// Converts an [ErrorCode] into a system call value
+pub fn error_code_to_u64(code: ErrorCode) -> u64 {
+    match code {
+        ErrorCode::DeadlineMissed => 0,
+        ErrorCode::ApplicationError => 1,
+        ErrorCode::NumericError => 2,
+        ErrorCode::IllegalRequest => 3,
+        ErrorCode::StackOverflow => 4,
+        ErrorCode::MemoryViolation => 5,
+        ErrorCode::HardwareFault => 6,
+        ErrorCode::PowerFail => 7,
+    }
+}
+
+/// Converts a system call value back into an [ErrorCode]
+pub fn error_code_from_u64(value: u64) -> Result<ErrorCode> {
+    Ok(match value {
+        0 => ErrorCode::DeadlineMissed,
+        1 => ErrorCode::ApplicationError,
+        2 => ErrorCode::NumericError,
+        3 => ErrorCode::IllegalRequest,
+        4 => ErrorCode::StackOverflow,
+        5 => ErrorCode::MemoryViolation,
+        6 => ErrorCode::HardwareFault,
+        7 => ErrorCode::PowerFail,
+        _ => bail!("unknown error code {value}"),
+    })
 }
 
 impl SyscallRequest {
@@ -198,6 +331,7 @@ impl SyscallRequest {
     /// id [u64]
     /// nparams [u8]
     /// params [u64 * nparams]
+    /// data [u8 * remaining bytes]
     /// ```
     ///
     /// All integers are encoded in native endian.
@@ -208,6 +342,7 @@ impl SyscallRequest {
         for &param in &self.params {
             serialized.write_u64::<NativeEndian>(param)?;
         }
+        serialized.extend_from_slice(&self.data);
 
         Ok(serialized)
     }
@@ -226,7 +361,11 @@ impl SyscallRequest {
             params.push(serialized.read_u64::<NativeEndian>()?);
         }
 
-        Ok(SyscallRequest { id, params })
+        Ok(SyscallRequest {
+            id,
+            params,
+            data: serialized.to_vec(),
+        })
     }
 }
 
@@ -237,13 +376,28 @@ impl SyscallResponse {
     /// ```text
     /// id [u64]
     /// status [u64]
+    /// nvalues [u8]
+    /// values [u64 * nvalues]
+    /// data [u8 * remaining bytes]
     /// ```
     ///
+    /// The fields following the status are omitted, if the response carries
+    /// neither values nor data.
+    ///
     /// All integers are encoded in native endian.
     pub fn serialize(&self) -> Result<Vec<u8>> {
         let mut serialized: Vec<u8> = Vec::new();
         serialized.write_u64::<NativeEndian>(self.id as u64)?;
         serialized.write_u64::<NativeEndian>(self.status)?;
+        if self.values.is_empty() && self.data.is_empty() {
+            return Ok(serialized);
+        }
+
+        serialized.write_u8(self.values.len().try_into()?)?;
+        for &value in &self.values {
+            serialized.write_u64::<NativeEndian>(value)?;
+        }
+        serialized.extend_from_slice(&self.data);
 
         Ok(serialized)
     }
@@ -256,8 +410,40 @@ impl SyscallResponse {
         let id = ApexSyscall::from_u64(serialized.read_u64::<NativeEndian>()?)
             .ok_or(anyhow!("deserialization of ApexSyscall failed"))?;
         let status = serialized.read_u64::<NativeEndian>()?;
+        if serialized.is_empty() {
+            return Ok(SyscallResponse::new(id, status));
+        }
+
+        let nvalues = serialized.read_u8()?;
+        let mut values: Vec<u64> = Vec::with_capacity(nvalues as usize);
+        for _ in 0..nvalues {
+            values.push(serialized.read_u64::<NativeEndian>()?);
+        }
+
+        Ok(SyscallResponse {
+            id,
+            status,
+            values,
+            data: serialized.to_vec(),
+        })
+    }
+
+    /// Creates a response without any output parameters
+    pub fn new(id: ApexSyscall, status: u64) -> Self {
+        Self {
+            id,
+            status,
+            values: Vec::new(),
+            data: Vec::new(),
+        }
+    }
 
-        Ok(SyscallResponse { id, status })
+    /// Converts the status of the response into a [Result]
+    pub fn result(&self) -> Result<Result<(), ErrorReturnCode>> {
+        Ok(match error_from_status(self.status)? {
+            None => Ok(()),
+            Some(err) => Err(err),
+        })
     }
 }
 
@@ -270,6 +456,7 @@ mod tests {
         let request = SyscallRequest {
             id: ApexSyscall::Start,
             params: vec![1, 2, 3],
+            data: vec![],
         };
         let serialized = request.serialize().unwrap();
         let mut serialized: &[u8] = &serialized;
@@ -291,10 +478,7 @@ mod tests {
 
     #[test]
     fn test_serialize_response() {
-        let response = SyscallResponse {
-            id: ApexSyscall::Start,
-            status: 42,
-        };
+        let response = SyscallResponse::new(ApexSyscall::Start, 42);
         let serialized = response.serialize().unwrap();
         let mut serialized: &[u8] = &serialized;
 
@@ -311,6 +495,7 @@ mod tests {
         let request = SyscallRequest {
             id: ApexSyscall::Start,
             params: vec![1, 2, 3],
+            data: vec![],
         };
         let serialized = request.serialize().unwrap();
         let deserialized = SyscallRequest::deserialize(&serialized).unwrap();
@@ -320,13 +505,107 @@ mod tests {
 
     #[test]
     fn test_deserialize_response() {
-        let response = SyscallResponse {
-            id: ApexSyscall::Start,
-            status: 42,
-        };
+        let response = SyscallResponse::new(ApexSyscall::Start, 42);
         let serialized = response.serialize().unwrap();
         let deserialized = SyscallResponse::deserialize(&serialized).unwrap();
         assert_eq!(response, deserialized);
         assert!(!serialized.is_empty());
     }
+
+    #[test]
+    fn test_payload() {
+        let request = SyscallRequest {
+            id: ApexSyscall::ReportApplicationMessage,
+            params: vec![7],
+            data: b"Hello".to_vec(),
+        };
+        let serialized = request.serialize().unwrap();
+        assert_eq!(SyscallRequest::deserialize(&serialized).unwrap(), request);
+
+        let response = SyscallResponse {
+            id: ApexSyscall::GetPartitionStatus,
+            status: SYSCALL_SUCCESS,
+            values: vec![1, 2],
+            data: b"World".to_vec(),
+        };
+        let serialized = response.serialize().unwrap();
+        assert_eq!(SyscallResponse::deserialize(&serialized).unwrap(), response);
+
+        // Data without values must survive the roundtrip as well
+        let response = SyscallResponse {
+            values: vec![],
+            ..response
+        };
+        let serialized = response.serialize().unwrap();
+        assert_eq!(SyscallResponse::deserialize(&serialized).unwrap(), response);
+    }
+
+    #[test]
+    fn test_status() {
+        for err in [
+            ErrorReturnCode::NoAction,
+            ErrorReturnCode::NotAvailable,
+            ErrorReturnCode::InvalidParam,
+            ErrorReturnCode::InvalidConfig,
+            ErrorReturnCode::InvalidMode,
+            ErrorReturnCode::TimedOut,
+        ] {
+            let response = SyscallResponse::new(ApexSyscall::Start, status_from_error(err));
+            assert_eq!(response.result().unwrap(), Err(err));
+        }
+
+        let response = SyscallResponse::new(ApexSyscall::Start, SYSCALL_SUCCESS);
+        assert_eq!(response.result().unwrap(), Ok(()));
+        assert!(SyscallResponse::new(ApexSyscall::Start, 42)
+            .result()
+            .is_err());
+    }
+
+    #[test]
+    fn test_process_values() {
+        for state in [
+            ProcessState::Dormant,
+            ProcessState::Ready,
+            ProcessState::Running,
+            ProcessState::Waiting,
+            ProcessState::Faulted,
+        ] {
+            let value = process_state_to_u64(state);
+            assert_eq!(process_state_from_u64(value).unwrap(), state);
+        }
+        assert!(process_state_from_u64(5).is_err());
+
+        for deadline in [Deadline::Soft, Deadline::Hard] {
+            assert_eq!(
+                deadline_from_u64(deadline_to_u64(deadline)).unwrap(),
+                deadline
+            );
+        }
+        assert!(deadline_from_u64(2).is_err());
+
+        for discipline in [QueuingDiscipline::Fifo, QueuingDiscipline::Priority] {
+            assert_eq!(
+                queuing_discipline_from_u64(queuing_discipline_to_u64(discipline)).unwrap(),
+                discipline
+            );
+        }
+        assert!(queuing_discipline_from_u64(2).is_err());
+    }
+
+    #[test]
+    fn test_error_code() {
+        for code in [
+            ErrorCode::DeadlineMissed,
+            ErrorCode::ApplicationError,
+            ErrorCode::NumericError,
+            ErrorCode::IllegalRequest,
+            ErrorCode::StackOverflow,
+            ErrorCode::MemoryViolation,
+            ErrorCode::HardwareFault,
+            ErrorCode::PowerFail,
+        ] {
+            assert_eq!(error_code_from_u64(error_code_to_u64(code)).unwrap(), code);
+        }
+        assert!(error_code_from_u64(8).is_err());
+    }
 }
@@ -0,0 +1,226 @@
+//! Companion CLI for commanding a running hypervisor through its control
+//! socket
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use clap::{Parser, Subcommand};
+use serde::Serialize;
+
+use a653rs_linux_core::error::TypedResult;
+use a653rs_linux_hypervisor::hypervisor::control::{
+    self, ChannelKind, ChannelStatus, LogLine, ModuleStatus, PartitionAction, PartitionStatus,
+    Request, Response,
+};
+
+/// Command a running hypervisor
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Args {
+    /// Control socket of the hypervisor, as passed to its `--control` option
+    #[clap(short, long)]
+    socket: PathBuf,
+
+    /// Print JSON instead of human-readable output
+    #[clap(long, global = true)]
+    json: bool,
+
+    #[clap(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Show the schedule, partitions and channels
+    Status,
+    /// Show the partitions with their processes
+    Partitions,
+    /// Show the channels with their number of messages
+    Channels,
+    /// Restart a partition
+    Restart {
+        partition: String,
+        /// Warm start instead of cold start the partition
+        #[clap(long)]
+        warm: bool,
+    },
+    /// Put a partition into the idle mode
+    Idle { partition: String },
+    /// Pause the scheduling of partitions after the current major frame
+    Pause,
+    /// Resume the scheduling of partitions with the next major frame
+    Resume,
+    /// Shut the hypervisor down after the current major frame
+    Shutdown,
+    /// Follow the log messages of a partition
+    TailLog { partition: String },
+}
+
+fn main() {
+    let args = Args::parse();
+    if let Err(e) = run(&args) {
+        eprintln!("error: {e}");
+        std::process::exit(1);
+    }
+}
+
+fn run(args: &Args) -> TypedResult<()> {
+    let request = match &args.command {
+        Command::Status | Command::Partitions | Command::Channels => Request::Status,
+        Command::Restart { partition, warm } => Request::Partition {
+            name: partition.clone(),
+            action: if *warm {
+                PartitionAction::WarmStart
+            } else {
+                PartitionAction::ColdStart
+            },
+        },
+        Command::Idle { partition } => Request::Partition {
+            name: partition.clone(),
+            action: PartitionAction::Idle,
+        },
+        Command::Pause => Request::Pause,
+        Command::Resume => Request::Resume,
+        Command::Shutdown => Request::Shutdown,
+        Command::TailLog { partition } => return tail_log(&args.socket, partition, args.json),
+    };
+
+    let status = match control::send(&args.socket, &request)? {
+        Response::Error(e) => {
+            eprintln!("error: {e}");
+            std::process::exit(1);
+        }
+        Response::Ok => {
+            if args.json {
+                print_json(&Response::Ok);
+            } else {
+                println!("ok");
+            }
+            return Ok(());
+        }
+        Response::Status(status) => status,
+    };
+
+    match (&args.command, args.json) {
+        (Command::Partitions, true) => print_json(&status.partitions),
+        (Command::Partitions, false) => print!("{}", partitions(&status.partitions)),
+        (Command::Channels, true) => print_json(&status.channels),
+        (Command::Channels, false) => print!("{}", channels(&status.channels)),
+        (_, true) => print_json(&status),
+        (_, false) => print!("{}", module(&status)),
+    }
+    Ok(())
+}
+
+fn tail_log(socket: &Path, partition: &str, json: bool) -> TypedResult<()> {
+    control::tail_log(socket, partition, |line: LogLine| {
+        if json {
+            println!("{}", serde_json::to_string(&line).unwrap_or_default());
+        } else {
+            println!("{:<5} {}", line.level, line.message);
+        }
+    })
+}
+
+fn print_json<T: Serialize>(value: &T) {
+    match serde_json::to_string_pretty(value) {
+        Ok(json) => println!("{json}"),
+        Err(e) => eprintln!("error: {e}"),
+    }
+}
+
+/// Rounds `duration` to milliseconds for display
+fn millis(duration: Duration) -> humantime::FormattedDuration {
+    humantime::format_duration(Duration::from_millis(duration.as_millis() as u64))
+}
+
+fn module(status: &ModuleStatus) -> String {
+    let schedule = &status.schedule;
+    let mut out = format!(
+        "schedule:  {} (next: {}), major frame {}\n\
+         frames:    {} in {}{}\n\n",
+        schedule.current,
+        schedule.next,
+        millis(schedule.major_frame),
+        schedule.major_frames,
+        millis(schedule.uptime),
+        if status.paused { ", paused" } else { "" },
+    );
+    out += &partitions(&status.partitions);
+    out.push('\n');
+    out += &channels(&status.channels);
+    out
+}
+
+fn partitions(partitions: &[PartitionStatus]) -> String {
+    let rows = partitions
+        .iter()
+        .flat_map(|p| {
+            let partition = vec![
+                p.id.to_string(),
+                p.name.clone(),
+                p.mode.clone(),
+                p.pid.to_string(),
+                p.cgroup.display().to_string(),
+            ];
+            let processes = p.processes.iter().map(|process| {
+                vec![
+                    String::new(),
+                    format!("  {}:{}", process.id, process.name),
+                    process.state.clone(),
+                    String::new(),
+                    String::new(),
+                ]
+            });
+            std::iter::once(partition).chain(processes)
+        })
+        .collect();
+    table(&["ID", "NAME", "MODE", "PID", "CGROUP"], rows)
+}
+
+fn channels(channels: &[ChannelStatus]) -> String {
+    let rows = channels
+        .iter()
+        .map(|c| {
+            let (kind, queued) = match (c.kind, &c.queue) {
+                (ChannelKind::Queuing, Some(q)) => (
+                    "queuing",
+                    format!("{} + {} of {}", q.source, q.destination, q.msg_num),
+                ),
+                (ChannelKind::Queuing, None) => ("queuing", String::new()),
+                (ChannelKind::Sampling, _) => ("sampling", "-".to_string()),
+            };
+            vec![
+                c.name.clone(),
+                kind.to_string(),
+                c.msg_size.to_string(),
+                c.transferred.to_string(),
+                queued,
+            ]
+        })
+        .collect();
+    table(&["NAME", "KIND", "MSG SIZE", "TRANSFERRED", "QUEUED"], rows)
+}
+
+/// Aligns `rows` in columns below `header`
+fn table(header: &[&str], rows: Vec<Vec<String>>) -> String {
+    let mut widths = header.iter().map(|h| h.len()).collect::<Vec<_>>();
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.len());
+        }
+    }
+
+    let header = header.iter().map(|h| h.to_string()).collect();
+    std::iter::once(header)
+        .chain(rows)
+        .map(|row: Vec<String>| {
+            let line = row
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{cell:<width$}"))
+                .collect::<Vec<_>>()
+                .join("  ");
+            line.trim_end().to_string() + "\n"
+        })
+        .collect()
+}
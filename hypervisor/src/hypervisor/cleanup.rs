@@ -0,0 +1,164 @@
+//! Removal of cgroups and working directories left behind by hypervisors,
+//! which did not shut down properly, e.g. because they were killed by SIGKILL
+//!
+//! The root cgroup of a hypervisor is named `<cgroup>-<pid>` and the working
+//! directories of its partitions [WORKING_DIR_PREFIX]`<pid>-*`. Leftovers are
+//! only removed once the owning process is dead, hence hypervisors running
+//! concurrently are left alone.
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::anyhow;
+use nix::errno::Errno;
+use nix::sys::signal::kill;
+use nix::unistd::{getpid, Pid};
+
+use a653rs_linux_core::cgroup::CGroup;
+use a653rs_linux_core::error::{ResultExt, SystemError, TypedResult};
+
+/// Prefix of the working directories of partitions in the temporary
+/// directory, followed by the PID of the hypervisor
+pub const WORKING_DIR_PREFIX: &str = "a653rs-linux-hypervisor-";
+
+/// Leftovers found by [clean]
+#[derive(Debug, Default)]
+pub struct Cleaned {
+    pub cgroups: Vec<PathBuf>,
+    pub working_dirs: Vec<PathBuf>,
+    /// Leftovers, which could not be removed
+    pub failed: Vec<PathBuf>,
+}
+
+/// Removes the cgroups and working directories of dead hypervisors
+///
+/// `cgroup` is the cgroup a hypervisor is configured with, next to which the
+/// root cgroups of the hypervisors are created.
+pub fn clean(cgroup: &Path) -> TypedResult<Cleaned> {
+    let (Some(parent), Some(name)) = (cgroup.parent(), cgroup.file_name()) else {
+        return Err(anyhow!("invalid hypervisor cgroup {cgroup:?}")).typ(SystemError::CGroup);
+    };
+    let prefix = format!("{}-", name.to_string_lossy());
+    let mut cleaned = Cleaned::default();
+
+    for path in stale(parent, &prefix).typ(SystemError::CGroup)? {
+        info!("Removing stale cgroup {path:?}");
+        match remove_cgroup(&path) {
+            Ok(()) => cleaned.cgroups.push(path),
+            Err(e) => {
+                warn!("Failed to remove stale cgroup {path:?}: {e}");
+                cleaned.failed.push(path);
+            }
+        }
+    }
+
+    for path in stale(&std::env::temp_dir(), WORKING_DIR_PREFIX).typ(SystemError::Panic)? {
+        info!("Removing stale working directory {path:?}");
+        match fs::remove_dir_all(&path) {
+            Ok(()) => cleaned.working_dirs.push(path),
+            Err(e) => {
+                warn!("Failed to remove stale working directory {path:?}: {e}");
+                cleaned.failed.push(path);
+            }
+        }
+    }
+
+    Ok(cleaned)
+}
+
+/// Kills all processes of a cgroup, thaws and removes it
+fn remove_cgroup(path: &Path) -> anyhow::Result<()> {
+    let cg = CGroup::import_root(path)?;
+    // Frozen processes are killed as well
+    cg.kill()?;
+    cg.unfreeze()?;
+    cg.rm()
+}
+
+/// Returns the directories in `dir` named `<prefix><pid>` or
+/// `<prefix><pid>-*`, whose owning process is dead
+fn stale(dir: &Path, prefix: &str) -> std::io::Result<Vec<PathBuf>> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut stale = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        if !entry.file_type()?.is_dir() {
+            continue;
+        }
+        let name = entry.file_name();
+        match owner(&name.to_string_lossy(), prefix) {
+            Some(pid) if !is_alive(pid) => stale.push(entry.path()),
+            _ => {}
+        }
+    }
+    stale.sort();
+    Ok(stale)
+}
+
+/// Returns the PID following `prefix` in `name`
+fn owner(name: &str, prefix: &str) -> Option<Pid> {
+    let rest = name.strip_prefix(prefix)?;
+    let pid = rest.split_once('-').map_or(rest, |(pid, _)| pid);
+    if pid.is_empty() || !pid.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    pid.parse().ok().map(Pid::from_raw)
+}
+
+fn is_alive(pid: Pid) -> bool {
+    // Processes of other users are alive, even though they may not be
+    // signaled
+    pid == getpid() || kill(pid, None) != Err(Errno::ESRCH)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::process::Command;
+
+    use super::*;
+
+    #[test]
+    fn owners() {
+        let prefix = "linux-hypervisor-";
+        assert_eq!(
+            owner("linux-hypervisor-42", prefix),
+            Some(Pid::from_raw(42))
+        );
+        assert_eq!(
+            owner("linux-hypervisor-42-a1b2c3", prefix),
+            Some(Pid::from_raw(42))
+        );
+        assert_eq!(owner("linux-hypervisor-", prefix), None);
+        assert_eq!(owner("linux-hypervisor-abc", prefix), None);
+        assert_eq!(owner("linux-hypervisor-+42", prefix), None);
+        assert_eq!(owner("other-42", prefix), None);
+    }
+
+    #[test]
+    fn stale_dirs() {
+        let mut child = Command::new("true").spawn().unwrap();
+        let dead = child.id();
+        child.wait().unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let own = dir
+            .path()
+            .join(format!("{WORKING_DIR_PREFIX}{}-abc", getpid()));
+        let stale_dir = dir.path().join(format!("{WORKING_DIR_PREFIX}{dead}-abc"));
+        let unrelated = dir.path().join(format!("unrelated-{dead}"));
+        for d in [&own, &stale_dir, &unrelated] {
+            fs::create_dir(d).unwrap();
+        }
+        // Files are never considered
+        fs::write(dir.path().join(format!("{WORKING_DIR_PREFIX}{dead}")), "").unwrap();
+
+        assert_eq!(
+            stale(dir.path(), WORKING_DIR_PREFIX).unwrap(),
+            vec![stale_dir]
+        );
+        assert!(stale(&dir.path().join("missing"), WORKING_DIR_PREFIX)
+            .unwrap()
+            .is_empty());
+    }
+}
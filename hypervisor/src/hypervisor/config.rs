@@ -1,12 +1,16 @@
 //! Configuration for a653rs-linux-hypervisor.
 //!
-//! A configuration contains information about the partition schedule.
-//! Currently, only a single schedule is supported. Each schedule's major frame
+//! A configuration contains information about the partition schedules. The
+//! initial schedule is derived from the timing of the partitions, while further
+//! named schedules may be listed in `schedules`. Privileged partitions can
+//! switch between these schedules at runtime. Each schedule's major frame
 //! (MAF) has a fixed duration and number of slots. Each slot is occupied by at
 //! most one partition that executes during the slot. A slot has a fixed
 //! duration and offset inside the MAF. A partition may occupy multiple slots
 //! inside the schedule, in which case it may be repeated using the `period`
 //! parameter. Also the MAF must be cleanly dividable by this period.
+//! Alternatively, the slots of a schedule can be listed explicitly as
+//! `windows`, which allows slots of the same partition to differ in length.
 //!
 //! The hypervisor runs the executable file specified by `image` for each
 //! partition as a long-running process that is started and stopped according to
@@ -27,15 +31,34 @@
 //!     offset: 0ms
 //!     period: 500ms
 //!     image: target/x86_64-unknown-linux-musl/release/hello_part
+//!     privileged: true
 //!   - id: 1
 //!     name: Bar
 //!     offset: 100ms
 //!     duration: 10ms
 //!     image: target/x86_64-unknown-linux-musl/release/hello_part
 //!     period: 1s
+//!     memory_max: 64MB
+//!     pids_max: 32
 //!     sockets:
 //!       - type: tcp_connect
 //!         address: 127.0.0.1:8083
+//! schedules:
+//!   - name: Cruise
+//!     major_frame: 500ms
+//!     partitions:
+//!       - name: Foo
+//!         offset: 0ms
+//!         duration: 50ms
+//!         period: 500ms
+//!     windows:
+//!       - partition: Bar
+//!         start: 100ms
+//!         duration: 20ms
+//!         periodic_processing_start: true
+//!       - partition: Bar
+//!         start: 300ms
+//!         duration: 5ms
 //! channel:
 //!   - !Sampling
 //!     msg_size: 10KB
@@ -45,6 +68,15 @@
 //!     destination:
 //!       - partition: Bar
 //!         port: Hello
+//!   - !Queuing
+//!     msg_size: 1KB
+//!     msg_num: 10
+//!     source:
+//!       partition: Bar
+//!       port: RequestSend
+//!     destination:
+//!       partition: Foo
+//!       port: Request
 //! # ";
 //! # serde_yaml::from_str::<Config>(yaml).unwrap();
 //! ```
@@ -53,15 +85,18 @@ use std::net::{SocketAddr, ToSocketAddrs};
 use std::path::PathBuf;
 use std::time::Duration;
 
-use a653rs::bindings::PartitionId;
+use a653rs::bindings::{ErrorCode, PartitionId};
 use anyhow::anyhow;
+use bytesize::ByteSize;
 use serde::{Deserialize, Serialize};
 
 use a653rs_linux_core::channel::{QueuingChannelConfig, SamplingChannelConfig};
 use a653rs_linux_core::error::{ResultExt, SystemError, TypedResult};
-use a653rs_linux_core::health::{ModuleInitHMTable, ModuleRunHMTable, PartitionHMTable};
+use a653rs_linux_core::health::{
+    HMLevel, ModuleInitHMTable, ModuleRunHMTable, PartitionHMTable, ProcessHMTable,
+};
 
-use crate::hypervisor::scheduler::{PartitionSchedule, ScheduledTimeframe};
+use crate::hypervisor::scheduler::{ModuleSchedule, PartitionSchedule, ScheduledTimeframe};
 
 /// Main configuration of the hypervisor
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -91,6 +126,22 @@ pub struct Config {
     #[serde(default)]
     pub channel: Vec<Channel>,
 
+    /// Additional schedules of the module
+    ///
+    /// The initial schedule is named [Config::DEFAULT_SCHEDULE] and derived
+    /// from [Config::major_frame] and the timing of the partitions. Privileged
+    /// partitions may switch to any other schedule, which then takes effect at
+    /// the end of the current major frame.
+    #[serde(default)]
+    pub schedules: Vec<Schedule>,
+
+    /// Explicit windows of the initial schedule
+    ///
+    /// If any windows are given, they replace the ones derived from the
+    /// offset, period and duration of the partitions.
+    #[serde(default)]
+    pub windows: Vec<Window>,
+
     // TODO fill in documentation
     #[serde(default)]
     pub hm_init_table: ModuleInitHMTable,
@@ -98,6 +149,24 @@ pub struct Config {
     // TODO fill in documentation
     #[serde(default)]
     pub hm_run_table: ModuleRunHMTable,
+
+    /// Whether messages in the channels survive a module reset
+    ///
+    /// By default, all sampling and queuing channels are empty after the
+    /// module was reset.
+    #[serde(default)]
+    pub preserve_channels: bool,
+
+    /// Time granted to the partitions to exit on their own during a shutdown
+    ///
+    /// Once the hypervisor is asked to shut down, e.g. by SIGINT or SIGTERM,
+    /// it finishes the current window and sends SIGTERM to all partitions.
+    /// Partitions still running after this duration are killed.
+    #[serde(
+        default = "Config::default_shutdown_grace_period",
+        with = "humantime_serde"
+    )]
+    pub shutdown_grace_period: Duration,
 }
 
 /// Partition configuration
@@ -112,21 +181,25 @@ pub struct Partition {
     /// Duration of the partition window / Minor Frame (MiF)
     ///
     /// Whenever the partition is scheduled, it is executed for this long.
-    #[serde(with = "humantime_serde")]
-    pub duration: Duration,
+    ///
+    /// Like [Partition::offset] and [Partition::period], this is required
+    /// unless the default schedule is given as [Config::windows], in which case
+    /// it must be omitted.
+    #[serde(default, with = "humantime_serde")]
+    pub duration: Option<Duration>,
 
     /// Offset from beginning of the MaF ([Config::major_frame]), when the MiF
     /// starts
     ///
     /// Specifies when the partition is scheduled, relative to the beginning of
     /// the current MaF
-    #[serde(with = "humantime_serde")]
-    pub offset: Duration,
+    #[serde(default, with = "humantime_serde")]
+    pub offset: Option<Duration>,
 
     /// Repetition interval of the slice inside the MAF.
     // TODO add an explanation
-    #[serde(with = "humantime_serde")]
-    pub period: Duration,
+    #[serde(default, with = "humantime_serde")]
+    pub period: Option<Duration>,
 
     /// Path to the executable of the partition
     pub image: PathBuf,
@@ -135,6 +208,13 @@ pub struct Partition {
     #[serde(default)]
     pub hm_table: PartitionHMTable,
 
+    /// Levels at which the errors of the partition's processes are handled
+    ///
+    /// Errors handled at process level are delivered to the error handler of
+    /// the partition.
+    #[serde(default)]
+    pub process_hm_table: ProcessHMTable,
+
     /// Bindmounts from host to partition
     ///
     /// Use this to expose a path / file / device file from the host environment
@@ -146,8 +226,130 @@ pub struct Partition {
     #[serde(default)]
     pub mounts: Vec<(PathBuf, PathBuf)>,
 
+    /// Size of the partition's persistent memory
+    ///
+    /// The persistent memory keeps its content across warm starts of the
+    /// partition, while it is zeroed on cold starts.
+    #[serde(default)]
+    pub persistent_memory: Option<ByteSize>,
+
     #[serde(default)]
     pub sockets: Vec<PosixSocket>,
+
+    /// Whether the partition may switch the module schedule
+    #[serde(default)]
+    pub privileged: bool,
+
+    /// Hard limit of the memory of the partition (`memory.max`)
+    ///
+    /// Exceeding it invokes the OOM killer, which is reported as
+    /// [SystemError::OutOfMemory] to the health monitor of the partition.
+    #[serde(default)]
+    pub memory_max: Option<ByteSize>,
+
+    /// Memory of the partition, above which its processes are throttled
+    /// (`memory.high`)
+    #[serde(default)]
+    pub memory_high: Option<ByteSize>,
+
+    /// Maximum number of processes and threads of the partition (`pids.max`)
+    #[serde(default)]
+    pub pids_max: Option<u64>,
+
+    /// Size of the tmpfs holding the root file system of the partition
+    #[serde(default = "Partition::default_tmpfs_size")]
+    pub tmpfs_size: ByteSize,
+}
+
+impl Partition {
+    fn default_tmpfs_size() -> ByteSize {
+        ByteSize::kb(500)
+    }
+
+    /// Controllers required for the resource limits of the partition
+    pub(crate) fn cgroup_controllers(&self) -> Vec<&'static str> {
+        let mut controllers = Vec::new();
+        if self.memory_max.is_some() || self.memory_high.is_some() {
+            controllers.push("memory");
+        }
+        if self.pids_max.is_some() {
+            controllers.push("pids");
+        }
+        controllers
+    }
+}
+
+/// Period and duration of a partition in the default schedule
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct PartitionTiming {
+    pub period: Duration,
+    pub duration: Duration,
+}
+
+/// Named schedule of the module
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Schedule {
+    /// Name of the schedule, by which partitions select it
+    pub name: String,
+
+    /// Duration of one Major Frame (MaF) of this schedule
+    #[serde(with = "humantime_serde")]
+    pub major_frame: Duration,
+
+    /// Timing of the partitions in this schedule
+    ///
+    /// Partitions without an entry or window are not executed while this
+    /// schedule is active.
+    #[serde(default)]
+    pub partitions: Vec<ScheduledPartition>,
+
+    /// Explicit windows of this schedule, in addition to the ones derived from
+    /// [Schedule::partitions]
+    #[serde(default)]
+    pub windows: Vec<Window>,
+}
+
+/// Timing of a partition inside of a [Schedule]
+///
+/// The fields have the same meaning as the ones of [Partition].
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ScheduledPartition {
+    /// Name of the partition
+    pub name: String,
+
+    #[serde(with = "humantime_serde")]
+    pub duration: Duration,
+
+    #[serde(with = "humantime_serde")]
+    pub offset: Duration,
+
+    #[serde(with = "humantime_serde")]
+    pub period: Duration,
+}
+
+/// Explicit window of a partition inside of a major frame
+///
+/// Unlike the timing of a [Partition], windows of the same partition may
+/// differ in length.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Window {
+    /// Name of the partition executed during the window
+    pub partition: String,
+
+    /// Offset from the beginning of the major frame, when the window starts
+    #[serde(with = "humantime_serde")]
+    pub start: Duration,
+
+    #[serde(with = "humantime_serde")]
+    pub duration: Duration,
+
+    /// Whether the periodic processes of the partition are released at the
+    /// start of this window, after the partition entered the normal mode
+    ///
+    /// If no window of a partition is flagged, periodic processes are
+    /// released as soon as the partition enters the normal mode.
+    #[serde(default)]
+    pub periodic_processing_start: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -197,38 +399,399 @@ pub enum ModuleStates {
 }
 
 impl Config {
-    pub(crate) fn generate_schedule(&self) -> TypedResult<PartitionSchedule> {
-        // Verify Periods and Major Frame
-        let lcm_periods = self
-            .partitions
+    fn default_shutdown_grace_period() -> Duration {
+        Duration::from_secs(1)
+    }
+
+    /// Checks the health monitor tables of all partitions
+    ///
+    /// Errors, which affect the whole partition or module, can not be handled
+    /// at process level.
+    pub(crate) fn check_hm_tables(&self) -> TypedResult<()> {
+        match self.hm_table_problems().into_iter().next() {
+            Some(problem) => Err(anyhow!(problem)).typ(SystemError::PartitionConfig),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns all problems of the health monitor tables of the partitions
+    pub(crate) fn hm_table_problems(&self) -> Vec<String> {
+        let partition_wide = [
+            ErrorCode::StackOverflow,
+            ErrorCode::MemoryViolation,
+            ErrorCode::HardwareFault,
+            ErrorCode::PowerFail,
+        ];
+        self.partitions
             .iter()
-            .map(|p| p.period.as_nanos())
-            .reduce(num::integer::lcm);
-        if let Some(lcm_periods) = lcm_periods {
-            if self.major_frame.as_nanos() % lcm_periods != 0 {
-                return Err(anyhow!("major frame is not a multiple of the least-common-multiple of all partition periods.\n\
-                lcm: {:?}, major_frame: {:?}", Duration::from_nanos(lcm_periods as u64), self.major_frame))
+            .flat_map(|p| {
+                partition_wide
+                    .into_iter()
+                    .filter(|code| p.process_hm_table.level(*code) == HMLevel::Process)
+                    .map(|code| {
+                        format!(
+                            "{code:?} of partition \"{}\" can not be handled at process level",
+                            p.name
+                        )
+                    })
+            })
+            .collect()
+    }
+
+    /// Name of the schedule derived from the timing of the partitions
+    pub const DEFAULT_SCHEDULE: &'static str = "default";
+
+    /// Generates all schedules of the module
+    ///
+    /// The first schedule is always the [Config::DEFAULT_SCHEDULE], followed
+    /// by the ones listed in [Config::schedules].
+    pub(crate) fn generate_schedules(&self) -> TypedResult<Vec<ModuleSchedule>> {
+        let timing = self
+            .periodic_timing()
+            .map_err(|problem| anyhow!(problem))
+            .typ(SystemError::PartitionConfig)?
+            .into_iter()
+            .map(|(p, offset, period, duration)| (p.id, offset, period, duration))
+            .collect();
+        let mut schedules = vec![ModuleSchedule {
+            name: Self::DEFAULT_SCHEDULE.to_string(),
+            major_frame: self.major_frame,
+            schedule: self.generate_schedule(
+                Self::DEFAULT_SCHEDULE,
+                self.major_frame,
+                timing,
+                &self.windows,
+            )?,
+        }];
+
+        for s in &self.schedules {
+            if schedules.iter().any(|other| other.name == s.name) {
+                return Err(anyhow!("Schedule \"{}\" already exists", s.name))
                     .typ(SystemError::Config);
             }
+            let timing = s
+                .partitions
+                .iter()
+                .map(|p| {
+                    let id = self.partition_id(&s.name, &p.name)?;
+                    Ok((id, p.offset, p.period, p.duration))
+                })
+                .collect::<TypedResult<Vec<_>>>()?;
+            schedules.push(ModuleSchedule {
+                name: s.name.clone(),
+                major_frame: s.major_frame,
+                schedule: self.generate_schedule(&s.name, s.major_frame, timing, &s.windows)?,
+            });
+        }
+
+        Ok(schedules)
+    }
+
+    /// Returns the offset, period and duration of the partitions, from which
+    /// the default schedule is derived
+    ///
+    /// Explicit [Config::windows] replace the timing of the partitions, which
+    /// then must not be given at all. Otherwise it is required for all
+    /// partitions.
+    pub(crate) fn periodic_timing(
+        &self,
+    ) -> Result<Vec<(&Partition, Duration, Duration, Duration)>, String> {
+        let windows = !self.windows.is_empty();
+        let mut timing = Vec::new();
+        for p in &self.partitions {
+            match (p.offset, p.period, p.duration) {
+                (None, None, None) if windows => {}
+                (Some(offset), Some(period), Some(duration)) if !windows => {
+                    timing.push((p, offset, period, duration))
+                }
+                _ if windows => {
+                    return Err(format!(
+                        "partition \"{}\": offset, period and duration must be omitted, as the \
+                         default schedule is given as windows",
+                        p.name
+                    ))
+                }
+                _ => {
+                    return Err(format!(
+                        "partition \"{}\": offset, period and duration are required, unless the \
+                         default schedule is given as windows",
+                        p.name
+                    ))
+                }
+            }
+        }
+        Ok(timing)
+    }
+
+    /// Returns the period and duration of `partition` in the default schedule
+    ///
+    /// A partition scheduled by explicit [Config::windows] is executed once
+    /// per major frame, for the sum of its windows.
+    pub(crate) fn partition_timing(&self, partition: &Partition) -> PartitionTiming {
+        match (partition.period, partition.duration) {
+            (Some(period), Some(duration)) => PartitionTiming { period, duration },
+            _ => PartitionTiming {
+                period: self.major_frame,
+                duration: self
+                    .windows
+                    .iter()
+                    .filter(|w| w.partition == partition.name)
+                    .map(|w| w.duration)
+                    .sum(),
+            },
+        }
+    }
+
+    /// Returns the least-common-multiple of all `periods`, if the major frame
+    /// is not a multiple of it
+    pub(crate) fn unfitting_periods(
+        major_frame: Duration,
+        periods: impl Iterator<Item = Duration>,
+    ) -> Option<Duration> {
+        let lcm_periods = periods
+            .map(|period| period.as_nanos())
+            .reduce(num::integer::lcm)?;
+        if major_frame.as_nanos() % lcm_periods != 0 {
+            return Some(Duration::from_nanos(lcm_periods as u64));
+        }
+        None
+    }
+
+    /// Repeats the timeframe of a partition every `period` within the major
+    /// frame
+    pub(crate) fn periodic_timeframes(
+        major_frame: Duration,
+        partition: PartitionId,
+        offset: Duration,
+        period: Duration,
+        duration: Duration,
+    ) -> impl Iterator<Item = ScheduledTimeframe> {
+        let pimf = (major_frame.as_nanos() / period.as_nanos()) as u32;
+        (0..pimf).map(move |i| {
+            let start = offset + (period * i);
+            ScheduledTimeframe {
+                start,
+                end: start + duration,
+                partition,
+                periodic_start: false,
+            }
+        })
+    }
+
+    /// Returns the id of the partition named `name`, which is part of the
+    /// schedule `schedule`
+    fn partition_id(&self, schedule: &str, name: &str) -> TypedResult<PartitionId> {
+        match self.partitions.iter().find(|p| p.name == name) {
+            Some(p) => Ok(p.id),
+            None => Err(anyhow!(
+                "Schedule \"{schedule}\" contains unknown partition \"{name}\""
+            ))
+            .typ(SystemError::PartitionConfig),
+        }
+    }
+
+    /// Generates a schedule from the offset, period and duration of each
+    /// partition and the explicit windows
+    fn generate_schedule(
+        &self,
+        name: &str,
+        major_frame: Duration,
+        timing: Vec<(PartitionId, Duration, Duration, Duration)>,
+        windows: &[Window],
+    ) -> TypedResult<PartitionSchedule> {
+        // Verify Periods and Major Frame
+        if let Some(lcm_periods) =
+            Self::unfitting_periods(major_frame, timing.iter().map(|(_, _, period, _)| *period))
+        {
+            return Err(anyhow!("major frame is not a multiple of the least-common-multiple of all partition periods.\n\
+            lcm: {:?}, major_frame: {:?}", lcm_periods, major_frame))
+                .typ(SystemError::Config);
         }
 
         // Generate Schedule
-        let timeframes = self
-            .partitions
-            .iter()
-            .flat_map(|p| {
-                let pimf = (self.major_frame.as_nanos() / p.period.as_nanos()) as u32;
-                (0..pimf).map(|i| {
-                    let start = p.offset + (p.period * i);
-                    ScheduledTimeframe {
-                        start,
-                        end: start + p.duration,
-                        partition: p.id,
-                    }
-                })
+        let mut timeframes = timing
+            .into_iter()
+            .flat_map(|(id, offset, period, duration)| {
+                Self::periodic_timeframes(major_frame, id, offset, period, duration)
             })
             .collect::<Vec<_>>();
 
+        for w in windows {
+            let end = w.start + w.duration;
+            if end > major_frame {
+                return Err(anyhow!(
+                    "Window of partition \"{}\" in schedule \"{name}\" ends after the major frame.\n\
+                    end: {end:?}, major_frame: {major_frame:?}",
+                    w.partition
+                ))
+                .typ(SystemError::Config);
+            }
+            timeframes.push(ScheduledTimeframe {
+                start: w.start,
+                end,
+                partition: self.partition_id(name, &w.partition)?,
+                periodic_start: w.periodic_processing_start,
+            });
+        }
+
         PartitionSchedule::from_timeframes(timeframes).typ(SystemError::PartitionConfig)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = "
+major_frame: 1s
+partitions:
+  - id: 0
+    name: Foo
+    duration: 10ms
+    offset: 0ms
+    period: 1s
+    image: hello_part
+    persistent_memory: 4KiB
+    process_hm_table:
+      deadline_missed: Partition
+      application_error: Process
+      numeric_error: Process
+      illegal_request: Module
+      stack_overflow: Partition
+      memory_violation: Partition
+      hardware_fault: Module
+      power_fail: Module
+";
+
+    #[test]
+    fn process_hm_table() {
+        let config: Config = serde_yaml::from_str(CONFIG).unwrap();
+        let table = &config.partitions[0].process_hm_table;
+        assert_eq!(table.level(ErrorCode::DeadlineMissed), HMLevel::Partition);
+        assert_eq!(table.level(ErrorCode::IllegalRequest), HMLevel::Module);
+        config.check_hm_tables().unwrap();
+
+        let config: Config = serde_yaml::from_str(
+            &CONFIG.replace("memory_violation: Partition", "memory_violation: Process"),
+        )
+        .unwrap();
+        assert!(config.check_hm_tables().is_err());
+    }
+
+    #[test]
+    fn persistent_memory() {
+        let config: Config = serde_yaml::from_str(CONFIG).unwrap();
+        assert_eq!(
+            config.partitions[0].persistent_memory,
+            Some(ByteSize::kib(4))
+        );
+
+        let config: Config =
+            serde_yaml::from_str(&CONFIG.replace("    persistent_memory: 4KiB\n", "")).unwrap();
+        assert_eq!(config.partitions[0].persistent_memory, None);
+    }
+
+    #[test]
+    fn schedules() {
+        let schedules = "
+schedules:
+  - name: Fast
+    major_frame: 100ms
+    partitions:
+      - name: Foo
+        duration: 10ms
+        offset: 20ms
+        period: 50ms
+  - name: Idle
+    major_frame: 1s
+    partitions: []
+";
+        let config: Config = serde_yaml::from_str(&format!("{CONFIG}{schedules}")).unwrap();
+        let schedules = config.generate_schedules().unwrap();
+        assert_eq!(
+            schedules
+                .iter()
+                .map(|s| s.name.as_str())
+                .collect::<Vec<_>>(),
+            [Config::DEFAULT_SCHEDULE, "Fast", "Idle"]
+        );
+        assert_eq!(schedules[1].major_frame, Duration::from_millis(100));
+        let starts = schedules[1]
+            .schedule
+            .iter()
+            .map(|t| t.start.as_millis())
+            .collect::<Vec<_>>();
+        assert_eq!(starts, [20, 70]);
+        assert_eq!(schedules[2].schedule.iter().count(), 0);
+
+        let config: Config =
+            serde_yaml::from_str(&format!("{CONFIG}{}", schedules_yaml("Bar"))).unwrap();
+        assert!(config.generate_schedules().is_err());
+        let config: Config = serde_yaml::from_str(&format!(
+            "{CONFIG}{}",
+            schedules_yaml("Foo").replace("Fast", Config::DEFAULT_SCHEDULE)
+        ))
+        .unwrap();
+        assert!(config.generate_schedules().is_err());
+    }
+
+    #[test]
+    fn windows() {
+        let windows = "
+windows:
+  - partition: Foo
+    start: 0ms
+    duration: 100ms
+  - partition: Foo
+    start: 500ms
+    duration: 20ms
+    periodic_processing_start: true
+";
+        // Windows replace the timing of the partitions
+        let untimed = CONFIG.replace("    duration: 10ms\n    offset: 0ms\n    period: 1s\n", "");
+        let config: Config = serde_yaml::from_str(&format!("{untimed}{windows}")).unwrap();
+        let schedules = config.generate_schedules().unwrap();
+        let timeframes = schedules[0]
+            .schedule
+            .iter()
+            .map(|t| (t.start.as_millis(), t.end.as_millis(), t.periodic_start))
+            .collect::<Vec<_>>();
+        assert_eq!(timeframes, [(0, 100, false), (500, 520, true)]);
+        let timing = config.partition_timing(&config.partitions[0]);
+        assert_eq!(timing.period, Duration::from_secs(1));
+        assert_eq!(timing.duration, Duration::from_millis(120));
+
+        let config: Config =
+            serde_yaml::from_str(&format!("{untimed}{}", windows.replace("500ms", "990ms")))
+                .unwrap();
+        assert!(config.generate_schedules().is_err());
+        let config: Config =
+            serde_yaml::from_str(&format!("{untimed}{}", windows.replace("500ms", "50ms")))
+                .unwrap();
+        assert!(config.generate_schedules().is_err());
+
+        // The timing is either given for all partitions or replaced by windows
+        let config: Config = serde_yaml::from_str(&format!("{CONFIG}{windows}")).unwrap();
+        assert!(config.generate_schedules().is_err());
+        let config: Config = serde_yaml::from_str(&untimed).unwrap();
+        assert!(config.generate_schedules().is_err());
+        let config: Config =
+            serde_yaml::from_str(&CONFIG.replace("    offset: 0ms\n", "")).unwrap();
+        assert!(config.generate_schedules().is_err());
+    }
+
+    fn schedules_yaml(partition: &str) -> String {
+        format!(
+            "
+schedules:
+  - name: Fast
+    major_frame: 100ms
+    partitions:
+      - name: {partition}
+        duration: 10ms
+        offset: 0ms
+        period: 100ms
+"
+        )
+    }
+}
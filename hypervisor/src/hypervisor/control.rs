@@ -0,0 +1,407 @@
+//! Control socket for the inspection and control of a running hypervisor
+//!
+//! The hypervisor listens on a local Unix socket for [Request]s. Every
+//! connection carries a single request as a line of JSON, which is answered by
+//! a single [Response] as a line of JSON.
+//!
+//! Requests are only served in between major frames, so that the schedule of
+//! the partitions is not disturbed. Hence, a response may take up to a major
+//! frame.
+//!
+//! A [Request::TailLog] keeps its connection open. After the response, the
+//! hypervisor sends a [LogLine] for every log message of the partition, until
+//! the client closes the connection.
+use std::fs::Permissions;
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use anyhow::anyhow;
+use polling::{Event, Events, Poller};
+use serde::{Deserialize, Serialize};
+
+use a653rs_linux_core::error::{ResultExt, SystemError, TypedResult};
+
+/// Request to the hypervisor
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum Request {
+    /// Returns the [ModuleStatus]
+    Status,
+    /// Forces a partition into another operating mode
+    Partition {
+        name: String,
+        action: PartitionAction,
+    },
+    /// Stops scheduling partitions after the current major frame
+    Pause,
+    /// Continues scheduling partitions with the next major frame
+    Resume,
+    /// Shuts the hypervisor down after the current major frame
+    Shutdown,
+    /// Streams the log messages of a partition
+    TailLog { partition: String },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PartitionAction {
+    Idle,
+    ColdStart,
+    WarmStart,
+}
+
+/// Response of the hypervisor to a [Request]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Response {
+    Ok,
+    Status(ModuleStatus),
+    Error(String),
+}
+
+/// Status of the module, as returned for [Request::Status]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModuleStatus {
+    /// Whether the scheduling of partitions is paused
+    pub paused: bool,
+    pub schedule: ScheduleStatus,
+    pub partitions: Vec<PartitionStatus>,
+    pub channels: Vec<ChannelStatus>,
+}
+
+/// Position of the hypervisor inside its schedule
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScheduleStatus {
+    /// Name of the current schedule
+    pub current: String,
+    /// Name of the schedule, which is used from the next major frame on
+    pub next: String,
+    #[serde(with = "humantime_serde")]
+    pub major_frame: Duration,
+    /// Number of major frames since the start of the hypervisor
+    pub major_frames: u64,
+    /// Time passed since the start of the current major frame
+    #[serde(with = "humantime_serde")]
+    pub time_in_frame: Duration,
+    /// Time passed since the start of the hypervisor
+    #[serde(with = "humantime_serde")]
+    pub uptime: Duration,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PartitionStatus {
+    pub id: i64,
+    pub name: String,
+    /// Operating mode of the partition
+    pub mode: String,
+    pub cgroup: PathBuf,
+    /// PID of the main process of the partition
+    pub pid: i32,
+    pub processes: Vec<ProcessStatus>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProcessStatus {
+    pub id: i64,
+    pub name: String,
+    pub state: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChannelStatus {
+    pub name: String,
+    pub kind: ChannelKind,
+    pub msg_size: usize,
+    /// Number of messages transferred to the destination since the channel
+    /// was created
+    pub transferred: usize,
+    /// Capacity and number of messages waiting in the source and the
+    /// destination queue of queuing channels
+    pub queue: Option<QueueStatus>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChannelKind {
+    Sampling,
+    Queuing,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueueStatus {
+    pub msg_num: usize,
+    pub source: usize,
+    pub destination: usize,
+}
+
+/// Log message of a partition, as streamed for [Request::TailLog]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LogLine {
+    pub partition: String,
+    pub level: String,
+    pub message: String,
+}
+
+/// Sends `request` to the control socket at `path` and waits for the response
+pub fn send(path: &Path, request: &Request) -> TypedResult<Response> {
+    let mut reader = connect(path, request)?;
+    read_line(&mut reader)?
+        .ok_or_else(|| anyhow!("the hypervisor closed the connection without a response"))
+        .typ(SystemError::Panic)
+}
+
+/// Streams the log messages of `partition` from the control socket at `path`
+/// to `f`, until the hypervisor closes the connection
+pub fn tail_log(path: &Path, partition: &str, mut f: impl FnMut(LogLine)) -> TypedResult<()> {
+    let request = Request::TailLog {
+        partition: partition.to_string(),
+    };
+    let mut reader = connect(path, &request)?;
+    match read_line(&mut reader)? {
+        Some(Response::Ok) => {}
+        Some(Response::Error(e)) => return Err(anyhow!("{e}")).typ(SystemError::Panic),
+        response => {
+            return Err(anyhow!("unexpected response: {response:?}")).typ(SystemError::Panic)
+        }
+    }
+    while let Some(line) = read_line(&mut reader)? {
+        f(line)
+    }
+    Ok(())
+}
+
+fn connect(path: &Path, request: &Request) -> TypedResult<BufReader<UnixStream>> {
+    let mut stream = UnixStream::connect(path)
+        .map_err(|e| anyhow!("could not connect to {path:?}: {e}"))
+        .typ(SystemError::Panic)?;
+    write_line(&mut stream, request)?;
+    Ok(BufReader::new(stream))
+}
+
+fn write_line<T: Serialize>(stream: &mut UnixStream, value: &T) -> TypedResult<()> {
+    let mut line = serde_json::to_vec(value).typ(SystemError::Panic)?;
+    line.push(b'\n');
+    stream.write_all(&line).typ(SystemError::Panic)
+}
+
+fn read_line<T: for<'de> Deserialize<'de>>(reader: &mut impl BufRead) -> TypedResult<Option<T>> {
+    let mut line = String::new();
+    if reader.read_line(&mut line).typ(SystemError::Panic)? == 0 {
+        return Ok(None);
+    }
+    serde_json::from_str(&line)
+        .typ(SystemError::Panic)
+        .map(Some)
+}
+
+/// Listening side of the control socket. The socket file is removed once the
+/// socket is dropped.
+pub(crate) struct ControlSocket {
+    path: PathBuf,
+    listener: UnixListener,
+    poller: Poller,
+    /// Clients streaming the log of a partition
+    subscribers: Vec<(String, UnixStream)>,
+}
+
+impl ControlSocket {
+    /// Timeout for reading a request from and writing a response to a client
+    const CLIENT_TIMEOUT: Duration = Duration::from_millis(100);
+
+    pub fn bind(path: &Path) -> TypedResult<Self> {
+        // Remove the socket of a previous hypervisor
+        if path.exists() {
+            std::fs::remove_file(path).typ(SystemError::Panic)?;
+        }
+        let listener = UnixListener::bind(path).typ(SystemError::Panic)?;
+        // Only the user running the hypervisor may command it
+        std::fs::set_permissions(path, Permissions::from_mode(0o600)).typ(SystemError::Panic)?;
+        listener.set_nonblocking(true).typ(SystemError::Panic)?;
+        let poller = Poller::new().typ(SystemError::Panic)?;
+        unsafe { poller.add(&listener, Event::readable(0)) }.typ(SystemError::Panic)?;
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            listener,
+            poller,
+            subscribers: Vec::new(),
+        })
+    }
+
+    /// Waits until `deadline` for the next request. Clients sending invalid
+    /// requests are answered with an error right away.
+    ///
+    /// A pending request is returned even if `deadline` already passed, so
+    /// that requests are served even if partitions use up the major frame.
+    pub fn next(&mut self, deadline: Instant) -> TypedResult<Option<Client>> {
+        for attempt in 0.. {
+            if attempt > 0 && Instant::now() >= deadline {
+                break;
+            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let mut events = Events::new();
+            self.poller
+                .wait(&mut events, Some(remaining))
+                .typ(SystemError::Panic)?;
+            if events.is_empty() {
+                return Ok(None);
+            }
+            self.poller
+                .modify(&self.listener, Event::readable(0))
+                .typ(SystemError::Panic)?;
+
+            let mut stream = match self.listener.accept() {
+                Ok((stream, _)) => stream,
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => continue,
+                Err(e) => return Err(e).typ(SystemError::Panic),
+            };
+            if let Err(e) = Self::prepare(&stream) {
+                warn!("Control socket: {e}");
+                continue;
+            }
+            match read_line::<Request>(&mut BufReader::new(&stream)) {
+                Ok(Some(request)) => return Ok(Some(Client { stream, request })),
+                Ok(None) => {}
+                Err(e) => {
+                    let response = Response::Error(format!("invalid request: {e}"));
+                    if let Err(e) = write_line(&mut stream, &response) {
+                        warn!("Control socket: could not respond: {e}")
+                    }
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// Streams the log of `partition` to `client` from now on
+    pub fn subscribe(&mut self, client: Client, partition: &str) {
+        // A slow client must not delay the hypervisor
+        match client.stream.set_nonblocking(true) {
+            Ok(()) => self
+                .subscribers
+                .push((partition.to_string(), client.stream)),
+            Err(e) => warn!("Control socket: {e}"),
+        }
+    }
+
+    /// Returns whether any client streams the log of `partition`
+    pub fn subscribed(&self, partition: &str) -> bool {
+        self.subscribers.iter().any(|(p, _)| p == partition)
+    }
+
+    /// Sends `line` to all clients streaming the log of its partition. Clients
+    /// which cannot keep up or closed their connection are dropped.
+    pub fn publish(&mut self, line: &LogLine) {
+        self.subscribers.retain_mut(|(partition, stream)| {
+            if *partition != line.partition {
+                return true;
+            }
+            match write_line(stream, line) {
+                Ok(()) => true,
+                Err(e) => {
+                    debug!("Control socket: dropping log client of \"{partition}\": {e}");
+                    false
+                }
+            }
+        });
+    }
+
+    fn prepare(stream: &UnixStream) -> std::io::Result<()> {
+        stream.set_nonblocking(false)?;
+        stream.set_read_timeout(Some(Self::CLIENT_TIMEOUT))?;
+        stream.set_write_timeout(Some(Self::CLIENT_TIMEOUT))
+    }
+}
+
+impl Drop for ControlSocket {
+    fn drop(&mut self) {
+        if let Err(e) = std::fs::remove_file(&self.path) {
+            error!("{e}")
+        }
+    }
+}
+
+/// A client of the control socket waiting for the response to its request
+pub(crate) struct Client {
+    stream: UnixStream,
+    pub request: Request,
+}
+
+impl Client {
+    pub fn respond(&mut self, response: &Response) {
+        if let Err(e) = write_line(&mut self.stream, response) {
+            warn!("Control socket: could not respond: {e}")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+
+    #[test]
+    fn request_response() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("control.sock");
+        let mut socket = ControlSocket::bind(&path).unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        let client = {
+            let path = path.clone();
+            thread::spawn(move || {
+                let request = Request::Partition {
+                    name: "Foo".to_string(),
+                    action: PartitionAction::ColdStart,
+                };
+                send(&path, &request).unwrap()
+            })
+        };
+
+        let mut client_request = socket
+            .next(Instant::now() + Duration::from_secs(5))
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            client_request.request,
+            Request::Partition {
+                name: "Foo".to_string(),
+                action: PartitionAction::ColdStart
+            }
+        );
+        client_request.respond(&Response::Ok);
+        assert_eq!(client.join().unwrap(), Response::Ok);
+
+        // Nothing is requested anymore
+        assert!(socket.next(Instant::now()).unwrap().is_none());
+        drop(socket);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn wire_format() {
+        let request: Request =
+            serde_json::from_str(r#"{"command":"partition","name":"Foo","action":"warm_start"}"#)
+                .unwrap();
+        assert_eq!(
+            request,
+            Request::Partition {
+                name: "Foo".to_string(),
+                action: PartitionAction::WarmStart
+            }
+        );
+        assert_eq!(
+            serde_json::to_string(&Request::Shutdown).unwrap(),
+            r#"{"command":"shutdown"}"#
+        );
+        assert_eq!(
+            serde_json::to_string(&Response::Error("oops".to_string())).unwrap(),
+            r#"{"error":"oops"}"#
+        );
+    }
+}
@@ -0,0 +1,164 @@
+//! Intra-partition communication objects, which are shared by the processes of
+//! a single partition
+//!
+//! The objects live inside the hypervisor, as processes blocked on them wait
+//! inside their system call, while their cgroup is frozen.
+use std::collections::VecDeque;
+
+use a653rs::bindings::{ErrorReturnCode, Priority, ProcessId, QueuingDiscipline};
+
+pub(crate) use blackboard::{Blackboard, MAX_BLACKBOARDS};
+pub(crate) use buffer::{Buffer, MAX_BUFFERS, MAX_NB_MESSAGE};
+pub(crate) use event::{Event, MAX_EVENTS};
+#[cfg(test)]
+pub(crate) use mutex::MAX_LOCK_COUNT;
+pub(crate) use mutex::{Mutex, MAX_MUTEXES};
+pub(crate) use semaphore::{Semaphore, MAX_SEMAPHORES};
+
+mod blackboard;
+mod buffer;
+mod event;
+mod mutex;
+mod semaphore;
+
+/// Maximum size of a message of a buffer or a blackboard
+pub(crate) const MAX_MESSAGE_SIZE: usize = 64 * 1024;
+
+/// An intra-partition communication object
+pub(crate) trait IntraObject {
+    fn name(&self) -> &str;
+
+    /// Removes a process, which no longer waits, from all waiting queues of
+    /// the object
+    fn cancel_wait(&mut self, id: ProcessId);
+}
+
+/// All objects of one kind of a partition, indexed by their id
+#[derive(Debug)]
+pub(crate) struct ObjectTable<T> {
+    objects: Vec<T>,
+    max: usize,
+}
+
+impl<T: IntraObject> ObjectTable<T> {
+    /// Creates an empty table, which holds up to `max` objects
+    pub fn new(max: usize) -> Self {
+        Self {
+            objects: Vec::new(),
+            max,
+        }
+    }
+
+    /// Adds an object and returns its id
+    pub fn create(&mut self, object: T) -> Result<i64, ErrorReturnCode> {
+        if self.id(object.name()).is_some() {
+            return Err(ErrorReturnCode::NoAction);
+        }
+        if self.objects.len() >= self.max {
+            return Err(ErrorReturnCode::InvalidConfig);
+        }
+        self.objects.push(object);
+        Ok(self.objects.len() as i64)
+    }
+
+    pub fn get(&self, id: i64) -> Option<&T> {
+        let index = usize::try_from(id).ok()?.checked_sub(1)?;
+        self.objects.get(index)
+    }
+
+    pub fn get_mut(&mut self, id: i64) -> Option<&mut T> {
+        let index = usize::try_from(id).ok()?.checked_sub(1)?;
+        self.objects.get_mut(index)
+    }
+
+    /// Returns the id of the object with the given name
+    pub fn id(&self, name: &str) -> Option<i64> {
+        self.find(|o| o.name() == name)
+    }
+
+    /// Returns the id of the first object matching `predicate`
+    pub fn find(&self, predicate: impl Fn(&T) -> bool) -> Option<i64> {
+        self.objects
+            .iter()
+            .position(predicate)
+            .map(|i| i as i64 + 1)
+    }
+
+    pub fn cancel_wait(&mut self, id: ProcessId) {
+        self.objects.iter_mut().for_each(|o| o.cancel_wait(id))
+    }
+}
+
+/// Processes waiting on an object, each with a value of its own
+///
+/// Depending on the queuing discipline, processes leave the queue in the order
+/// they entered it or by their priority. Processes of the same priority leave
+/// in the order they entered.
+#[derive(Debug)]
+pub(crate) struct WaitQueue<T = ()> {
+    discipline: QueuingDiscipline,
+    waiting: VecDeque<(ProcessId, Priority, T)>,
+}
+
+impl<T> WaitQueue<T> {
+    pub fn new(discipline: QueuingDiscipline) -> Self {
+        Self {
+            discipline,
+            waiting: VecDeque::new(),
+        }
+    }
+
+    pub fn push(&mut self, id: ProcessId, priority: Priority, value: T) {
+        let index = match self.discipline {
+            QueuingDiscipline::Fifo => self.waiting.len(),
+            QueuingDiscipline::Priority => self
+                .waiting
+                .iter()
+                .position(|(_, p, _)| *p < priority)
+                .unwrap_or(self.waiting.len()),
+        };
+        self.waiting.insert(index, (id, priority, value));
+    }
+
+    pub fn pop(&mut self) -> Option<(ProcessId, T)> {
+        self.waiting.pop_front().map(|(id, _, value)| (id, value))
+    }
+
+    /// Removes a process from the queue
+    pub fn remove(&mut self, id: ProcessId) {
+        self.waiting.retain(|(waiting, _, _)| *waiting != id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.waiting.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wait_queue_order() {
+        let mut fifo = WaitQueue::new(QueuingDiscipline::Fifo);
+        fifo.push(1, 10, ());
+        fifo.push(2, 20, ());
+        fifo.push(3, 10, ());
+        assert_eq!(fifo.pop(), Some((1, ())));
+        assert_eq!(fifo.pop(), Some((2, ())));
+        assert_eq!(fifo.pop(), Some((3, ())));
+        assert_eq!(fifo.pop(), None);
+
+        let mut priority = WaitQueue::new(QueuingDiscipline::Priority);
+        priority.push(1, 10, ());
+        priority.push(2, 20, ());
+        priority.push(3, 10, ());
+        priority.push(4, 30, ());
+        priority.remove(2);
+        assert_eq!(priority.len(), 3);
+        assert_eq!(priority.pop(), Some((4, ())));
+        assert_eq!(priority.pop(), Some((1, ())));
+        assert_eq!(priority.pop(), Some((3, ())));
+        assert_eq!(priority.len(), 0);
+    }
+}
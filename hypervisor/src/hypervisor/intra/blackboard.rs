@@ -0,0 +1,68 @@
+//! Blackboards, which display a single message to the processes of a partition
+use a653rs::bindings::{Priority, ProcessId, QueuingDiscipline};
+
+use super::{IntraObject, WaitQueue};
+
+/// Maximum number of blackboards of a partition
+pub(crate) const MAX_BLACKBOARDS: usize = 256;
+
+/// A message, which remains displayed until it is replaced or cleared
+///
+/// Processes wait on an empty blackboard, until a message is displayed.
+#[derive(Debug)]
+pub(crate) struct Blackboard {
+    name: String,
+    max_message_size: usize,
+    message: Option<Vec<u8>>,
+    /// Processes waiting for a message. All of them are woken up at once.
+    readers: WaitQueue,
+}
+
+impl Blackboard {
+    pub fn new(name: String, max_message_size: usize) -> Self {
+        Self {
+            name,
+            max_message_size,
+            message: None,
+            readers: WaitQueue::new(QueuingDiscipline::Fifo),
+        }
+    }
+
+    pub fn max_message_size(&self) -> usize {
+        self.max_message_size
+    }
+
+    pub fn message(&self) -> Option<&[u8]> {
+        self.message.as_deref()
+    }
+
+    pub fn waiting_processes(&self) -> usize {
+        self.readers.len()
+    }
+
+    /// Displays a message. Returns the waiting readers, which read it right
+    /// away.
+    pub fn display(&mut self, message: Vec<u8>) -> Vec<ProcessId> {
+        self.message = Some(message);
+        std::iter::from_fn(|| self.readers.pop().map(|(id, ())| id)).collect()
+    }
+
+    pub fn clear(&mut self) {
+        self.message = None;
+    }
+
+    /// Lets a process wait for a message on the empty blackboard
+    pub fn wait_read(&mut self, id: ProcessId, priority: Priority) {
+        self.readers.push(id, priority, ())
+    }
+}
+
+impl IntraObject for Blackboard {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn cancel_wait(&mut self, id: ProcessId) {
+        self.readers.remove(id);
+    }
+}
@@ -0,0 +1,107 @@
+//! Buffers, which hand over messages between the processes of a partition
+use std::collections::VecDeque;
+
+use a653rs::bindings::{Priority, ProcessId, QueuingDiscipline};
+
+use super::{IntraObject, WaitQueue};
+
+/// Maximum number of buffers of a partition
+pub(crate) const MAX_BUFFERS: usize = 256;
+/// Maximum number of messages a buffer may hold
+pub(crate) const MAX_NB_MESSAGE: usize = 1024;
+
+/// A bounded FIFO queue of messages
+///
+/// Processes wait on an empty buffer to receive a message and on a full buffer
+/// to send theirs.
+#[derive(Debug)]
+pub(crate) struct Buffer {
+    name: String,
+    max_message_size: usize,
+    max_nb_message: usize,
+    messages: VecDeque<Vec<u8>>,
+    /// Processes waiting for a message
+    receivers: WaitQueue,
+    /// Processes waiting for space for their message
+    senders: WaitQueue<Vec<u8>>,
+}
+
+impl Buffer {
+    pub fn new(
+        name: String,
+        max_message_size: usize,
+        max_nb_message: usize,
+        discipline: QueuingDiscipline,
+    ) -> Self {
+        Self {
+            name,
+            max_message_size,
+            max_nb_message,
+            messages: VecDeque::new(),
+            receivers: WaitQueue::new(discipline),
+            senders: WaitQueue::new(discipline),
+        }
+    }
+
+    pub fn max_message_size(&self) -> usize {
+        self.max_message_size
+    }
+
+    pub fn max_nb_message(&self) -> usize {
+        self.max_nb_message
+    }
+
+    pub fn nb_message(&self) -> usize {
+        self.messages.len()
+    }
+
+    /// Number of processes waiting to either send or receive a message
+    pub fn waiting_processes(&self) -> usize {
+        self.receivers.len() + self.senders.len()
+    }
+
+    /// Sends a message, which is handed over to the first waiting receiver
+    /// right away, if there is one. Returns the message if the buffer is full.
+    pub fn send(&mut self, message: Vec<u8>) -> Result<Option<(ProcessId, Vec<u8>)>, Vec<u8>> {
+        if let Some((receiver, ())) = self.receivers.pop() {
+            return Ok(Some((receiver, message)));
+        }
+        if self.messages.len() >= self.max_nb_message {
+            return Err(message);
+        }
+        self.messages.push_back(message);
+        Ok(None)
+    }
+
+    /// Lets a process wait for space for its message in the full buffer
+    pub fn wait_send(&mut self, id: ProcessId, priority: Priority, message: Vec<u8>) {
+        self.senders.push(id, priority, message)
+    }
+
+    /// Receives the oldest message. The message of the first waiting sender
+    /// takes its place, in which case the sender is returned as well.
+    pub fn receive(&mut self) -> Option<(Vec<u8>, Option<ProcessId>)> {
+        let message = self.messages.pop_front()?;
+        let sender = self.senders.pop().map(|(sender, message)| {
+            self.messages.push_back(message);
+            sender
+        });
+        Some((message, sender))
+    }
+
+    /// Lets a process wait for a message in the empty buffer
+    pub fn wait_receive(&mut self, id: ProcessId, priority: Priority) {
+        self.receivers.push(id, priority, ())
+    }
+}
+
+impl IntraObject for Buffer {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn cancel_wait(&mut self, id: ProcessId) {
+        self.receivers.remove(id);
+        self.senders.remove(id);
+    }
+}
@@ -0,0 +1,61 @@
+//! Events, which notify the processes of a partition of a condition
+use a653rs::bindings::{EventState, Priority, ProcessId, QueuingDiscipline};
+
+use super::{IntraObject, WaitQueue};
+
+/// Maximum number of events of a partition
+pub(crate) const MAX_EVENTS: usize = 256;
+
+/// An event, which is either up or down
+///
+/// Processes wait on an event, while it is down. Setting the event wakes up
+/// all of them at once.
+#[derive(Debug)]
+pub(crate) struct Event {
+    name: String,
+    state: EventState,
+    waiting: WaitQueue,
+}
+
+impl Event {
+    pub fn new(name: String) -> Self {
+        Self {
+            name,
+            state: EventState::Down,
+            waiting: WaitQueue::new(QueuingDiscipline::Fifo),
+        }
+    }
+
+    pub fn state(&self) -> EventState {
+        self.state
+    }
+
+    pub fn waiting_processes(&self) -> usize {
+        self.waiting.len()
+    }
+
+    /// Sets the event up. Returns all waiting processes.
+    pub fn set(&mut self) -> Vec<ProcessId> {
+        self.state = EventState::Up;
+        std::iter::from_fn(|| self.waiting.pop().map(|(id, ())| id)).collect()
+    }
+
+    pub fn reset(&mut self) {
+        self.state = EventState::Down;
+    }
+
+    /// Lets a process wait for the event to be set up
+    pub fn wait(&mut self, id: ProcessId, priority: Priority) {
+        self.waiting.push(id, priority, ())
+    }
+}
+
+impl IntraObject for Event {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn cancel_wait(&mut self, id: ProcessId) {
+        self.waiting.remove(id);
+    }
+}
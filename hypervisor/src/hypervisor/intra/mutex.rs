@@ -0,0 +1,121 @@
+//! Mutexes, which grant the processes of a partition exclusive access to a
+//! resource
+use a653rs::bindings::{ErrorReturnCode, LockCount, Priority, ProcessId, QueuingDiscipline};
+
+use super::{IntraObject, WaitQueue};
+
+/// Maximum number of mutexes of a partition
+pub(crate) const MAX_MUTEXES: usize = 256;
+/// Maximum number of times the owner may acquire a mutex recursively
+pub(crate) const MAX_LOCK_COUNT: LockCount = 16;
+
+/// A mutex with a priority ceiling
+///
+/// While a process owns the mutex, its current priority is raised to the
+/// priority of the mutex. Processes wait on an owned mutex, until it is
+/// released to them.
+#[derive(Debug)]
+pub(crate) struct Mutex {
+    name: String,
+    priority: Priority,
+    owner: Option<ProcessId>,
+    lock_count: LockCount,
+    waiting: WaitQueue,
+}
+
+impl Mutex {
+    pub fn new(name: String, priority: Priority, discipline: QueuingDiscipline) -> Self {
+        Self {
+            name,
+            priority,
+            owner: None,
+            lock_count: 0,
+            waiting: WaitQueue::new(discipline),
+        }
+    }
+
+    pub fn priority(&self) -> Priority {
+        self.priority
+    }
+
+    pub fn owner(&self) -> Option<ProcessId> {
+        self.owner
+    }
+
+    pub fn lock_count(&self) -> LockCount {
+        self.lock_count
+    }
+
+    pub fn waiting_processes(&self) -> usize {
+        self.waiting.len()
+    }
+
+    /// Acquires the mutex for a process. Returns whether this was possible
+    /// without waiting, which is also the case if the process already owns
+    /// the mutex.
+    pub fn try_acquire(&mut self, id: ProcessId) -> Result<bool, ErrorReturnCode> {
+        match self.owner {
+            None => {
+                self.owner = Some(id);
+                self.lock_count = 1;
+                Ok(true)
+            }
+            Some(owner) if owner == id => {
+                if self.lock_count >= MAX_LOCK_COUNT {
+                    return Err(ErrorReturnCode::InvalidConfig);
+                }
+                self.lock_count += 1;
+                Ok(true)
+            }
+            Some(_) => Ok(false),
+        }
+    }
+
+    /// Lets a process wait for the mutex to be released
+    pub fn wait(&mut self, id: ProcessId, priority: Priority) {
+        self.waiting.push(id, priority, ())
+    }
+
+    /// Releases the mutex once on behalf of its owner. Returns whether the
+    /// owner released it completely.
+    pub fn release(&mut self, id: ProcessId) -> Result<bool, ErrorReturnCode> {
+        if self.owner != Some(id) {
+            return Err(ErrorReturnCode::InvalidMode);
+        }
+        self.lock_count -= 1;
+        if self.lock_count > 0 {
+            return Ok(false);
+        }
+        self.owner = None;
+        Ok(true)
+    }
+
+    /// Releases the mutex completely, regardless of its lock count. Returns
+    /// the previous owner.
+    pub fn reset(&mut self) -> Option<ProcessId> {
+        self.lock_count = 0;
+        self.owner.take()
+    }
+
+    /// Passes an available mutex on to the first waiting process, which
+    /// becomes its new owner
+    pub fn hand_over(&mut self) -> Option<ProcessId> {
+        if self.owner.is_some() {
+            return None;
+        }
+        let (id, ()) = self.waiting.pop()?;
+        self.owner = Some(id);
+        self.lock_count = 1;
+        Some(id)
+    }
+}
+
+impl IntraObject for Mutex {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn cancel_wait(&mut self, id: ProcessId) {
+        self.waiting.remove(id);
+    }
+}
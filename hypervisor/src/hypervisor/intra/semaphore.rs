@@ -0,0 +1,84 @@
+//! Counting semaphores, which synchronize the processes of a partition
+use a653rs::bindings::{ErrorReturnCode, Priority, ProcessId, QueuingDiscipline, SemaphoreValue};
+
+use super::{IntraObject, WaitQueue};
+
+/// Maximum number of semaphores of a partition
+pub(crate) const MAX_SEMAPHORES: usize = 256;
+
+/// A counting semaphore
+///
+/// Processes wait on a semaphore, whose value is zero, until it is signaled.
+#[derive(Debug)]
+pub(crate) struct Semaphore {
+    name: String,
+    current_value: SemaphoreValue,
+    maximum_value: SemaphoreValue,
+    waiting: WaitQueue,
+}
+
+impl Semaphore {
+    pub fn new(
+        name: String,
+        current_value: SemaphoreValue,
+        maximum_value: SemaphoreValue,
+        discipline: QueuingDiscipline,
+    ) -> Self {
+        Self {
+            name,
+            current_value,
+            maximum_value,
+            waiting: WaitQueue::new(discipline),
+        }
+    }
+
+    pub fn current_value(&self) -> SemaphoreValue {
+        self.current_value
+    }
+
+    pub fn maximum_value(&self) -> SemaphoreValue {
+        self.maximum_value
+    }
+
+    pub fn waiting_processes(&self) -> usize {
+        self.waiting.len()
+    }
+
+    /// Decrements the value of the semaphore. Returns whether this was
+    /// possible without waiting.
+    pub fn try_wait(&mut self) -> bool {
+        if self.current_value == 0 {
+            return false;
+        }
+        self.current_value -= 1;
+        true
+    }
+
+    /// Lets a process wait for the semaphore to be signaled
+    pub fn wait(&mut self, id: ProcessId, priority: Priority) {
+        self.waiting.push(id, priority, ())
+    }
+
+    /// Signals the semaphore. Returns the first waiting process, which takes
+    /// the signal right away.
+    pub fn signal(&mut self) -> Result<Option<ProcessId>, ErrorReturnCode> {
+        if let Some((id, ())) = self.waiting.pop() {
+            return Ok(Some(id));
+        }
+        if self.current_value >= self.maximum_value {
+            return Err(ErrorReturnCode::NoAction);
+        }
+        self.current_value += 1;
+        Ok(None)
+    }
+}
+
+impl IntraObject for Semaphore {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn cancel_wait(&mut self, id: ProcessId) {
+        self.waiting.remove(id);
+    }
+}
@@ -1,52 +1,123 @@
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::thread::sleep;
 use std::time::{Duration, Instant};
 
 use a653rs::bindings::PartitionId;
-use anyhow::anyhow;
+use a653rs::prelude::StartCondition;
+use anyhow::{anyhow, bail, Context};
+use itertools::Itertools;
 use once_cell::sync::OnceCell;
 
 use a653rs_linux_core::cgroup::CGroup;
 use a653rs_linux_core::error::{ErrorLevel, LeveledResult, ResultExt, SystemError, TypedResultExt};
 use a653rs_linux_core::file::TempFile;
+use a653rs_linux_core::queuing::Queuing;
 use a653rs_linux_core::sampling::Sampling;
 
 use crate::hypervisor::config::{Channel, Config};
+use crate::hypervisor::control::{
+    ChannelKind, ChannelStatus, ControlSocket, LogLine, ModuleStatus, QueueStatus, Request,
+    Response, ScheduleStatus,
+};
 use crate::hypervisor::partition::Partition;
 use crate::hypervisor::scheduler::{Scheduler, Timeout};
 
+pub mod cleanup;
 pub mod config;
+pub mod control;
+pub mod intra;
 pub mod partition;
 pub mod process;
+pub mod recorder;
 pub mod rpc;
 pub mod scheduler;
+pub mod shutdown;
 pub mod syscall;
+pub mod timeline;
+pub mod validate;
 
 pub static SYSTEM_START_TIME: OnceCell<TempFile<Instant>> = OnceCell::new();
 
+/// Interval in which the partitions are checked for having exited during a
+/// shutdown
+const SHUTDOWN_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Leaf cgroup of the hypervisor process next to the cgroups of the
+/// partitions, as a cgroup containing processes may not enable controllers for
+/// its sub-cgroups
+pub(crate) const HYPERVISOR_CGROUP: &str = "hypervisor";
+
 //#[derive(Debug)]
 pub struct Hypervisor {
     cg: CGroup,
-    major_frame: Duration,
+    /// Cgroup of the hypervisor process itself
+    own_cg: CGroup,
     scheduler: Scheduler,
     partitions: HashMap<PartitionId, Partition>,
     sampling_channel: HashMap<String, Sampling>,
+    queuing_channel: HashMap<String, Queuing>,
     prev_cg: PathBuf,
-    _config: Config,
+    config: Config,
     terminate_after: Option<Duration>,
     t0: Option<Instant>,
+    control: Option<ControlSocket>,
+    /// Whether the scheduling of partitions is paused
+    paused: bool,
+    /// Number of major frames since the first run
+    major_frames: u64,
 }
 
 impl Hypervisor {
     pub fn new(config: Config, terminate_after: Option<Duration>) -> LeveledResult<Self> {
+        let mut hv = Self::create(config, terminate_after)?;
+        hv.add_partitions(StartCondition::NormalStart)?;
+
+        Ok(hv)
+    }
+
+    /// Resets the module
+    ///
+    /// All partitions and cgroups of this hypervisor are torn down and the
+    /// channels are recreated. Depending on [Config::preserve_channels], the
+    /// messages of the previous channels are either discarded or copied over.
+    /// Afterwards all partitions are started again with
+    /// [StartCondition::HmModuleRestart].
+    pub fn reset(mut self) -> LeveledResult<Self> {
+        info!("Resetting module");
+        let config = self.config.clone();
+        let terminate_after = self.terminate_after;
+        let t0 = self.t0;
+        let control = self.control.take();
+        let (paused, major_frames) = (self.paused, self.major_frames);
+        let sampling = std::mem::take(&mut self.sampling_channel);
+        let queuing = std::mem::take(&mut self.queuing_channel);
+        drop(self);
+
+        let mut hv = Self::create(config, terminate_after)?;
+        hv.t0 = t0;
+        hv.control = control;
+        hv.paused = paused;
+        hv.major_frames = major_frames;
+        if hv.config.preserve_channels {
+            hv.restore_channels(&sampling, &queuing)?;
+        }
+        hv.add_partitions(StartCondition::HmModuleRestart)?;
+
+        Ok(hv)
+    }
+
+    /// Creates the cgroup and channels of the hypervisor, without any
+    /// partitions
+    fn create(config: Config, terminate_after: Option<Duration>) -> LeveledResult<Self> {
         // Init SystemTime
         SYSTEM_START_TIME
             .get_or_try_init(|| TempFile::create("system_time").lev(ErrorLevel::ModuleInit))?;
 
         let prev_cg = PathBuf::from(config.cgroup.parent().unwrap());
 
-        let schedule = config.generate_schedule().lev(ErrorLevel::ModuleInit)?;
+        config.check_hm_tables().lev(ErrorLevel::ModuleInit)?;
+        let schedules = config.generate_schedules().lev(ErrorLevel::ModuleInit)?;
         let pid = std::process::id();
         let file_name = config.cgroup.file_name().unwrap().to_str().unwrap();
         let cg_name = format!("{file_name}-{pid}");
@@ -54,40 +125,128 @@ impl Hypervisor {
             .typ(SystemError::CGroup)
             .lev(ErrorLevel::ModuleInit)?;
 
+        // The resource limits of the partitions require their controllers to
+        // be enabled along the path to the cgroups of the partitions. Only the
+        // cgroup of the hypervisor itself is changed, the previous cgroup has
+        // to delegate the controllers to it.
+        let controllers = config
+            .partitions
+            .iter()
+            .flat_map(|p| p.cgroup_controllers())
+            .unique()
+            .collect_vec();
+        if !controllers.is_empty() {
+            let enabled = cg.controllers().and_then(|delegated| {
+                let missing = controllers
+                    .iter()
+                    .filter(|c| !delegated.iter().any(|d| d == *c))
+                    .join(", ");
+                if !missing.is_empty() {
+                    bail!("the {missing} controllers are not delegated by {prev_cg:?}");
+                }
+                cg.enable_controllers(&controllers)
+            });
+            if let Err(e) = enabled {
+                if let Err(e) = cg.rm() {
+                    error!("{e}")
+                }
+                return Err(e)
+                    .with_context(|| {
+                        format!(
+                            "failed to enable the {} controllers",
+                            controllers.join(", ")
+                        )
+                    })
+                    .lev_typ(SystemError::CGroup, ErrorLevel::ModuleInit);
+            }
+        }
+        let own_cg = cg
+            .new(HYPERVISOR_CGROUP)
+            .typ(SystemError::CGroup)
+            .lev(ErrorLevel::ModuleInit)?;
+
         let mut hv = Self {
             cg,
-            scheduler: Scheduler::new(schedule),
-            major_frame: config.major_frame,
+            own_cg,
+            scheduler: Scheduler::new(schedules),
             partitions: Default::default(),
             prev_cg,
-            _config: config.clone(),
+            config: config.clone(),
             sampling_channel: Default::default(),
+            queuing_channel: Default::default(),
             terminate_after,
             t0: None,
+            control: None,
+            paused: false,
+            major_frames: 0,
         };
 
         for c in config.channel {
             hv.add_channel(c)?;
         }
 
-        for p in config.partitions.iter() {
-            if hv.partitions.contains_key(&p.id) {
+        Ok(hv)
+    }
+
+    fn add_partitions(&mut self, condition: StartCondition) -> LeveledResult<()> {
+        for p in self.config.partitions.iter() {
+            if self.partitions.contains_key(&p.id) {
                 return Err(anyhow!("Partition \"{}\" already exists", p.name))
                     .lev_typ(SystemError::PartitionConfig, ErrorLevel::ModuleInit);
             }
-            hv.partitions.insert(
+            recorder::record(|r| r.partition(p.id, &p.name));
+            self.partitions.insert(
                 p.id,
-                Partition::new(hv.cg.get_path(), p.clone(), &hv.sampling_channel)
-                    .lev(ErrorLevel::ModuleInit)?,
+                Partition::new(
+                    self.cg.get_path(),
+                    p.clone(),
+                    &self.sampling_channel,
+                    &self.queuing_channel,
+                    self.config.partition_timing(p),
+                    self.scheduler.status(),
+                    condition,
+                )
+                .lev(ErrorLevel::ModuleInit)?,
             );
         }
 
-        Ok(hv)
+        Ok(())
+    }
+
+    /// Copies the messages of the previous channels into the current ones
+    fn restore_channels(
+        &mut self,
+        sampling: &HashMap<String, Sampling>,
+        queuing: &HashMap<String, Queuing>,
+    ) -> LeveledResult<()> {
+        for (name, channel) in self.sampling_channel.iter_mut() {
+            if let Some(previous) = sampling.get(name) {
+                channel.restore(previous).lev(ErrorLevel::ModuleInit)?;
+            }
+        }
+        for (name, channel) in self.queuing_channel.iter_mut() {
+            if let Some(previous) = queuing.get(name) {
+                channel.restore(previous);
+            }
+        }
+
+        Ok(())
     }
 
     fn add_channel(&mut self, channel: Channel) -> LeveledResult<()> {
         match channel {
-            Channel::Queuing(_) => todo!(),
+            Channel::Queuing(q) => {
+                if self.queuing_channel.contains_key(&q.source.name()) {
+                    return Err(anyhow!(
+                        "Queuing Channel \"{}\" already exists",
+                        q.source.name()
+                    ))
+                    .lev_typ(SystemError::PartitionConfig, ErrorLevel::ModuleInit);
+                }
+
+                let queuing = Queuing::try_from(q).lev(ErrorLevel::ModuleInit)?;
+                self.queuing_channel.insert(queuing.name(), queuing);
+            }
             Channel::Sampling(s) => {
                 if self.sampling_channel.contains_key(&s.name().to_string()) {
                     return Err(anyhow!("Sampling Channel \"{}\" already exists", s.name()))
@@ -102,8 +261,20 @@ impl Hypervisor {
         Ok(())
     }
 
-    pub fn run(mut self) -> LeveledResult<()> {
-        self.cg
+    /// Listens for requests on a control socket at `path` while running
+    pub fn listen(&mut self, path: &Path) -> LeveledResult<()> {
+        info!("Listening for control requests on {path:?}");
+        self.control = Some(ControlSocket::bind(path).lev(ErrorLevel::ModuleInit)?);
+        Ok(())
+    }
+
+    /// Runs the partitions according to the schedule
+    ///
+    /// Returns `Ok` once the module was shut down, either by a signal, through
+    /// the control socket or after the configured run-time. The cgroups are
+    /// torn down once the hypervisor is dropped.
+    pub fn run(&mut self) -> LeveledResult<()> {
+        self.own_cg
             .mv_proc(nix::unistd::getpid())
             .typ(SystemError::CGroup)
             .lev(ErrorLevel::ModuleInit)?;
@@ -123,18 +294,23 @@ impl Hypervisor {
         let mut frame_start = Instant::now();
 
         // retain the first frame start as our sytems t0
-        let t0 = self.t0.unwrap_or(frame_start);
+        let first_run = self.t0.is_none();
+        let t0 = *self.t0.get_or_insert(frame_start);
 
         let terminate_after_timeout = self
             .terminate_after
             .map(|duration| Timeout::new(t0, duration));
 
-        let sys_time = SYSTEM_START_TIME
-            .get()
-            .ok_or_else(|| anyhow!("SystemTime was not set"))
-            .lev_typ(SystemError::Panic, ErrorLevel::ModuleInit)?;
-        sys_time.write(&frame_start).lev(ErrorLevel::ModuleInit)?;
-        sys_time.seal_read_only().lev(ErrorLevel::ModuleInit)?;
+        // The system time keeps running across module resets, as it is sealed
+        // after the first run
+        if first_run {
+            let sys_time = SYSTEM_START_TIME
+                .get()
+                .ok_or_else(|| anyhow!("SystemTime was not set"))
+                .lev_typ(SystemError::Panic, ErrorLevel::ModuleInit)?;
+            sys_time.write(&frame_start).lev(ErrorLevel::ModuleInit)?;
+            sys_time.seal_read_only().lev(ErrorLevel::ModuleInit)?;
+        }
         loop {
             // terminate hypervisor now if timeout is over
             if let Some(timeout) = &terminate_after_timeout {
@@ -143,19 +319,253 @@ impl Hypervisor {
                         "quitting, as a run-time of {} was reached",
                         humantime::Duration::from(timeout.total_duration())
                     );
-                    quit::with_code(0)
+                    return self.shutdown(frame_start);
+                }
+            }
+
+            let paused = self.paused;
+            if !paused {
+                self.scheduler.run_major_frame(
+                    frame_start,
+                    &mut self.partitions,
+                    &mut self.sampling_channel,
+                    &mut self.queuing_channel,
+                )?;
+            }
+            if shutdown::requested() {
+                return self.shutdown(frame_start);
+            }
+
+            // Control requests are served in the remaining time of the major
+            // frame
+            let frame_end = frame_start + self.scheduler.major_frame();
+            if self.serve_control(frame_start, frame_end)? {
+                info!("Shutting down on request of the control socket");
+                return self.shutdown(frame_start);
+            }
+            let interrupted = shutdown::sleep_until(frame_end);
+            if !paused {
+                recorder::record(|r| {
+                    r.major_frame(self.scheduler.name(), frame_start)?;
+                    r.flush()
+                });
+            }
+            if interrupted {
+                return self.shutdown(frame_start);
+            }
+
+            self.major_frames += 1;
+            frame_start = frame_end;
+        }
+    }
+
+    /// Shuts the module down in between windows
+    ///
+    /// All partitions are asked to exit and are granted
+    /// [Config::shutdown_grace_period] to do so, before the final statistics
+    /// are logged.
+    fn shutdown(&mut self, frame_start: Instant) -> LeveledResult<()> {
+        let grace_period = self.config.shutdown_grace_period;
+        info!(
+            "Shutting down, granting the partitions {} to exit",
+            humantime::Duration::from(grace_period)
+        );
+        for partition in self.partitions.values() {
+            if let Err(e) = partition.terminate() {
+                error!("{e}")
+            }
+        }
+
+        let deadline = Instant::now() + grace_period;
+        loop {
+            let running = self
+                .partitions
+                .values()
+                .filter(|p| p.populated().unwrap_or(false))
+                .map(|p| p.name())
+                .sorted()
+                .collect_vec();
+            if running.is_empty() {
+                break;
+            }
+            if Instant::now() >= deadline {
+                warn!(
+                    "Killing partitions, which did not exit in time: {}",
+                    running.join(", ")
+                );
+                break;
+            }
+            sleep(SHUTDOWN_POLL_INTERVAL);
+        }
+
+        self.log_stats(frame_start);
+        Ok(())
+    }
+
+    /// Logs the statistics collected since the first run
+    fn log_stats(&mut self, frame_start: Instant) {
+        let status = self.status(frame_start);
+        let uptime = Duration::from_millis(status.schedule.uptime.as_millis() as u64);
+        info!(
+            "Ran {} major frames in {}",
+            status.schedule.major_frames,
+            humantime::Duration::from(uptime)
+        );
+        for p in &status.partitions {
+            info!(
+                "Partition \"{}\": {} mode, {} processes",
+                p.name,
+                p.mode,
+                p.processes.len()
+            );
+        }
+        for c in &status.channels {
+            info!(
+                "Channel \"{}\": {} messages transferred",
+                c.name, c.transferred
+            );
+        }
+    }
+
+    /// Serves the requests of the control socket until `deadline`. Returns
+    /// whether a shutdown was requested.
+    fn serve_control(&mut self, frame_start: Instant, deadline: Instant) -> LeveledResult<bool> {
+        let Some(mut control) = self.control.take() else {
+            return Ok(false);
+        };
+        self.publish_logs(&mut control);
+        let res = self.serve_requests(&mut control, frame_start, deadline);
+        self.control = Some(control);
+        res
+    }
+
+    fn serve_requests(
+        &mut self,
+        control: &mut ControlSocket,
+        frame_start: Instant,
+        deadline: Instant,
+    ) -> LeveledResult<bool> {
+        while let Some(mut client) = control.next(deadline).lev(ErrorLevel::ModuleRun)? {
+            debug!("Control request: {:?}", client.request);
+            let response = match &client.request {
+                Request::Status => Response::Status(self.status(frame_start)),
+                Request::Partition { name, action } => {
+                    match self.partitions.values_mut().find(|p| p.name() == name) {
+                        None => Response::Error(format!("unknown partition \"{name}\"")),
+                        Some(partition) => {
+                            info!("Forcing partition \"{name}\" to {action:?}");
+                            match partition.force_mode(*action) {
+                                Ok(()) => Response::Ok,
+                                Err(e) => {
+                                    error!("{e}");
+                                    Response::Error(e.to_string())
+                                }
+                            }
+                        }
+                    }
+                }
+                Request::Pause => {
+                    info!("Pausing after the current major frame");
+                    self.paused = true;
+                    Response::Ok
+                }
+                Request::Resume => {
+                    info!("Resuming with the next major frame");
+                    self.paused = false;
+                    Response::Ok
+                }
+                Request::Shutdown => {
+                    client.respond(&Response::Ok);
+                    return Ok(true);
+                }
+                Request::TailLog { partition } => {
+                    let partition = partition.clone();
+                    match self.partitions.values_mut().find(|p| p.name() == partition) {
+                        None => Response::Error(format!("unknown partition \"{partition}\"")),
+                        Some(p) => {
+                            p.capture_log(true);
+                            client.respond(&Response::Ok);
+                            control.subscribe(client, &partition);
+                            continue;
+                        }
+                    }
                 }
+            };
+            client.respond(&response);
+            if Instant::now() >= deadline {
+                break;
             }
+        }
+        Ok(false)
+    }
 
-            self.scheduler.run_major_frame(
-                frame_start,
-                &mut self.partitions,
-                &mut self.sampling_channel,
-            )?;
+    /// Sends the log messages captured during the last major frame to the
+    /// clients of the control socket
+    fn publish_logs(&mut self, control: &mut ControlSocket) {
+        for partition in self.partitions.values_mut() {
+            for (level, message) in partition.take_log() {
+                control.publish(&LogLine {
+                    partition: partition.name().to_string(),
+                    level: level.to_string(),
+                    message,
+                });
+            }
+            // Stop capturing once all clients are gone
+            if !control.subscribed(partition.name()) {
+                partition.capture_log(false);
+            }
+        }
+    }
 
-            sleep(self.major_frame.saturating_sub(frame_start.elapsed()));
+    fn status(&mut self, frame_start: Instant) -> ModuleStatus {
+        let t0 = self.t0.unwrap_or(frame_start);
+        let schedule = ScheduleStatus {
+            current: self.scheduler.name().to_string(),
+            next: self.scheduler.next_name().to_string(),
+            major_frame: self.scheduler.major_frame(),
+            major_frames: self.major_frames,
+            time_in_frame: frame_start.elapsed(),
+            uptime: t0.elapsed(),
+        };
+
+        let partitions = self
+            .partitions
+            .values()
+            .map(Partition::status)
+            .sorted_by_key(|p| p.id)
+            .collect();
+
+        let sampling = self.sampling_channel.values().map(|s| ChannelStatus {
+            name: s.name(),
+            kind: ChannelKind::Sampling,
+            msg_size: s.msg_size(),
+            transferred: s.transferred(),
+            queue: None,
+        });
+        let queuing = self.queuing_channel.values_mut().map(|q| {
+            let (source, destination) = q.queued();
+            ChannelStatus {
+                name: q.name(),
+                kind: ChannelKind::Queuing,
+                msg_size: q.msg_size(),
+                transferred: q.transferred(),
+                queue: Some(QueueStatus {
+                    msg_num: q.msg_num(),
+                    source,
+                    destination,
+                }),
+            }
+        });
+        let channels = sampling
+            .chain(queuing)
+            .sorted_by(|a, b| a.name.cmp(&b.name))
+            .collect();
 
-            frame_start += self.major_frame;
+        ModuleStatus {
+            paused: self.paused,
+            schedule,
+            partitions,
+            channels,
         }
     }
 }
@@ -194,6 +604,7 @@ impl Drop for Hypervisor {
         if let Err(e) = self.cg.rm() {
             error!("{e}")
         }
+        recorder::record(|r| r.flush());
         trace!("Hypervisor clean up took: {:?}", now.elapsed())
     }
 }
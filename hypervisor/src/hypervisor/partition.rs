@@ -1,44 +1,78 @@
-use std::collections::HashMap;
+use std::cell::RefCell;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, VecDeque};
+use std::fs::File;
 use std::net::{TcpStream, UdpSocket};
+use std::os::fd::AsFd;
+use std::os::unix::fs::FileExt;
+use std::os::unix::net::UnixDatagram;
 use std::os::unix::prelude::{AsRawFd, FromRawFd, OwnedFd, PermissionsExt, RawFd};
 use std::os::unix::process::CommandExt;
 use std::path::{self, Path, PathBuf};
 use std::process::{Command, Stdio};
+use std::rc::Rc;
 use std::thread::sleep;
 use std::time::{Duration, Instant};
 
-use a653rs::bindings::{PartitionId, PortDirection};
-use a653rs::prelude::{OperatingMode, StartCondition};
+use a653rs::bindings::{
+    Deadline, ErrorCode, ErrorReturnCode, MutexId, PartitionId, PortDirection, ProcessId,
+    ProcessState, QueuingDiscipline,
+};
+use a653rs::prelude::{OperatingMode, StartCondition, SystemTime};
 use anyhow::{anyhow, Context};
 use bytesize::ByteSize;
 use clone3::Clone3;
 use itertools::Itertools;
+use log::Level;
+use memfd::{FileSeal, MemfdOptions};
 use nix::mount::{umount2, MntFlags};
 use nix::unistd::{chdir, close, getpid, pivot_root, setgid, setuid, Gid, Pid, Uid};
 use polling::{Event, Events, Poller};
 use procfs::process::Process;
-use tempfile::{tempdir, TempDir};
+use tempfile::TempDir;
 
 use a653rs_linux_core::cgroup::{self, CGroup};
 use a653rs_linux_core::error::{
     ErrorLevel, LeveledResult, ResultExt, SystemError, TypedError, TypedResult, TypedResultExt,
 };
 use a653rs_linux_core::file::TempFile;
-use a653rs_linux_core::health::{ModuleRecoveryAction, PartitionHMTable, RecoveryAction};
+use a653rs_linux_core::health::{
+    HMLevel, ModuleRecoveryAction, PartitionHMTable, ProcessHMTable, RecoveryAction,
+};
 use a653rs_linux_core::health_event::PartitionCall;
 use a653rs_linux_core::ipc::{bind_receiver, io_pair, IoReceiver, IoSender, IpcReceiver};
-use a653rs_linux_core::partition::{PartitionConstants, SamplingConstant};
+use a653rs_linux_core::partition::{PartitionConstants, QueuingConstant, SamplingConstant};
+use a653rs_linux_core::queuing::Queuing;
 use a653rs_linux_core::sampling::Sampling;
+use a653rs_linux_core::syscall::SYSCALL_SOCKET_PATH;
 pub use mounting::FileMounter;
 
-use crate::hypervisor::config::Partition as PartitionConfig;
+use crate::hypervisor::cleanup::WORKING_DIR_PREFIX;
+use crate::hypervisor::config::{Partition as PartitionConfig, PartitionTiming};
+use crate::hypervisor::control::{PartitionAction, PartitionStatus, ProcessStatus};
+use crate::hypervisor::recorder;
 use crate::hypervisor::SYSTEM_START_TIME;
 use crate::problem;
 
 use super::config::PosixSocket;
-use super::scheduler::Timeout;
+use super::intra::{
+    self, Blackboard, Buffer, Mutex, ObjectTable, Semaphore, MAX_BLACKBOARDS, MAX_BUFFERS,
+    MAX_EVENTS, MAX_MUTEXES, MAX_SEMAPHORES,
+};
+use super::process::{ProcessAttributes, ProcessError, ProcessTable, ERROR_HANDLER_PRIORITY};
+use super::scheduler::{ModuleScheduleStatus, Timeout};
+use super::syscall::{Syscall, SyscallOutput};
+use queuing::QueuingPort;
 
+mod apex;
 mod mounting;
+mod queuing;
+
+/// Maximum number of errors queued for the error handler of a partition
+const MAX_QUEUED_ERRORS: usize = 64;
+/// Maximum number of log messages captured for the control socket in between
+/// two major frames
+const MAX_CAPTURED_LOG: usize = 1024;
 
 #[derive(Debug, Clone, Copy)]
 pub enum TransitionAction {
@@ -51,18 +85,31 @@ pub enum TransitionAction {
 // Struct for holding information of a partition which is not in Idle Mode
 #[derive(Debug)]
 pub(crate) struct Run {
+    cgroup_processes: CGroup,
     _cgroup_main: CGroup,
-    cgroup_aperiodic: CGroup,
-    cgroup_periodic: CGroup,
-
-    _main: Pid,
-    periodic: bool,
-    aperiodic: bool,
+    cgroup_stopped: CGroup,
+
+    main: Pid,
+    processes: ProcessTable,
+    buffers: ObjectTable<Buffer>,
+    blackboards: ObjectTable<Blackboard>,
+    semaphores: ObjectTable<Semaphore>,
+    events: ObjectTable<intra::Event>,
+    mutexes: ObjectTable<Mutex>,
+    /// Queuing ports, on which processes waited so far, by their name
+    queuing_ports: HashMap<String, QueuingPort>,
+    error_handler: Option<ProcessId>,
+    /// Errors of processes, which the error handler did not fetch yet
+    errors: VecDeque<ProcessError>,
+    /// Whether the health monitor escalated an error of a process to the
+    /// module level
+    module_error: bool,
 
     mode: OperatingMode,
     _mode_file_fd: OwnedFd,
     mode_file: TempFile<OperatingMode>,
     call_rx: IpcReceiver<PartitionCall>,
+    syscall_rx: UnixDatagram,
     // We need to keep the struct for the sender's side, so
     // the sockets currently in transmission are not closed
     // before the partition has received them.
@@ -80,12 +127,10 @@ impl Run {
         let cgroup_main = cgroup_processes
             .new_threaded(PartitionConstants::MAIN_PROCESS_CGROUP)
             .typ(SystemError::CGroup)?;
-        let cgroup_periodic = cgroup_processes
-            .new_threaded(PartitionConstants::PERIODIC_PROCESS_CGROUP)
-            .typ(SystemError::CGroup)?;
-        let cgroup_aperiodic = cgroup_processes
-            .new_threaded(PartitionConstants::APERIODIC_PROCESS_CGROUP)
+        let cgroup_stopped = cgroup_processes
+            .new_threaded(PartitionConstants::STOPPED_PROCESS_CGROUP)
             .typ(SystemError::CGroup)?;
+        cgroup_stopped.freeze().typ(SystemError::CGroup)?;
 
         let real_uid = nix::unistd::getuid();
         let real_gid = nix::unistd::getgid();
@@ -102,6 +147,17 @@ impl Run {
         std::fs::create_dir_all(ipc_path.parent().unwrap()).typ(SystemError::Panic)?;
         let call_rx = bind_receiver::<PartitionCall>(&ipc_path)?;
 
+        let syscall_path = base
+            .working_dir
+            .path()
+            .join(SYSCALL_SOCKET_PATH.trim_start_matches('/'));
+        // Remove the socket of a previous run of this partition
+        if syscall_path.exists() {
+            std::fs::remove_file(&syscall_path).typ(SystemError::Panic)?;
+        }
+        let syscall_rx = UnixDatagram::bind(&syscall_path).typ(SystemError::Panic)?;
+        syscall_rx.set_nonblocking(true).typ(SystemError::Panic)?;
+
         // TODO add a `::new(warm_start: bool)->Self` function to `OperatingMode`, use
         // it here
         let mode = if warm_start {
@@ -109,6 +165,10 @@ impl Run {
         } else {
             OperatingMode::ColdStart
         };
+        if !warm_start {
+            base.clear_persistent_memory()?;
+        }
+
         let mode_file = TempFile::create("operation_mode")?;
         let mode_file_fd = unsafe { OwnedFd::from_raw_fd(mode_file.as_raw_fd()) };
         mode_file.write(&mode)?;
@@ -159,19 +219,22 @@ impl Run {
                 // Release all unneeded fd's
 
                 let mut keep = base.sampling_fds();
+                keep.extend(base.queuing_fds());
                 keep.push(sys_time.as_raw_fd());
                 keep.push(mode_file.as_raw_fd());
                 keep.push(udp_io_rx.as_raw_fd());
                 keep.push(tcp_io_rx.as_raw_fd());
+                keep.extend(base.persistent_memory.as_ref().map(|m| m.as_raw_fd()));
 
                 Partition::release_fds(&keep).unwrap();
 
                 let ipc_path_inner: PathBuf = PartitionConstants::IPC_SENDER[1..].into();
+                let syscall_path_inner: PathBuf = SYSCALL_SOCKET_PATH[1..].into();
 
                 // Mount the required mounts
                 let mut mounts = vec![
                     // Mount working directory as tmpfs
-                    FileMounter::tmpfs("", ByteSize::kb(500)),
+                    FileMounter::tmpfs("", base.tmpfs_size),
                     // Mount binary
                     FileMounter::bind_ro(&base.bin, "/bin").unwrap(),
                     // Mount /dev/null (for stdio::null)
@@ -182,6 +245,8 @@ impl Run {
                     FileMounter::cgroup(),
                     // IPC Socket for Syscalls
                     FileMounter::bind_rw(ipc_path, ipc_path_inner).unwrap(),
+                    // Socket for system calls
+                    FileMounter::bind_rw(syscall_path, syscall_path_inner).unwrap(),
                 ];
 
                 for (source, target) in base.mounts.iter().cloned() {
@@ -224,12 +289,19 @@ impl Run {
                     start_time_fd: sys_time.as_raw_fd(),
                     partition_mode_fd: mode_file.as_raw_fd(),
                     io_fd: udp_io_rx.as_raw_fd(),
+                    persistent_memory_fd: base.persistent_memory.as_ref().map(|m| m.as_raw_fd()),
                     sampling: base
                         .sampling_channel
                         .clone()
                         .drain()
                         .map(|(_, s)| s)
                         .collect_vec(),
+                    queuing: base
+                        .queuing_channel
+                        .clone()
+                        .drain()
+                        .map(|(_, q)| q)
+                        .collect_vec(),
                 }
                 .try_into()
                 .unwrap();
@@ -272,17 +344,26 @@ impl Run {
         let pid = Pid::from_raw(pid);
 
         Ok(Run {
+            cgroup_processes,
             _cgroup_main: cgroup_main,
-            cgroup_aperiodic,
-            cgroup_periodic,
-            _main: pid,
+            cgroup_stopped,
+            main: pid,
+            processes: ProcessTable::default(),
+            buffers: ObjectTable::new(MAX_BUFFERS),
+            blackboards: ObjectTable::new(MAX_BLACKBOARDS),
+            semaphores: ObjectTable::new(MAX_SEMAPHORES),
+            events: ObjectTable::new(MAX_EVENTS),
+            mutexes: ObjectTable::new(MAX_MUTEXES),
+            queuing_ports: HashMap::new(),
+            error_handler: None,
+            errors: VecDeque::new(),
+            module_error: false,
             mode,
             mode_file,
             call_rx,
+            syscall_rx,
             _io_udp_tx: udp_io_tx,
             _io_tcp_tx: tcp_io_tx,
-            periodic: false,
-            aperiodic: false,
             _mode_file_fd: mode_file_fd,
         })
     }
@@ -291,50 +372,304 @@ impl Run {
         self.mode
     }
 
+    /// PID of the main process of the partition
+    pub fn main_pid(&self) -> Pid {
+        self.main
+    }
+
     pub fn receiver(&self) -> &IpcReceiver<PartitionCall> {
         &self.call_rx
     }
 
-    pub fn unfreeze_aperiodic(&self) -> TypedResult<bool> {
-        if self.aperiodic {
-            self.cgroup_aperiodic.unfreeze().typ(SystemError::CGroup)?;
-            return Ok(true);
+    pub fn syscall_receiver(&self) -> &UnixDatagram {
+        &self.syscall_rx
+    }
+
+    pub fn processes(&self) -> &ProcessTable {
+        &self.processes
+    }
+
+    pub fn processes_mut(&mut self) -> &mut ProcessTable {
+        &mut self.processes
+    }
+
+    pub fn buffers(&self) -> &ObjectTable<Buffer> {
+        &self.buffers
+    }
+
+    pub fn buffers_mut(&mut self) -> &mut ObjectTable<Buffer> {
+        &mut self.buffers
+    }
+
+    pub fn blackboards(&self) -> &ObjectTable<Blackboard> {
+        &self.blackboards
+    }
+
+    pub fn blackboards_mut(&mut self) -> &mut ObjectTable<Blackboard> {
+        &mut self.blackboards
+    }
+
+    pub fn semaphores(&self) -> &ObjectTable<Semaphore> {
+        &self.semaphores
+    }
+
+    pub fn semaphores_mut(&mut self) -> &mut ObjectTable<Semaphore> {
+        &mut self.semaphores
+    }
+
+    pub fn events(&self) -> &ObjectTable<intra::Event> {
+        &self.events
+    }
+
+    pub fn events_mut(&mut self) -> &mut ObjectTable<intra::Event> {
+        &mut self.events
+    }
+
+    pub fn mutexes(&self) -> &ObjectTable<Mutex> {
+        &self.mutexes
+    }
+
+    pub fn mutexes_mut(&mut self) -> &mut ObjectTable<Mutex> {
+        &mut self.mutexes
+    }
+
+    /// Returns the queuing port `name` of the partition, whose waiting
+    /// processes are woken up in the order of `discipline`
+    pub fn queuing_port(
+        &mut self,
+        base: &Base,
+        name: &str,
+        discipline: QueuingDiscipline,
+    ) -> TypedResult<Option<&mut QueuingPort>> {
+        let Some(constant) = base.queuing_channel.values().find(|q| q.name == name) else {
+            return Ok(None);
+        };
+        let port = match self.queuing_ports.entry(name.to_string()) {
+            Entry::Occupied(port) => port.into_mut(),
+            Entry::Vacant(entry) => entry.insert(QueuingPort::new(constant, discipline)?),
+        };
+        Ok(Some(port))
+    }
+
+    /// Wakes up the processes waiting on queuing ports, which they may now
+    /// access, so that they try again
+    pub fn wake_queuing_ports(&mut self) -> TypedResult<()> {
+        for port in self.queuing_ports.values_mut() {
+            for id in port.ready() {
+                if let Some(process) = self.processes.get_mut(id) {
+                    process.wake(Ok(SyscallOutput::default()))?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Creates a new process, which resides in its own frozen cgroup
+    pub fn create_process(
+        &mut self,
+        attr: ProcessAttributes,
+    ) -> TypedResult<Result<ProcessId, ErrorReturnCode>> {
+        self.processes.create(&self.cgroup_processes, attr)
+    }
+
+    pub fn error_handler(&self) -> Option<ProcessId> {
+        self.error_handler
+    }
+
+    /// Creates the error handler, a dormant aperiodic process, which runs with
+    /// a higher priority than all other processes
+    pub fn create_error_handler(&mut self) -> TypedResult<Result<ProcessId, ErrorReturnCode>> {
+        if self.error_handler.is_some() {
+            return Ok(Err(ErrorReturnCode::NoAction));
+        }
+        let attr = ProcessAttributes {
+            name: PartitionConstants::ERROR_HANDLER_NAME.to_string(),
+            period: SystemTime::Infinite,
+            time_capacity: SystemTime::Infinite,
+            base_priority: ERROR_HANDLER_PRIORITY,
+            deadline: Deadline::Soft,
+        };
+        let id = self.create_process(attr)?;
+        self.error_handler = id.ok();
+        Ok(id)
+    }
+
+    /// Reports an error of a process
+    ///
+    /// Depending on the process health monitor table, the error is handled at
+    /// process, partition or module level. At process level, the error is
+    /// queued for the error handler, which is activated if it is dormant.
+    /// Without an error handler, and for errors of the error handler itself,
+    /// the error is handled at partition level as `fallback` instead.
+    pub fn report_error(
+        &mut self,
+        base: &Base,
+        error: ProcessError,
+        fallback: SystemError,
+    ) -> TypedResult<()> {
+        let message = String::from_utf8_lossy(&error.message).into_owned();
+        let handler = match base.process_hm().level(error.code) {
+            HMLevel::Process => self
+                .error_handler
+                .filter(|&id| id != error.process && self.processes.get(error.process).is_some()),
+            HMLevel::Partition => None,
+            HMLevel::Module => {
+                self.module_error = true;
+                return Err(TypedError::new(fallback, anyhow!("{message}")));
+            }
+        };
+        let Some(handler) = handler.and_then(|id| self.processes.get_mut(id)) else {
+            return base.raise(TypedError::new(fallback, anyhow!("{message}")));
+        };
+        if self.errors.len() >= MAX_QUEUED_ERRORS {
+            warn!(
+                "Partition \"{}\": Dropped {:?} of process {}, as the error queue is full",
+                base.name(),
+                error.code,
+                error.process
+            );
+            return Ok(());
+        }
+        debug!(
+            "Partition \"{}\": Queued {:?} of process {}",
+            base.name(),
+            error.code,
+            error.process
+        );
+        self.errors.push_back(error);
+
+        if handler.state() == ProcessState::Dormant {
+            handler.set_state(ProcessState::Ready);
+            // A previously activated error handler waits inside STOP_SELF
+            handler.wake(Ok(SyscallOutput::default()))?;
         }
-        Ok(false)
+        Ok(())
     }
 
-    pub fn freeze_aperiodic(&self) -> TypedResult<bool> {
-        if self.aperiodic {
-            self.cgroup_aperiodic.freeze().typ(SystemError::CGroup)?;
-            return Ok(true);
+    /// Removes the oldest error from the queue of the error handler
+    pub fn next_error(&mut self) -> Option<ProcessError> {
+        self.errors.pop_front()
+    }
+
+    pub fn queued_errors(&self) -> usize {
+        self.errors.len()
+    }
+
+    /// Returns whether an error of a process was escalated to the module
+    /// level, and resets this state
+    pub fn take_module_error(&mut self) -> bool {
+        std::mem::take(&mut self.module_error)
+    }
+
+    /// Executes all system calls currently pending on the system call socket
+    ///
+    /// Returns the amount of received system calls
+    pub fn handle_syscalls(&mut self, base: &Base) -> TypedResult<usize> {
+        let mut nsyscalls = 0;
+        while let Some(syscall) =
+            Syscall::try_recv(self.syscall_rx.as_fd()).typ(SystemError::Panic)?
+        {
+            apex::dispatch(base, self, syscall)?;
+            nsyscalls += 1;
         }
-        Ok(false)
+
+        // Processes may have been started, released or made waiting
+        if nsyscalls > 0 && self.mode == OperatingMode::Normal {
+            self.schedule()?;
+        }
+        Ok(nsyscalls)
     }
 
-    pub fn unfreeze_periodic(&self) -> TypedResult<bool> {
-        if self.periodic {
-            self.cgroup_periodic.unfreeze().typ(SystemError::CGroup)?;
-            return Ok(true);
+    /// Stops a process, which becomes dormant
+    pub fn stop_process(&mut self, id: ProcessId) -> TypedResult<()> {
+        if let Some(process) = self.processes.get_mut(id) {
+            process.stop(&self.cgroup_stopped)?;
+        }
+        self.cancel_wait(id);
+
+        // A stopped process releases the mutex it owns
+        if let Some(mutex_id) = self.mutexes.find(|m| m.owner() == Some(id)) {
+            self.mutexes.get_mut(mutex_id).unwrap().reset();
+            self.hand_over_mutex(mutex_id)?;
         }
-        Ok(false)
+        Ok(())
     }
 
-    pub fn periodic_events(&self) -> TypedResult<OwnedFd> {
-        Ok(std::fs::File::open(self.cgroup_periodic.get_events_path())
-            .typ(SystemError::CGroup)?
-            .into())
+    /// Passes a released mutex on to the first waiting process, which then
+    /// runs with the priority of the mutex
+    pub fn hand_over_mutex(&mut self, mutex_id: MutexId) -> TypedResult<()> {
+        let Some(mutex) = self.mutexes.get_mut(mutex_id) else {
+            return Ok(());
+        };
+        let priority = mutex.priority();
+        if let Some(process) = mutex
+            .hand_over()
+            .and_then(|owner| self.processes.get_mut(owner))
+        {
+            process.raise_priority(priority);
+            process.wake(Ok(SyscallOutput::default()))?;
+        }
+        Ok(())
     }
 
-    pub fn is_periodic_frozen(&self) -> TypedResult<bool> {
-        self.cgroup_periodic.frozen().typ(SystemError::CGroup)
+    /// Removes a process, which no longer waits, from the waiting queues of
+    /// all intra-partition communication objects and queuing ports
+    fn cancel_wait(&mut self, id: ProcessId) {
+        self.buffers.cancel_wait(id);
+        self.blackboards.cancel_wait(id);
+        self.semaphores.cancel_wait(id);
+        self.events.cancel_wait(id);
+        self.mutexes.cancel_wait(id);
+        self.queuing_ports
+            .values_mut()
+            .for_each(|port| port.cancel_wait(id));
     }
 
-    pub fn freeze_periodic(&self) -> TypedResult<bool> {
-        if self.periodic {
-            self.cgroup_periodic.freeze().typ(SystemError::CGroup)?;
-            return Ok(true);
+    /// Returns the point in time at which the next wait of a process times
+    /// out
+    pub fn next_timeout(&self) -> Option<Instant> {
+        // Time outs only progress in the normal mode
+        if self.mode != OperatingMode::Normal {
+            return None;
         }
-        Ok(false)
+        self.processes.next_timeout()
+    }
+
+    /// Wakes up all processes, whose wait timed out, and reports missed
+    /// deadlines as errors of the processes
+    pub fn expire_timeouts(&mut self, base: &Base) -> TypedResult<()> {
+        // Time outs only progress in the normal mode
+        if self.mode != OperatingMode::Normal {
+            return Ok(());
+        }
+        let expired = self.processes.expire()?;
+        for &id in &expired {
+            self.cancel_wait(id);
+        }
+        let missed = self.processes.missed_deadlines();
+        for &id in &missed {
+            let process = self.processes.get(id).unwrap();
+            let message = format!(
+                "Process \"{}\" missed its {:?} deadline",
+                process.name(),
+                process.attributes().deadline
+            );
+            let error = ProcessError {
+                code: ErrorCode::DeadlineMissed,
+                process: id,
+                message: message.into_bytes(),
+            };
+            self.report_error(base, error, SystemError::TimeDurationExceeded)?;
+        }
+        if !expired.is_empty() || !missed.is_empty() {
+            self.schedule()?;
+        }
+        Ok(())
+    }
+
+    /// Lets the ready process with the highest priority run
+    pub fn schedule(&mut self) -> TypedResult<()> {
+        self.processes.schedule()
     }
 
     /// Return error if invalid transition was requested
@@ -376,22 +711,18 @@ impl Run {
         }
 
         base.freeze()?;
-
-        if self.cgroup_aperiodic.populated().typ(SystemError::CGroup)? {
-            self.aperiodic = true;
-        }
-
-        if self.cgroup_periodic.populated().typ(SystemError::CGroup)? {
-            self.periodic = true;
-        }
-
-        self.freeze_aperiodic()?;
-        self.freeze_periodic()?;
+        self.processes.freeze()?;
 
         self.mode = OperatingMode::Normal;
         self.mode_file.write(&self.mode)?;
-
-        self.cgroup_aperiodic.unfreeze().typ(SystemError::CGroup)?;
+        recorder::record(|r| r.mode(base.id, self.mode));
+
+        let periodic_start = base
+            .schedules()
+            .borrow()
+            .next_periodic_start(base.id, Instant::now());
+        self.processes.begin_normal_mode(periodic_start)?;
+        self.schedule()?;
         base.unfreeze()?;
         Ok(())
     }
@@ -409,8 +740,11 @@ impl Run {
 
         base.freeze()?;
         base.kill()?;
+        // Remove the cgroups of all processes of the previous run
+        self.cgroup_processes.rm().typ(SystemError::CGroup)?;
 
         *self = Run::new(base, cond, warm_start).typ(SystemError::PartitionInit)?;
+        recorder::record(|r| r.mode(base.id, self.mode));
 
         Ok(())
     }
@@ -422,11 +756,11 @@ impl Run {
         }
 
         base.freeze()?;
-        self.freeze_aperiodic()?;
-        self.freeze_periodic()?;
+        self.processes.freeze()?;
 
         self.mode = OperatingMode::Idle;
         self.mode_file.write(&self.mode)?;
+        recorder::record(|r| r.mode(base.id, self.mode));
 
         Ok(())
     }
@@ -464,15 +798,21 @@ fn send_sockets(base: &Base) -> Result<IoTxRx, a653rs_linux_core::error::TypedEr
 pub(crate) struct Base {
     name: String,
     hm: PartitionHMTable,
+    process_hm: ProcessHMTable,
     id: PartitionId,
     bin: PathBuf,
     mounts: Vec<(PathBuf, PathBuf)>,
     cgroup: CGroup,
     sampling_channel: HashMap<String, SamplingConstant>,
+    queuing_channel: HashMap<String, QueuingConstant>,
     duration: Duration,
     period: Duration,
     working_dir: TempDir,
     sockets: Vec<PosixSocket>,
+    persistent_memory: Option<File>,
+    privileged: bool,
+    tmpfs_size: ByteSize,
+    schedules: Rc<RefCell<ModuleScheduleStatus>>,
 }
 
 impl Base {
@@ -488,10 +828,50 @@ impl Base {
         self.sampling_channel.values().map(|s| s.fd).collect_vec()
     }
 
+    pub fn queuing_fds(&self) -> Vec<RawFd> {
+        self.queuing_channel.values().map(|q| q.fd).collect_vec()
+    }
+
     pub fn freeze(&self) -> TypedResult<()> {
         self.cgroup.freeze().typ(SystemError::CGroup)
     }
 
+    /// Creates the persistent memory of a partition, which is kept across
+    /// restarts of the partition's binary
+    fn create_persistent_memory(size: ByteSize) -> TypedResult<File> {
+        if size.as_u64() == 0 {
+            return Err(anyhow!("persistent memory must not be empty"))
+                .typ(SystemError::PartitionConfig);
+        }
+        let mem = MemfdOptions::default()
+            .close_on_exec(false)
+            .allow_sealing(true)
+            .create("persistent_memory")
+            .typ(SystemError::PartitionInit)?;
+        mem.as_file()
+            .set_len(size.as_u64())
+            .typ(SystemError::PartitionInit)?;
+        mem.add_seals(&[FileSeal::SealShrink, FileSeal::SealGrow, FileSeal::SealSeal])
+            .typ(SystemError::PartitionInit)?;
+
+        Ok(mem.into_file())
+    }
+
+    /// Zeroes the persistent memory, so that nothing is retained across a cold
+    /// start
+    pub fn clear_persistent_memory(&self) -> TypedResult<()> {
+        const CHUNK: [u8; 4096] = [0; 4096];
+        let Some(mem) = &self.persistent_memory else {
+            return Ok(());
+        };
+        let len = mem.metadata().typ(SystemError::Panic)?.len();
+        for offset in (0..len).step_by(CHUNK.len()) {
+            let chunk = &CHUNK[..CHUNK.len().min((len - offset) as usize)];
+            mem.write_all_at(chunk, offset).typ(SystemError::Panic)?;
+        }
+        Ok(())
+    }
+
     pub fn is_frozen(&self) -> TypedResult<bool> {
         self.cgroup.frozen().typ(SystemError::CGroup)
     }
@@ -500,6 +880,33 @@ impl Base {
         &self.hm
     }
 
+    pub fn process_hm(&self) -> &ProcessHMTable {
+        &self.process_hm
+    }
+
+    /// Whether the partition may switch the module schedule
+    pub fn privileged(&self) -> bool {
+        self.privileged
+    }
+
+    pub fn schedules(&self) -> &RefCell<ModuleScheduleStatus> {
+        &self.schedules
+    }
+
+    /// Reports an error of the partition to the health monitor
+    ///
+    /// Errors ignored by the health monitor table are only logged. All others
+    /// are returned, so that the partition recovers from them.
+    pub fn raise(&self, err: TypedError) -> TypedResult<()> {
+        match self.hm.try_action(err.err()) {
+            Some(RecoveryAction::Module(ModuleRecoveryAction::Ignore)) => {
+                warn!("Partition \"{}\": {err}", self.name);
+                Ok(())
+            }
+            _ => Err(err),
+        }
+    }
+
     pub fn kill(&self) -> TypedResult<()> {
         self.cgroup.kill().typ(SystemError::CGroup)
     }
@@ -509,6 +916,12 @@ impl Base {
 pub(crate) struct Partition {
     base: Base,
     run: Run,
+    /// Log messages captured while a client of the control socket streams
+    /// the log of this partition
+    captured_log: Option<VecDeque<(Level, String)>>,
+    /// Processes killed by the OOM killer so far, if the memory of the
+    /// partition is limited
+    oom_kills: Option<u64>,
 }
 
 impl Partition {
@@ -516,18 +929,37 @@ impl Partition {
         cgroup_root: P,
         config: PartitionConfig,
         sampling: &HashMap<String, Sampling>,
+        queuing: &HashMap<String, Queuing>,
+        timing: PartitionTiming,
+        schedules: Rc<RefCell<ModuleScheduleStatus>>,
+        condition: StartCondition,
     ) -> TypedResult<Self> {
         // Todo implement drop for cgroup (in error case)
         let cgroup = CGroup::new_root(cgroup_root, &config.name).typ(SystemError::PartitionInit)?;
+        let oom_kills = Self::set_limits(&cgroup, &config).typ(SystemError::CGroup)?;
 
         let sampling_channel = sampling
             .iter()
             .filter_map(|(n, s)| s.constant(&config.name).map(|s| (n.clone(), s)))
             .collect();
 
-        let working_dir = tempdir().typ(SystemError::PartitionInit)?;
+        let queuing_channel = queuing
+            .iter()
+            .filter_map(|(n, q)| q.constant(&config.name).map(|q| (n.clone(), q)))
+            .collect();
+
+        // The PID allows to clean up the working directory, should the
+        // hypervisor be killed
+        let working_dir = tempfile::Builder::new()
+            .prefix(&format!("{WORKING_DIR_PREFIX}{}-", std::process::id()))
+            .tempdir()
+            .typ(SystemError::PartitionInit)?;
         trace!("CGroup Working directory: {:?}", working_dir.path());
         let bin = config.get_partition_bin()?;
+        let persistent_memory = config
+            .persistent_memory
+            .map(Base::create_persistent_memory)
+            .transpose()?;
 
         let base = Base {
             name: config.name,
@@ -535,24 +967,71 @@ impl Partition {
             cgroup,
             bin,
             mounts: config.mounts,
-            duration: config.duration,
-            period: config.period,
+            duration: timing.duration,
+            period: timing.period,
             working_dir,
             hm: config.hm_table,
+            process_hm: config.process_hm_table,
             sampling_channel,
+            queuing_channel,
             sockets: config.sockets,
+            persistent_memory,
+            privileged: config.privileged,
+            tmpfs_size: config.tmpfs_size,
+            schedules,
         };
-        // TODO use StartCondition::HmModuleRestart in case of a ModuleRestart!!
-        let run =
-            Run::new(&base, StartCondition::NormalStart, false).typ(SystemError::PartitionInit)?;
+        let run = Run::new(&base, condition, false).typ(SystemError::PartitionInit)?;
 
-        Ok(Self { base, run })
+        Ok(Self {
+            base,
+            run,
+            captured_log: None,
+            oom_kills,
+        })
     }
 
     pub(crate) fn name(&self) -> &str {
         self.base.name()
     }
 
+    /// Applies the resource limits of `config` to the cgroup of the
+    /// partition. Returns the current number of OOM kills, if the memory is
+    /// limited.
+    fn set_limits(cgroup: &CGroup, config: &PartitionConfig) -> anyhow::Result<Option<u64>> {
+        if let Some(max) = config.memory_max {
+            cgroup.set_memory_max(Some(max.as_u64()))?;
+        }
+        if let Some(high) = config.memory_high {
+            cgroup.set_memory_high(Some(high.as_u64()))?;
+        }
+        if let Some(max) = config.pids_max {
+            cgroup.set_pids_max(Some(max))?;
+        }
+        if config.memory_max.is_some() || config.memory_high.is_some() {
+            return cgroup.oom_kills().map(Some);
+        }
+        Ok(None)
+    }
+
+    /// Raises [SystemError::OutOfMemory] once the OOM killer killed another
+    /// process of the partition
+    pub(crate) fn check_memory(&mut self) -> TypedResult<()> {
+        let Some(previous) = self.oom_kills else {
+            return Ok(());
+        };
+        let oom_kills = self.base.cgroup.oom_kills().typ(SystemError::CGroup)?;
+        if oom_kills > previous {
+            self.oom_kills = Some(oom_kills);
+            problem!(
+                OutOfMemory,
+                "{} processes of partition \"{}\" were killed by the OOM killer",
+                oom_kills - previous,
+                self.base.name()
+            );
+        }
+        Ok(())
+    }
+
     fn release_fds(keep: &[RawFd]) -> TypedResult<()> {
         let proc = Process::myself().typ(SystemError::Panic)?;
         for fd in proc
@@ -596,6 +1075,76 @@ impl Partition {
         (&self.base, &mut self.run)
     }
 
+    /// Prints the log message of `call` and captures it, if requested
+    fn log(&mut self, call: &PartitionCall) {
+        call.print_partition_log(self.base.name());
+        if let Some(log) = self.captured_log.as_mut() {
+            if log.len() >= MAX_CAPTURED_LOG {
+                log.pop_front();
+            }
+            log.push_back(call.log_record());
+        }
+    }
+
+    /// Starts or stops capturing the log messages of the partition
+    pub(crate) fn capture_log(&mut self, capture: bool) {
+        match (capture, &self.captured_log) {
+            (true, None) => self.captured_log = Some(VecDeque::new()),
+            (false, _) => self.captured_log = None,
+            (true, Some(_)) => {}
+        }
+    }
+
+    /// Returns the log messages captured since the last call
+    pub(crate) fn take_log(&mut self) -> Vec<(Level, String)> {
+        self.captured_log
+            .as_mut()
+            .map(|log| log.drain(..).collect())
+            .unwrap_or_default()
+    }
+
+    /// Returns the status of the partition for the control socket
+    pub(crate) fn status(&self) -> PartitionStatus {
+        PartitionStatus {
+            id: self.base.id,
+            name: self.base.name.clone(),
+            mode: format!("{:?}", self.run.mode()),
+            cgroup: self.base.cgroup.get_path(),
+            pid: self.run.main_pid().as_raw(),
+            processes: self
+                .run
+                .processes()
+                .iter()
+                .map(|p| ProcessStatus {
+                    id: p.id(),
+                    name: p.name().to_string(),
+                    state: format!("{:?}", p.state()),
+                })
+                .collect(),
+        }
+    }
+
+    /// Forces the partition into the mode of `action`, e.g. on request of an
+    /// operator. Must be called in between the windows of the partition.
+    pub(crate) fn force_mode(&mut self, action: PartitionAction) -> TypedResult<()> {
+        // Transitions may only happen while the partition is not frozen. The
+        // transitions freeze the partition again right away.
+        self.base.unfreeze()?;
+        let res = match action {
+            PartitionAction::Idle => self.run.idle_transition(&self.base),
+            PartitionAction::ColdStart => {
+                self.run
+                    .start_transition(&self.base, false, StartCondition::PartitionRestart)
+            }
+            PartitionAction::WarmStart => {
+                self.run
+                    .start_transition(&self.base, true, StartCondition::PartitionRestart)
+            }
+        };
+        self.base.freeze()?;
+        res
+    }
+
     //fn idle_transition(mut self) -> Result<()> {
     //    self.cgroup.freeze();
     //    self.cgroup.kill_all_wait()?;
@@ -614,11 +1163,38 @@ impl Partition {
         self.base.cgroup.freeze().typ(SystemError::CGroup)
     }
 
+    /// Asks the partition to exit by sending SIGTERM to its main process. The
+    /// partition is left unfrozen, so that it is able to exit.
+    pub(crate) fn terminate(&self) -> TypedResult<()> {
+        if self.run.mode() == OperatingMode::Idle {
+            return Ok(());
+        }
+        self.base.unfreeze()?;
+        match nix::sys::signal::kill(self.run.main_pid(), nix::sys::signal::SIGTERM) {
+            // The main process already exited
+            Ok(()) | Err(nix::errno::Errno::ESRCH) => Ok(()),
+            Err(e) => Err(e).typ(SystemError::Panic),
+        }
+    }
+
+    /// Whether any process of the partition is still alive
+    pub(crate) fn populated(&self) -> TypedResult<bool> {
+        self.base.cgroup.populated().typ(SystemError::CGroup)
+    }
+
     pub(crate) fn rm(self) -> TypedResult<()> {
         self.base.cgroup.rm().typ(SystemError::CGroup)
     }
 
-    pub fn run_post_timeframe(&mut self, sampling_channels: &mut HashMap<String, Sampling>) {
+    /// Transfers the messages sent through the channels of the partition.
+    ///
+    /// Returns the errors of partitions, which corrupted the queues of
+    /// queuing channels, by the names of the partitions.
+    pub fn run_post_timeframe(
+        &mut self,
+        sampling_channels: &mut HashMap<String, Sampling>,
+        queuing_channels: &mut HashMap<String, Queuing>,
+    ) -> Vec<(String, TypedError)> {
         // TODO remove because a base freeze is not necessary here, as all run_* methods
         // should freeze base themself after execution. Before removal of this, check
         // all run_* methods.
@@ -631,84 +1207,52 @@ impl Partition {
             .filter(|(_, s)| s.dir == PortDirection::Source)
         {
             sampling_channels.get_mut(name).unwrap().swap();
+            recorder::record(|r| r.channel_swap(self.base.id, name));
         }
-    }
-
-    /// Executes the periodic process for a maximum duration specified through
-    /// the `timeout` parameter. Returns whether the periodic process exists
-    /// and was run.
-    pub fn run_periodic_process(&mut self, timeout: Timeout) -> TypedResult<bool> {
-        match self.run.unfreeze_periodic() {
-            Ok(true) => {}
-            other => return other,
-        }
-
-        let mut poller = PeriodicPoller::new(&self.run)?;
-
-        self.base.unfreeze()?;
-
-        while timeout.has_time_left() {
-            let event = poller.wait_timeout(&mut self.run, timeout)?;
-            match &event {
-                PeriodicEvent::Timeout => {}
-                PeriodicEvent::Frozen => {
-                    self.base.freeze()?;
 
-                    return Ok(true);
-                }
-                // TODO Error Handling with HM
-                PeriodicEvent::Call(e @ PartitionCall::Error(se)) => {
-                    e.print_partition_log(self.base.name());
-                    match self.base.part_hm().try_action(*se) {
-                        Some(RecoveryAction::Module(ModuleRecoveryAction::Ignore)) => {}
-                        Some(_) => {
-                            return Err(TypedError::new(*se, anyhow!("Received Partition Error")))
-                        }
-                        None => {
-                            return Err(TypedError::new(
-                                SystemError::Panic,
-                                anyhow!(
-                                "Could not get recovery action for requested partition error: {se}"
-                            ),
-                            ))
-                        }
-                    };
-                }
-                PeriodicEvent::Call(c @ PartitionCall::Message(_)) => {
-                    c.print_partition_log(self.base.name())
-                }
-                PeriodicEvent::Call(PartitionCall::Transition(mode)) => {
-                    // Only exit run_periodic, if we changed our mode
-                    if self.run.handle_transition(&self.base, *mode)?.is_some() {
-                        return Ok(true);
-                    }
-                }
+        let mut errors = Vec::new();
+        for (name, _) in self
+            .base
+            .queuing_channel
+            .iter()
+            .filter(|(_, q)| q.dir == PortDirection::Source)
+        {
+            if let Err(e) = queuing_channels.get_mut(name).unwrap().swap() {
+                errors.push(e);
             }
+            recorder::record(|r| r.channel_swap(self.base.id, name));
         }
-
-        // TODO being here means that we exceeded the timeout
-        // So we should return a SystemError stating that the time was exceeded
-        Ok(true)
+        // The swaps made room for the messages of waiting senders
+        if let Err(e) = self.run.wake_queuing_ports() {
+            errors.push((self.base.name.clone(), e));
+        }
+        errors
     }
 
-    pub fn run_aperiodic_process(&mut self, timeout: Timeout) -> TypedResult<bool> {
-        match self.run.unfreeze_aperiodic() {
-            Ok(true) => {}
-            other => return other,
-        }
+    /// Executes the processes of the partition for a maximum duration
+    /// specified through the `timeout` parameter. The partition must be in
+    /// the normal mode.
+    ///
+    /// Processes whose wait expired outside of the partition's windows, as
+    /// well as processes waiting on queuing ports, which other partitions
+    /// swapped in the meantime, are woken up before the ready process with the
+    /// highest priority is scheduled. Returns whether there was any process to
+    /// run.
+    pub fn run_processes(&mut self, timeout: Timeout) -> TypedResult<bool> {
+        self.run.expire_timeouts(&self.base)?;
+        self.run.wake_queuing_ports()?;
+        self.run.schedule()?;
+        let scheduled = self.run.processes().running().is_some();
+
+        let mut poller = PartitionPoller::new(&self.run)?;
 
-        // Did we even need to unfreeze aperiodic?
         self.base.unfreeze()?;
 
         while timeout.has_time_left() {
-            match &self
-                .run
-                .receiver()
-                .try_recv_timeout(timeout.remaining_time())?
-            {
-                Some(m @ PartitionCall::Message(_)) => m.print_partition_log(self.base.name()),
-                Some(e @ PartitionCall::Error(se)) => {
-                    e.print_partition_log(self.base.name());
+            match &poller.wait_timeout(&self.base, &mut self.run, timeout)? {
+                PartitionEvent::Call(m @ PartitionCall::Message(_)) => self.log(m),
+                PartitionEvent::Call(e @ PartitionCall::Error(se)) => {
+                    self.log(e);
                     match self.base.part_hm().try_action(*se) {
                         Some(RecoveryAction::Module(ModuleRecoveryAction::Ignore)) => {}
                         Some(_) => {
@@ -724,38 +1268,41 @@ impl Partition {
                         }
                     };
                 }
-                Some(t @ PartitionCall::Transition(mode)) => {
-                    // In case of a transition to idle, just sleep. Do not care for the rest
-                    t.print_partition_log(self.base.name());
-                    if let Some(OperatingMode::Idle) =
-                        self.run.handle_transition(&self.base, *mode)?
-                    {
-                        sleep(timeout.remaining_time());
-                        return Ok(true);
+                PartitionEvent::Call(t @ PartitionCall::Transition(mode)) => {
+                    self.log(t);
+                    match self.run.handle_transition(&self.base, *mode)? {
+                        // In case of a transition to idle, just sleep. Do not care for the rest
+                        Some(OperatingMode::Idle) => {
+                            sleep(timeout.remaining_time());
+                            return Ok(scheduled);
+                        }
+                        // Leave the remaining time to the start of the partition
+                        Some(_) => return self.base.freeze().map(|_| scheduled),
+                        None => {}
                     }
                 }
-                None => {}
+                PartitionEvent::Timeout => {}
             }
         }
 
-        self.run.freeze_aperiodic()?;
-
-        Ok(true)
+        // Exceeded time budgets of processes are reported through their deadlines
+        self.base.freeze()?;
+        Ok(scheduled)
     }
 
-    /// Currently the same as run_aperiodic
+    /// Executes the partition in one of the start modes for a maximum
+    /// duration specified through the `timeout` parameter. Returns early once
+    /// the partition transitions to the normal mode.
     pub fn run_start(&mut self, timeout: Timeout, _warm_start: bool) -> TypedResult<()> {
+        let mut poller = PartitionPoller::new(&self.run)?;
+
         self.base.unfreeze()?;
 
         while timeout.has_time_left() {
-            match &self
-                .run
-                .receiver()
-                .try_recv_timeout(timeout.remaining_time())?
-            {
-                Some(m @ PartitionCall::Message(_)) => m.print_partition_log(self.base.name()),
-                Some(e @ PartitionCall::Error(se)) => {
-                    e.print_partition_log(self.base.name());
+            match &poller.wait_timeout(&self.base, &mut self.run, timeout)? {
+                PartitionEvent::Call(m @ PartitionCall::Message(_)) => self.log(m),
+                PartitionEvent::Call(e @ PartitionCall::Error(se)) => {
+                    self.log(e);
                     match self.base.part_hm().try_action(*se) {
                         Some(RecoveryAction::Module(ModuleRecoveryAction::Ignore)) => {}
                         Some(_) => {
@@ -771,17 +1318,22 @@ impl Partition {
                         }
                     };
                 }
-                Some(t @ PartitionCall::Transition(mode)) => {
+                PartitionEvent::Call(t @ PartitionCall::Transition(mode)) => {
                     // In case of a transition to idle, just sleep. Do not care for the rest
-                    t.print_partition_log(self.base.name());
-                    if let Some(OperatingMode::Idle) =
-                        self.run.handle_transition(&self.base, *mode)?
-                    {
-                        sleep(timeout.remaining_time());
-                        return Ok(());
+                    self.log(t);
+                    match self.run.handle_transition(&self.base, *mode)? {
+                        Some(OperatingMode::Idle) => {
+                            sleep(timeout.remaining_time());
+                            return Ok(());
+                        }
+                        // Leave the remaining time to the processes
+                        Some(OperatingMode::Normal) => return self.base.freeze(),
+                        // A restart replaces the sockets of the partition
+                        Some(_) => poller = PartitionPoller::new(&self.run)?,
+                        None => {}
                     }
                 }
-                None => {}
+                PartitionEvent::Timeout => {}
             }
         }
 
@@ -792,6 +1344,11 @@ impl Partition {
     pub fn handle_error(&mut self, err: TypedError) -> LeveledResult<()> {
         debug!("Partition \"{}\" received err: {err:?}", self.base.name());
 
+        // The module health monitor handles errors of processes escalated to it
+        if self.run.take_module_error() {
+            return TypedResult::Err(err).lev(ErrorLevel::ModuleRun);
+        }
+
         let now = Instant::now();
 
         let action = match self.base.part_hm().try_action(err.err()) {
@@ -814,6 +1371,7 @@ impl Partition {
 
         debug!("Handling: {err:?}");
         debug!("Apply Partition Recovery Action: {action:?}");
+        recorder::record(|r| r.hm_action(Some(self.base.id), err.err(), &action));
 
         // TODO do not unwrap/expect these errors. Maybe raise Module Level
         // PartitionInit Error?
@@ -828,7 +1386,7 @@ impl Partition {
                 .expect("Start(Cold) Transition Failed"),
             a653rs_linux_core::health::PartitionRecoveryAction::WarmStart => self
                 .run
-                .start_transition(&self.base, false, StartCondition::HmPartitionRestart)
+                .start_transition(&self.base, true, StartCondition::HmPartitionRestart)
                 .expect("Start(Warm) Transition Failed"),
         }
 
@@ -850,7 +1408,7 @@ impl PartitionConfig {
     ///   - is executable
     /// - be a relative path starting with `./`, in which case it is resolved
     ///   relative to the hypervisors current workind directory
-    fn get_partition_bin(&self) -> TypedResult<PathBuf> {
+    pub(crate) fn get_partition_bin(&self) -> TypedResult<PathBuf> {
         let PartitionConfig { image, name, .. } = self;
 
         // if image is either an absolute path or starts with ./ , it is left as is
@@ -895,63 +1453,62 @@ impl PartitionConfig {
     }
 }
 
-pub(crate) struct PeriodicPoller {
+/// Waits for events of a running partition
+///
+/// System calls of the partition are executed while waiting.
+pub(crate) struct PartitionPoller {
     poll: Poller,
-    events: OwnedFd,
 }
 
-pub enum PeriodicEvent {
+pub enum PartitionEvent {
     Timeout,
-    Frozen,
     Call(PartitionCall),
 }
 
-impl PeriodicPoller {
-    const EVENTS_ID: usize = 1;
-    const RECEIVER_ID: usize = 2;
-
-    pub fn new(run: &Run) -> TypedResult<PeriodicPoller> {
-        let events = run.periodic_events()?;
+impl PartitionPoller {
+    const RECEIVER_ID: usize = 1;
+    const SYSCALL_ID: usize = 2;
 
+    /// Creates a poller for partition calls and system calls
+    pub fn new(run: &Run) -> TypedResult<PartitionPoller> {
         let poll = Poller::new().typ(SystemError::Panic)?;
         unsafe {
-            poll.add(events.as_raw_fd(), Event::readable(Self::EVENTS_ID))
-                .typ(SystemError::Panic)?;
             poll.add(
                 run.receiver().as_raw_fd(),
                 Event::readable(Self::RECEIVER_ID),
             )
             .typ(SystemError::Panic)?;
+            poll.add(
+                run.syscall_receiver().as_raw_fd(),
+                Event::readable(Self::SYSCALL_ID),
+            )
+            .typ(SystemError::Panic)?;
         }
 
-        Ok(PeriodicPoller { poll, events })
+        Ok(PartitionPoller { poll })
     }
 
-    pub fn wait_timeout(&mut self, run: &mut Run, timeout: Timeout) -> TypedResult<PeriodicEvent> {
-        if run.is_periodic_frozen()? {
-            return Ok(PeriodicEvent::Frozen);
-        }
-
+    pub fn wait_timeout(
+        &mut self,
+        base: &Base,
+        run: &mut Run,
+        timeout: Timeout,
+    ) -> TypedResult<PartitionEvent> {
         while timeout.has_time_left() {
+            // Wake up for the next time out of a process
+            let mut remaining_time = timeout.remaining_time();
+            if let Some(next) = run.next_timeout() {
+                remaining_time = remaining_time.min(next.saturating_duration_since(Instant::now()));
+            }
+
             let mut events = Events::new();
             self.poll
-                .wait(&mut events, Some(timeout.remaining_time()))
+                .wait(&mut events, Some(remaining_time))
                 .typ(SystemError::Panic)?;
+            run.expire_timeouts(base)?;
 
             for e in events.iter() {
                 match e.key {
-                    // Got a Frozen event
-                    Self::EVENTS_ID => {
-                        // Re-sub the readable event
-                        self.poll
-                            .modify(&mut self.events, Event::readable(Self::EVENTS_ID))
-                            .typ(SystemError::Panic)?;
-
-                        // Then check if the cg is actually frozen
-                        if run.is_periodic_frozen()? {
-                            return Ok(PeriodicEvent::Frozen);
-                        }
-                    }
                     // got a call events
                     Self::RECEIVER_ID => {
                         // Re-sub the readable event
@@ -965,9 +1522,17 @@ impl PeriodicPoller {
 
                         // Now receive anything
                         if let Some(call) = run.receiver().try_recv()? {
-                            return Ok(PeriodicEvent::Call(call));
+                            return Ok(PartitionEvent::Call(call));
                         }
                     }
+                    // got system calls
+                    Self::SYSCALL_ID => {
+                        self.poll
+                            .modify(run.syscall_receiver(), Event::readable(Self::SYSCALL_ID))
+                            .typ(SystemError::Panic)?;
+
+                        run.handle_syscalls(base)?;
+                    }
                     _ => {
                         return Err(anyhow!("Unexpected Event Received: {e:?}"))
                             .typ(SystemError::Panic)
@@ -976,6 +1541,6 @@ impl PeriodicPoller {
             }
         }
 
-        Ok(PeriodicEvent::Timeout)
+        Ok(PartitionEvent::Timeout)
     }
 }
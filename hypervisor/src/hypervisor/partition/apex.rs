@@ -0,0 +1,2859 @@
+//! Hypervisor side of the APEX services, which partitions request through
+//! system calls
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use a653rs::bindings::{
+    ApexSystemTime, BlackboardId, BufferId, ErrorCode, ErrorReturnCode, EventId, EventState,
+    MutexId, Priority, ProcessId, ProcessState, QueuingDiscipline, SemaphoreId, SemaphoreValue,
+    MAX_ERROR_MESSAGE_SIZE,
+};
+use a653rs::prelude::{OperatingMode, SystemTime};
+use anyhow::anyhow;
+use once_cell::sync::Lazy;
+
+use a653rs_linux_core::error::{ResultExt, SystemError, TypedResult};
+use a653rs_linux_core::health_event::PartitionCall;
+use a653rs_linux_core::partition::PartitionConstants;
+use a653rs_linux_core::syscall::{
+    deadline_from_u64, error_code_to_u64, process_state_to_u64, queuing_discipline_from_u64,
+    ApexSyscall, SyscallRequest,
+};
+
+use super::{Base, Run};
+use crate::hypervisor::intra::{
+    Blackboard, Buffer, Event, IntraObject, Mutex, ObjectTable, Semaphore, MAX_MESSAGE_SIZE,
+    MAX_NB_MESSAGE,
+};
+use crate::hypervisor::process::{
+    ProcessAttributes, ProcessError, ERROR_HANDLER_PRIORITY, MAX_PRIORITY, MIN_PRIORITY,
+};
+use crate::hypervisor::syscall::{Syscall, SyscallOutput};
+use crate::hypervisor::SYSTEM_START_TIME;
+
+pub(crate) type SyscallResult = Result<SyscallOutput, ErrorReturnCode>;
+
+/// Outcome of an APEX service
+#[derive(Debug)]
+pub(crate) enum SyscallReturn {
+    /// The service was executed and may be answered right away
+    Done(SyscallResult),
+    /// The calling process waits, its response is deferred until it is woken
+    /// up again or the optional time out is reached
+    Wait(ProcessId, Option<Instant>),
+    /// The calling process was stopped and never receives a response
+    Stopped,
+}
+
+impl From<SyscallOutput> for SyscallReturn {
+    fn from(output: SyscallOutput) -> Self {
+        SyscallReturn::Done(Ok(output))
+    }
+}
+
+impl From<ErrorReturnCode> for SyscallReturn {
+    fn from(err: ErrorReturnCode) -> Self {
+        SyscallReturn::Done(Err(err))
+    }
+}
+
+/// Executes an APEX service on behalf of a partition
+pub(crate) type SyscallHandler = fn(&Base, &mut Run, &SyscallRequest) -> TypedResult<SyscallReturn>;
+
+static HANDLERS: Lazy<HashMap<ApexSyscall, SyscallHandler>> = Lazy::new(|| {
+    HashMap::from([
+        (
+            ApexSyscall::GetPartitionStatus,
+            get_partition_status as SyscallHandler,
+        ),
+        (ApexSyscall::GetProcessStatus, get_process_status),
+        (ApexSyscall::CreateProcess, create_process),
+        (ApexSyscall::SetPriority, set_priority),
+        (ApexSyscall::SuspendSelf, suspend_self),
+        (ApexSyscall::Suspend, suspend),
+        (ApexSyscall::Resume, resume),
+        (ApexSyscall::StopSelf, stop_self),
+        (ApexSyscall::Stop, stop),
+        (ApexSyscall::Start, start),
+        (ApexSyscall::DelayStart, delay_start),
+        (ApexSyscall::LockPreemption, lock_preemption),
+        (ApexSyscall::UnlockPreemption, unlock_preemption),
+        (ApexSyscall::TimedWait, timed_wait),
+        (ApexSyscall::PeriodicWait, periodic_wait),
+        (ApexSyscall::Replenish, replenish),
+        (ApexSyscall::SendQueuingMessage, wait_queuing_port),
+        (ApexSyscall::ReceiveQueuingMessage, wait_queuing_port),
+        (ApexSyscall::CreateBuffer, create_buffer),
+        (ApexSyscall::SendBuffer, send_buffer),
+        (ApexSyscall::ReceiveBuffer, receive_buffer),
+        (ApexSyscall::GetBufferId, get_buffer_id),
+        (ApexSyscall::GetBufferStatus, get_buffer_status),
+        (ApexSyscall::CreateBlackboard, create_blackboard),
+        (ApexSyscall::DisplayBlackboard, display_blackboard),
+        (ApexSyscall::ReadBlackboard, read_blackboard),
+        (ApexSyscall::ClearBlackboard, clear_blackboard),
+        (ApexSyscall::GetBlackboardId, get_blackboard_id),
+        (ApexSyscall::GetBlackboardStatus, get_blackboard_status),
+        (ApexSyscall::CreateSemaphore, create_semaphore),
+        (ApexSyscall::WaitSemaphore, wait_semaphore),
+        (ApexSyscall::SignalSemaphore, signal_semaphore),
+        (ApexSyscall::GetSemaphoreId, get_semaphore_id),
+        (ApexSyscall::GetSemaphoreStatus, get_semaphore_status),
+        (ApexSyscall::CreateEvent, create_event),
+        (ApexSyscall::SetEvent, set_event),
+        (ApexSyscall::ResetEvent, reset_event),
+        (ApexSyscall::WaitEvent, wait_event),
+        (ApexSyscall::GetEventId, get_event_id),
+        (ApexSyscall::GetEventStatus, get_event_status),
+        (ApexSyscall::CreateMutex, create_mutex),
+        (ApexSyscall::AcquireMutex, acquire_mutex),
+        (ApexSyscall::ReleaseMutex, release_mutex),
+        (ApexSyscall::ResetMutex, reset_mutex),
+        (ApexSyscall::GetMutexId, get_mutex_id),
+        (ApexSyscall::GetMutexStatus, get_mutex_status),
+        (ApexSyscall::GetProciessMutexState, get_process_mutex_state),
+        (
+            ApexSyscall::ReportApplicationMessage,
+            report_application_message,
+        ),
+        (ApexSyscall::CreateErrorHandler, create_error_handler),
+        (ApexSyscall::GetErrorStatus, get_error_status),
+        (ApexSyscall::RaiseApplicationError, raise_application_error),
+        (ApexSyscall::SetModuleSchedule, set_module_schedule),
+        (
+            ApexSyscall::GetModuleScheduleStatus,
+            get_module_schedule_status,
+        ),
+        (ApexSyscall::GetModuleScheduleId, get_module_schedule_id),
+    ])
+});
+
+/// Routes a system call to the handler of the requested APEX service and
+/// either answers it or lets the calling process wait for its response
+pub(crate) fn dispatch(base: &Base, run: &mut Run, syscall: Syscall) -> TypedResult<()> {
+    let request = syscall.request();
+    let ret = match HANDLERS.get(&request.id) {
+        Some(handler) => handler(base, run, request)?,
+        None => {
+            warn!(
+                "Partition \"{}\" requested unsupported system call {:?}",
+                base.name(),
+                request.id
+            );
+            ErrorReturnCode::NotAvailable.into()
+        }
+    };
+    trace!("System call {:?} returned {ret:?}", request.id);
+
+    match ret {
+        SyscallReturn::Done(result) => syscall.complete(result).typ(SystemError::Panic),
+        SyscallReturn::Wait(id, timeout) => match run.processes_mut().get_mut(id) {
+            Some(process) => process.wait(syscall, timeout),
+            None => syscall
+                .complete(Err(ErrorReturnCode::InvalidParam))
+                .typ(SystemError::Panic),
+        },
+        SyscallReturn::Stopped => Ok(()),
+    }
+}
+
+/// Returns the parameter at `index` of the request
+fn param(request: &SyscallRequest, index: usize) -> Result<u64, ErrorReturnCode> {
+    request
+        .params
+        .get(index)
+        .copied()
+        .ok_or(ErrorReturnCode::InvalidParam)
+}
+
+/// Returns the parameter at `index` of the request as a time
+fn time_param(request: &SyscallRequest, index: usize) -> Result<SystemTime, ErrorReturnCode> {
+    param(request, index).map(|t| SystemTime::new(t as ApexSystemTime))
+}
+
+/// Returns the parameter at `index` of the request as a queuing discipline
+fn discipline_param(
+    request: &SyscallRequest,
+    index: usize,
+) -> Result<QueuingDiscipline, ErrorReturnCode> {
+    param(request, index)
+        .and_then(|d| queuing_discipline_from_u64(d).map_err(|_| ErrorReturnCode::InvalidParam))
+}
+
+/// Returns the name of an object, which the request holds as its data
+fn name_param(request: &SyscallRequest) -> Result<String, ErrorReturnCode> {
+    std::str::from_utf8(&request.data)
+        .map(str::to_string)
+        .map_err(|_| ErrorReturnCode::InvalidParam)
+}
+
+/// Looks up the id of an object by the name, which the request holds as its
+/// data
+fn object_id<T: IntraObject>(objects: &ObjectTable<T>, request: &SyscallRequest) -> SyscallReturn {
+    let name = match name_param(request) {
+        Ok(name) => name,
+        Err(e) => return e.into(),
+    };
+    match objects.id(&name) {
+        Some(id) => SyscallOutput {
+            values: vec![id as u64],
+            data: Vec::new(),
+        }
+        .into(),
+        None => ErrorReturnCode::InvalidConfig.into(),
+    }
+}
+
+/// Returns the point in time, until which the calling process may wait for an
+/// object, or `None` if it may wait forever
+///
+/// Only processes other than the error handler may wait, and only while
+/// preemption is not locked. A time out of zero does not wait at all.
+fn wait_until(
+    run: &Run,
+    id: ProcessId,
+    time_out: SystemTime,
+) -> Result<Option<Instant>, ErrorReturnCode> {
+    if time_out == SystemTime::Normal(Duration::ZERO) {
+        return Err(ErrorReturnCode::NotAvailable);
+    }
+    if run.mode() != OperatingMode::Normal
+        || run.processes().get(id).is_none()
+        || run.processes().lock_level() > 0
+        || run.error_handler() == Some(id)
+    {
+        return Err(ErrorReturnCode::InvalidMode);
+    }
+    Ok(match time_out {
+        SystemTime::Normal(time_out) => Some(Instant::now() + time_out),
+        SystemTime::Infinite => None,
+    })
+}
+
+/// Converts a point in time into the system time of the partitions, which
+/// starts with the first major frame
+fn system_time(instant: Option<Instant>) -> TypedResult<ApexSystemTime> {
+    let Some(instant) = instant else {
+        return Ok(SystemTime::Infinite.into());
+    };
+    let start = SYSTEM_START_TIME
+        .get()
+        .ok_or_else(|| anyhow!("SystemTime was not set"))
+        .typ(SystemError::Panic)?
+        .read()?;
+    Ok(SystemTime::Normal(instant.saturating_duration_since(start)).into())
+}
+
+/// P1-5 3.2.2.1 - GET_PARTITION_STATUS
+///
+/// Only returns the lock level, as the partition knows the remaining status
+/// itself.
+fn get_partition_status(
+    _base: &Base,
+    run: &mut Run,
+    _request: &SyscallRequest,
+) -> TypedResult<SyscallReturn> {
+    Ok(SyscallOutput {
+        values: vec![run.processes().lock_level() as u64],
+        data: Vec::new(),
+    }
+    .into())
+}
+
+/// P1-5 3.3.2.2 - GET_PROCESS_STATUS
+fn get_process_status(
+    _base: &Base,
+    run: &mut Run,
+    request: &SyscallRequest,
+) -> TypedResult<SyscallReturn> {
+    let id = match param(request, 0) {
+        Ok(id) => id as ProcessId,
+        Err(e) => return Ok(e.into()),
+    };
+    let Some(process) = run.processes().get(id) else {
+        return Ok(ErrorReturnCode::InvalidParam.into());
+    };
+
+    let deadline_time = system_time(process.deadline_time())?;
+    Ok(SyscallOutput {
+        values: vec![
+            process_state_to_u64(process.state()),
+            process.current_priority() as u64,
+            deadline_time as u64,
+        ],
+        data: Vec::new(),
+    }
+    .into())
+}
+
+/// P1-5 3.3.2.3 - CREATE_PROCESS
+///
+/// Parameters are the period, the time capacity, the base priority and the
+/// deadline of the process. The data holds the name of the process.
+/// Returns the id of the new process.
+fn create_process(
+    base: &Base,
+    run: &mut Run,
+    request: &SyscallRequest,
+) -> TypedResult<SyscallReturn> {
+    if run.mode() == OperatingMode::Normal {
+        return Ok(ErrorReturnCode::InvalidMode.into());
+    }
+
+    let attr = match parse_process_attributes(request) {
+        Ok(attr) => attr,
+        Err(e) => return Ok(e.into()),
+    };
+    if !(MIN_PRIORITY..=MAX_PRIORITY).contains(&attr.base_priority) {
+        return Ok(ErrorReturnCode::InvalidParam.into());
+    }
+
+    // The period of a periodic process must be a multiple of the partition period
+    if let SystemTime::Normal(period) = attr.period {
+        if period.is_zero() || period.as_nanos() % base.period.as_nanos() != 0 {
+            return Ok(ErrorReturnCode::InvalidConfig.into());
+        }
+        // A periodic process has to finish before its next release
+        if matches!(attr.time_capacity, SystemTime::Normal(capacity) if capacity > period) {
+            return Ok(ErrorReturnCode::InvalidParam.into());
+        }
+    }
+
+    let id = match run.create_process(attr)? {
+        Ok(id) => id,
+        Err(e) => return Ok(e.into()),
+    };
+    Ok(SyscallOutput {
+        values: vec![id as u64],
+        data: Vec::new(),
+    }
+    .into())
+}
+
+fn parse_process_attributes(
+    request: &SyscallRequest,
+) -> Result<ProcessAttributes, ErrorReturnCode> {
+    let name = std::str::from_utf8(&request.data)
+        .map_err(|_| ErrorReturnCode::InvalidParam)?
+        .to_string();
+    Ok(ProcessAttributes {
+        name,
+        period: time_param(request, 0)?,
+        time_capacity: time_param(request, 1)?,
+        base_priority: param(request, 2)? as Priority,
+        deadline: deadline_from_u64(param(request, 3)?)
+            .map_err(|_| ErrorReturnCode::InvalidParam)?,
+    })
+}
+
+/// P1-5 3.3.2.4 - SET_PRIORITY
+fn set_priority(
+    _base: &Base,
+    run: &mut Run,
+    request: &SyscallRequest,
+) -> TypedResult<SyscallReturn> {
+    let (id, priority) = match (param(request, 0), param(request, 1)) {
+        (Ok(id), Ok(priority)) => (id as ProcessId, priority as Priority),
+        (Err(e), _) | (_, Err(e)) => return Ok(e.into()),
+    };
+    if run.error_handler() == Some(id) {
+        return Ok(ErrorReturnCode::InvalidParam.into());
+    }
+    if !(MIN_PRIORITY..=MAX_PRIORITY).contains(&priority) {
+        return Ok(ErrorReturnCode::InvalidParam.into());
+    }
+    let Some(process) = run.processes_mut().get_mut(id) else {
+        return Ok(ErrorReturnCode::InvalidParam.into());
+    };
+    if process.state() == ProcessState::Dormant {
+        return Ok(ErrorReturnCode::InvalidMode.into());
+    }
+
+    process.set_priority(priority);
+    Ok(SyscallOutput::default().into())
+}
+
+/// P1-5 3.3.2.5 - SUSPEND_SELF
+///
+/// Parameters are the id of the calling process and the time out.
+fn suspend_self(
+    _base: &Base,
+    run: &mut Run,
+    request: &SyscallRequest,
+) -> TypedResult<SyscallReturn> {
+    let (id, time_out) = match (param(request, 0), time_param(request, 1)) {
+        (Ok(id), Ok(time_out)) => (id as ProcessId, time_out),
+        (Err(e), _) | (_, Err(e)) => return Ok(e.into()),
+    };
+    // A process may not wait while it holds the preemption lock, and the error
+    // handler may not wait at all
+    if run.processes().lock_level() > 0 || run.error_handler() == Some(id) {
+        return Ok(ErrorReturnCode::InvalidMode.into());
+    }
+    let Some(process) = run.processes_mut().get_mut(id) else {
+        return Ok(ErrorReturnCode::InvalidParam.into());
+    };
+    if process.periodic() {
+        return Ok(ErrorReturnCode::InvalidMode.into());
+    }
+
+    let timeout = match time_out {
+        SystemTime::Normal(time_out) if time_out.is_zero() => {
+            return Ok(SyscallOutput::default().into())
+        }
+        SystemTime::Normal(time_out) => Some(Instant::now() + time_out),
+        SystemTime::Infinite => None,
+    };
+    process.suspend()?;
+    Ok(SyscallReturn::Wait(id, timeout))
+}
+
+/// P1-5 3.3.2.6 - SUSPEND
+fn suspend(_base: &Base, run: &mut Run, request: &SyscallRequest) -> TypedResult<SyscallReturn> {
+    let id = match param(request, 0) {
+        Ok(id) => id as ProcessId,
+        Err(e) => return Ok(e.into()),
+    };
+    if run.error_handler() == Some(id) {
+        return Ok(ErrorReturnCode::InvalidParam.into());
+    }
+    let Some(process) = run.processes_mut().get_mut(id) else {
+        return Ok(ErrorReturnCode::InvalidParam.into());
+    };
+    if process.periodic()
+        || matches!(
+            process.state(),
+            ProcessState::Dormant | ProcessState::Faulted
+        )
+    {
+        return Ok(ErrorReturnCode::InvalidMode.into());
+    }
+    if process.suspended() {
+        return Ok(ErrorReturnCode::NoAction.into());
+    }
+
+    process.suspend()?;
+    Ok(SyscallOutput::default().into())
+}
+
+/// P1-5 3.3.2.7 - RESUME
+fn resume(_base: &Base, run: &mut Run, request: &SyscallRequest) -> TypedResult<SyscallReturn> {
+    let id = match param(request, 0) {
+        Ok(id) => id as ProcessId,
+        Err(e) => return Ok(e.into()),
+    };
+    if run.error_handler() == Some(id) {
+        return Ok(ErrorReturnCode::InvalidParam.into());
+    }
+    let Some(process) = run.processes_mut().get_mut(id) else {
+        return Ok(ErrorReturnCode::InvalidParam.into());
+    };
+    if process.state() == ProcessState::Dormant {
+        return Ok(ErrorReturnCode::InvalidMode.into());
+    }
+    if !process.suspended() {
+        return Ok(ErrorReturnCode::NoAction.into());
+    }
+
+    process.resume()?;
+    Ok(SyscallOutput::default().into())
+}
+
+/// P1-5 3.3.2.8 - STOP_SELF
+///
+/// The only parameter is the id of the calling process.
+fn stop_self(_base: &Base, run: &mut Run, request: &SyscallRequest) -> TypedResult<SyscallReturn> {
+    let id = match param(request, 0) {
+        Ok(id) => id as ProcessId,
+        Err(e) => return Ok(e.into()),
+    };
+    if run.processes().get(id).is_none() {
+        return Ok(ErrorReturnCode::InvalidParam.into());
+    }
+
+    // Only the running process can hold the preemption lock
+    run.processes_mut().reset_preemption();
+
+    // The error handler keeps its thread, which waits inside STOP_SELF until
+    // the handler is activated again
+    if run.error_handler() == Some(id) {
+        if run.queued_errors() > 0 {
+            return Ok(SyscallOutput::default().into());
+        }
+        run.processes_mut().get_mut(id).unwrap().deactivate()?;
+        return Ok(SyscallReturn::Wait(id, None));
+    }
+
+    run.stop_process(id)?;
+    Ok(SyscallReturn::Stopped)
+}
+
+/// P1-5 3.3.2.9 - STOP
+fn stop(_base: &Base, run: &mut Run, request: &SyscallRequest) -> TypedResult<SyscallReturn> {
+    let id = match param(request, 0) {
+        Ok(id) => id as ProcessId,
+        Err(e) => return Ok(e.into()),
+    };
+    if run.error_handler() == Some(id) {
+        return Ok(ErrorReturnCode::InvalidParam.into());
+    }
+    match run.processes().get(id) {
+        None => return Ok(ErrorReturnCode::InvalidParam.into()),
+        Some(process) if process.state() == ProcessState::Dormant => {
+            return Ok(ErrorReturnCode::NoAction.into())
+        }
+        Some(_) => {}
+    }
+
+    run.stop_process(id)?;
+    Ok(SyscallOutput::default().into())
+}
+
+/// P1-5 3.3.2.10 - START
+///
+/// A periodic process started in the normal mode is released right away.
+/// Otherwise it is released once the partition enters the normal mode.
+fn start(_base: &Base, run: &mut Run, request: &SyscallRequest) -> TypedResult<SyscallReturn> {
+    let id = match param(request, 0) {
+        Ok(id) => id as ProcessId,
+        Err(e) => return Ok(e.into()),
+    };
+    if run.error_handler() == Some(id) {
+        return Ok(ErrorReturnCode::InvalidParam.into());
+    }
+    let normal = run.mode() == OperatingMode::Normal;
+    let Some(process) = run.processes_mut().get_mut(id) else {
+        return Ok(ErrorReturnCode::InvalidParam.into());
+    };
+    if process.state() != ProcessState::Dormant {
+        return Ok(ErrorReturnCode::NoAction.into());
+    }
+
+    process.set_state(ProcessState::Ready);
+    if normal {
+        process.release(Instant::now());
+    }
+    Ok(SyscallOutput::default().into())
+}
+
+/// P1-5 3.3.2.11 - DELAYED_START
+///
+/// Parameters are the id of the process and the delay.
+fn delay_start(
+    _base: &Base,
+    run: &mut Run,
+    request: &SyscallRequest,
+) -> TypedResult<SyscallReturn> {
+    let (id, delay) = match (param(request, 0), time_param(request, 1)) {
+        (Ok(id), Ok(SystemTime::Normal(delay))) => (id as ProcessId, delay),
+        (Err(e), _) | (_, Err(e)) => return Ok(e.into()),
+        (_, Ok(SystemTime::Infinite)) => return Ok(ErrorReturnCode::InvalidParam.into()),
+    };
+    if run.error_handler() == Some(id) {
+        return Ok(ErrorReturnCode::InvalidParam.into());
+    }
+    let normal = run.mode() == OperatingMode::Normal;
+    let Some(process) = run.processes_mut().get_mut(id) else {
+        return Ok(ErrorReturnCode::InvalidParam.into());
+    };
+    // The delay of a periodic process must be shorter than its period
+    if let SystemTime::Normal(period) = process.attributes().period {
+        if delay >= period {
+            return Ok(ErrorReturnCode::InvalidParam.into());
+        }
+    }
+    if process.state() != ProcessState::Dormant {
+        return Ok(ErrorReturnCode::NoAction.into());
+    }
+
+    if delay.is_zero() {
+        process.set_state(ProcessState::Ready);
+        if normal {
+            process.release(Instant::now());
+        }
+    } else if normal {
+        process.delay(Instant::now() + delay)?;
+    } else {
+        process.delay_start(delay)?;
+    }
+    Ok(SyscallOutput::default().into())
+}
+
+/// P1-5 3.3.2.12 - LOCK_PREEMPTION
+///
+/// Returns the previous lock level.
+fn lock_preemption(
+    _base: &Base,
+    run: &mut Run,
+    _request: &SyscallRequest,
+) -> TypedResult<SyscallReturn> {
+    if run.mode() != OperatingMode::Normal {
+        return Ok(ErrorReturnCode::NoAction.into());
+    }
+    Ok(match run.processes_mut().lock_preemption() {
+        Ok(level) => SyscallOutput {
+            values: vec![level as u64],
+            data: Vec::new(),
+        }
+        .into(),
+        Err(e) => e.into(),
+    })
+}
+
+/// P1-5 3.3.2.13 - UNLOCK_PREEMPTION
+///
+/// Returns the previous lock level.
+fn unlock_preemption(
+    _base: &Base,
+    run: &mut Run,
+    _request: &SyscallRequest,
+) -> TypedResult<SyscallReturn> {
+    if run.mode() != OperatingMode::Normal {
+        return Ok(ErrorReturnCode::NoAction.into());
+    }
+    Ok(match run.processes_mut().unlock_preemption() {
+        Ok(level) => SyscallOutput {
+            values: vec![level as u64],
+            data: Vec::new(),
+        }
+        .into(),
+        Err(e) => e.into(),
+    })
+}
+
+/// P1-5 3.4.2.1 - TIMED_WAIT
+///
+/// Parameters are the id of the calling process and the delay. A delay of
+/// zero lets the process yield to the other ready processes of its priority.
+fn timed_wait(_base: &Base, run: &mut Run, request: &SyscallRequest) -> TypedResult<SyscallReturn> {
+    let (id, delay) = match (param(request, 0), time_param(request, 1)) {
+        (Ok(id), Ok(SystemTime::Normal(delay))) => (id as ProcessId, delay),
+        (Err(e), _) | (_, Err(e)) => return Ok(e.into()),
+        (_, Ok(SystemTime::Infinite)) => return Ok(ErrorReturnCode::InvalidParam.into()),
+    };
+    // A process may not wait while it holds the preemption lock, and the error
+    // handler may not wait at all
+    if run.processes().lock_level() > 0 || run.error_handler() == Some(id) {
+        return Ok(ErrorReturnCode::InvalidMode.into());
+    }
+    let Some(process) = run.processes_mut().get_mut(id) else {
+        return Ok(ErrorReturnCode::InvalidParam.into());
+    };
+
+    if delay.is_zero() {
+        process.yield_now();
+        return Ok(SyscallOutput::default().into());
+    }
+    Ok(SyscallReturn::Wait(id, Some(Instant::now() + delay)))
+}
+
+/// P1-5 3.4.2.2 - PERIODIC_WAIT
+///
+/// The only parameter is the id of the calling process. The process waits
+/// until its next release point, which is its previous release point plus its
+/// period. Its deadline is its time capacity after the next release point. A
+/// process, which was not released yet, has no release point to wait for.
+fn periodic_wait(
+    _base: &Base,
+    run: &mut Run,
+    request: &SyscallRequest,
+) -> TypedResult<SyscallReturn> {
+    let id = match param(request, 0) {
+        Ok(id) => id as ProcessId,
+        Err(e) => return Ok(e.into()),
+    };
+    // A process may not wait while it holds the preemption lock
+    if run.processes().lock_level() > 0 {
+        return Ok(ErrorReturnCode::InvalidMode.into());
+    }
+    match run.processes_mut().get_mut(id) {
+        Some(process) if process.periodic() => Ok(match process.periodic_wait() {
+            Some(next_release) => SyscallReturn::Wait(id, Some(next_release)),
+            None => ErrorReturnCode::InvalidMode.into(),
+        }),
+        Some(_) => Ok(ErrorReturnCode::InvalidMode.into()),
+        None => Ok(ErrorReturnCode::InvalidParam.into()),
+    }
+}
+
+/// P1-5 3.4.2.4 - REPLENISH
+///
+/// Parameters are the id of the calling process and the budget, by which its
+/// deadline is postponed from now on.
+fn replenish(_base: &Base, run: &mut Run, request: &SyscallRequest) -> TypedResult<SyscallReturn> {
+    let (id, budget) = match (param(request, 0), time_param(request, 1)) {
+        (Ok(id), Ok(budget)) => (id as ProcessId, budget),
+        (Err(e), _) | (_, Err(e)) => return Ok(e.into()),
+    };
+    if run.mode() != OperatingMode::Normal {
+        return Ok(ErrorReturnCode::NoAction.into());
+    }
+    let Some(process) = run.processes_mut().get_mut(id) else {
+        return Ok(ErrorReturnCode::InvalidParam.into());
+    };
+
+    match process.replenish(budget) {
+        Ok(()) => Ok(SyscallOutput::default().into()),
+        Err(e) => Ok(e.into()),
+    }
+}
+
+/// P1-5 3.6.2.2.2 - SEND_QUEUING_MESSAGE and P1-5 3.6.2.2.3 -
+/// RECEIVE_QUEUING_MESSAGE
+///
+/// Only called by a process, which found the queue of its port full or empty.
+/// Parameters are the id of the calling process, the time out and the queuing
+/// discipline of the port. The data holds the name of the port. Returns once
+/// the process may try again to access the queue.
+fn wait_queuing_port(
+    base: &Base,
+    run: &mut Run,
+    request: &SyscallRequest,
+) -> TypedResult<SyscallReturn> {
+    let (id, time_out, discipline) =
+        match (param(request, 0), time_param(request, 1), param(request, 2)) {
+            (Ok(id), Ok(time_out), Ok(discipline)) => (id as ProcessId, time_out, discipline),
+            (Err(e), ..) | (_, Err(e), _) | (.., Err(e)) => return Ok(e.into()),
+        };
+    let Ok(discipline) = queuing_discipline_from_u64(discipline) else {
+        return Ok(ErrorReturnCode::InvalidParam.into());
+    };
+    let Ok(name) = std::str::from_utf8(&request.data) else {
+        return Ok(ErrorReturnCode::InvalidParam.into());
+    };
+    let until = match wait_until(run, id, time_out) {
+        Ok(until) => until,
+        Err(e) => return Ok(e.into()),
+    };
+    let priority = run.processes().get(id).unwrap().current_priority();
+    let Some(port) = run.queuing_port(base, name, discipline)? else {
+        return Ok(ErrorReturnCode::InvalidParam.into());
+    };
+
+    // The queue may have changed since the process accessed it
+    if port.available() > 0 {
+        return Ok(SyscallOutput::default().into());
+    }
+    port.wait(id, priority);
+    Ok(SyscallReturn::Wait(id, until))
+}
+
+/// P1-5 3.7.2.1.1 - CREATE_BUFFER
+///
+/// Parameters are the maximum message size, the maximum number of messages and
+/// the queuing discipline. The data holds the name of the buffer. Returns the
+/// id of the new buffer. Sizes beyond [MAX_MESSAGE_SIZE] and
+/// [MAX_NB_MESSAGE] are invalid.
+fn create_buffer(
+    _base: &Base,
+    run: &mut Run,
+    request: &SyscallRequest,
+) -> TypedResult<SyscallReturn> {
+    let (name, max_message_size, max_nb_message, discipline) = match (
+        name_param(request),
+        param(request, 0),
+        param(request, 1),
+        discipline_param(request, 2),
+    ) {
+        (Ok(name), Ok(size), Ok(nb), Ok(discipline)) => (name, size, nb, discipline),
+        (Err(e), ..) | (_, Err(e), ..) | (.., Err(e), _) | (.., Err(e)) => return Ok(e.into()),
+    };
+    // Negative sizes of the partition arrive sign extended and thus too large
+    if !(1..=MAX_MESSAGE_SIZE as u64).contains(&max_message_size)
+        || !(1..=MAX_NB_MESSAGE as u64).contains(&max_nb_message)
+    {
+        return Ok(ErrorReturnCode::InvalidParam.into());
+    }
+    if run.mode() == OperatingMode::Normal {
+        return Ok(ErrorReturnCode::InvalidMode.into());
+    }
+
+    let buffer = Buffer::new(
+        name,
+        max_message_size as usize,
+        max_nb_message as usize,
+        discipline,
+    );
+    match run.buffers_mut().create(buffer) {
+        Ok(id) => Ok(SyscallOutput {
+            values: vec![id as u64],
+            data: Vec::new(),
+        }
+        .into()),
+        Err(e) => Ok(e.into()),
+    }
+}
+
+/// P1-5 3.7.2.1.2 - SEND_BUFFER
+///
+/// Parameters are the id of the calling process, the id of the buffer and the
+/// time out. The data holds the message. A sender waits while the buffer is
+/// full.
+fn send_buffer(
+    _base: &Base,
+    run: &mut Run,
+    request: &SyscallRequest,
+) -> TypedResult<SyscallReturn> {
+    let (id, buffer_id, time_out) =
+        match (param(request, 0), param(request, 1), time_param(request, 2)) {
+            (Ok(id), Ok(buffer_id), Ok(time_out)) => {
+                (id as ProcessId, buffer_id as BufferId, time_out)
+            }
+            (Err(e), ..) | (_, Err(e), _) | (.., Err(e)) => return Ok(e.into()),
+        };
+    let Some(buffer) = run.buffers_mut().get_mut(buffer_id) else {
+        return Ok(ErrorReturnCode::InvalidParam.into());
+    };
+    if request.data.len() > buffer.max_message_size() {
+        return Ok(ErrorReturnCode::InvalidConfig.into());
+    }
+    if request.data.is_empty() {
+        return Ok(ErrorReturnCode::InvalidParam.into());
+    }
+
+    let message = match buffer.send(request.data.clone()) {
+        Ok(Some((receiver, message))) => {
+            if let Some(receiver) = run.processes_mut().get_mut(receiver) {
+                receiver.wake(Ok(SyscallOutput {
+                    values: Vec::new(),
+                    data: message,
+                }))?;
+            }
+            return Ok(SyscallOutput::default().into());
+        }
+        Ok(None) => return Ok(SyscallOutput::default().into()),
+        Err(message) => message,
+    };
+
+    // The buffer is full
+    let until = match wait_until(run, id, time_out) {
+        Ok(until) => until,
+        Err(e) => return Ok(e.into()),
+    };
+    let priority = run.processes().get(id).unwrap().current_priority();
+    run.buffers_mut()
+        .get_mut(buffer_id)
+        .unwrap()
+        .wait_send(id, priority, message);
+    Ok(SyscallReturn::Wait(id, until))
+}
+
+/// P1-5 3.7.2.1.3 - RECEIVE_BUFFER
+///
+/// Parameters are the id of the calling process, the id of the buffer and the
+/// time out. Returns the message as data. A receiver waits while the buffer is
+/// empty.
+fn receive_buffer(
+    _base: &Base,
+    run: &mut Run,
+    request: &SyscallRequest,
+) -> TypedResult<SyscallReturn> {
+    let (id, buffer_id, time_out) =
+        match (param(request, 0), param(request, 1), time_param(request, 2)) {
+            (Ok(id), Ok(buffer_id), Ok(time_out)) => {
+                (id as ProcessId, buffer_id as BufferId, time_out)
+            }
+            (Err(e), ..) | (_, Err(e), _) | (.., Err(e)) => return Ok(e.into()),
+        };
+    let Some(buffer) = run.buffers_mut().get_mut(buffer_id) else {
+        return Ok(ErrorReturnCode::InvalidParam.into());
+    };
+
+    if let Some((message, sender)) = buffer.receive() {
+        // The message of a waiting sender took the place of the received one
+        if let Some(sender) = sender.and_then(|s| run.processes_mut().get_mut(s)) {
+            sender.wake(Ok(SyscallOutput::default()))?;
+        }
+        return Ok(SyscallOutput {
+            values: Vec::new(),
+            data: message,
+        }
+        .into());
+    }
+
+    // The buffer is empty
+    let until = match wait_until(run, id, time_out) {
+        Ok(until) => until,
+        Err(e) => return Ok(e.into()),
+    };
+    let priority = run.processes().get(id).unwrap().current_priority();
+    run.buffers_mut()
+        .get_mut(buffer_id)
+        .unwrap()
+        .wait_receive(id, priority);
+    Ok(SyscallReturn::Wait(id, until))
+}
+
+/// P1-5 3.7.2.1.4 - GET_BUFFER_ID
+///
+/// The data holds the name of the buffer. Returns the id of the buffer.
+fn get_buffer_id(
+    _base: &Base,
+    run: &mut Run,
+    request: &SyscallRequest,
+) -> TypedResult<SyscallReturn> {
+    Ok(object_id(run.buffers(), request))
+}
+
+/// P1-5 3.7.2.1.5 - GET_BUFFER_STATUS
+///
+/// The only parameter is the id of the buffer. Returns the number of
+/// messages, the maximum number of messages, the maximum message size and
+/// the number of waiting processes.
+fn get_buffer_status(
+    _base: &Base,
+    run: &mut Run,
+    request: &SyscallRequest,
+) -> TypedResult<SyscallReturn> {
+    let buffer_id = match param(request, 0) {
+        Ok(id) => id as BufferId,
+        Err(e) => return Ok(e.into()),
+    };
+    let Some(buffer) = run.buffers().get(buffer_id) else {
+        return Ok(ErrorReturnCode::InvalidParam.into());
+    };
+
+    Ok(SyscallOutput {
+        values: vec![
+            buffer.nb_message() as u64,
+            buffer.max_nb_message() as u64,
+            buffer.max_message_size() as u64,
+            buffer.waiting_processes() as u64,
+        ],
+        data: Vec::new(),
+    }
+    .into())
+}
+
+/// P1-5 3.7.2.2.1 - CREATE_BLACKBOARD
+///
+/// The only parameter is the maximum message size. The data holds the name of
+/// the blackboard. Returns the id of the new blackboard. Sizes beyond
+/// [MAX_MESSAGE_SIZE] are invalid.
+fn create_blackboard(
+    _base: &Base,
+    run: &mut Run,
+    request: &SyscallRequest,
+) -> TypedResult<SyscallReturn> {
+    let (name, max_message_size) = match (name_param(request), param(request, 0)) {
+        (Ok(name), Ok(size)) => (name, size),
+        (Err(e), _) | (_, Err(e)) => return Ok(e.into()),
+    };
+    if max_message_size == 0 {
+        return Ok(ErrorReturnCode::InvalidParam.into());
+    }
+    if max_message_size > MAX_MESSAGE_SIZE as u64 {
+        return Ok(ErrorReturnCode::InvalidConfig.into());
+    }
+    if run.mode() == OperatingMode::Normal {
+        return Ok(ErrorReturnCode::InvalidMode.into());
+    }
+
+    let blackboard = Blackboard::new(name, max_message_size as usize);
+    match run.blackboards_mut().create(blackboard) {
+        Ok(id) => Ok(SyscallOutput {
+            values: vec![id as u64],
+            data: Vec::new(),
+        }
+        .into()),
+        Err(e) => Ok(e.into()),
+    }
+}
+
+/// P1-5 3.7.2.2.2 - DISPLAY_BLACKBOARD
+///
+/// The only parameter is the id of the blackboard. The data holds the
+/// message, which all waiting readers receive right away.
+fn display_blackboard(
+    _base: &Base,
+    run: &mut Run,
+    request: &SyscallRequest,
+) -> TypedResult<SyscallReturn> {
+    let blackboard_id = match param(request, 0) {
+        Ok(id) => id as BlackboardId,
+        Err(e) => return Ok(e.into()),
+    };
+    let Some(blackboard) = run.blackboards_mut().get_mut(blackboard_id) else {
+        return Ok(ErrorReturnCode::InvalidParam.into());
+    };
+    if request.data.is_empty() || request.data.len() > blackboard.max_message_size() {
+        return Ok(ErrorReturnCode::InvalidParam.into());
+    }
+
+    for reader in blackboard.display(request.data.clone()) {
+        if let Some(reader) = run.processes_mut().get_mut(reader) {
+            reader.wake(Ok(SyscallOutput {
+                values: Vec::new(),
+                data: request.data.clone(),
+            }))?;
+        }
+    }
+    Ok(SyscallOutput::default().into())
+}
+
+/// P1-5 3.7.2.2.3 - READ_BLACKBOARD
+///
+/// Parameters are the id of the calling process, the id of the blackboard and
+/// the time out. Returns the displayed message as data. A reader waits while
+/// the blackboard is empty.
+fn read_blackboard(
+    _base: &Base,
+    run: &mut Run,
+    request: &SyscallRequest,
+) -> TypedResult<SyscallReturn> {
+    let (id, blackboard_id, time_out) =
+        match (param(request, 0), param(request, 1), time_param(request, 2)) {
+            (Ok(id), Ok(blackboard_id), Ok(time_out)) => {
+                (id as ProcessId, blackboard_id as BlackboardId, time_out)
+            }
+            (Err(e), ..) | (_, Err(e), _) | (.., Err(e)) => return Ok(e.into()),
+        };
+    let Some(blackboard) = run.blackboards().get(blackboard_id) else {
+        return Ok(ErrorReturnCode::InvalidParam.into());
+    };
+    if let Some(message) = blackboard.message() {
+        return Ok(SyscallOutput {
+            values: Vec::new(),
+            data: message.to_vec(),
+        }
+        .into());
+    }
+
+    // The blackboard is empty
+    let until = match wait_until(run, id, time_out) {
+        Ok(until) => until,
+        Err(e) => return Ok(e.into()),
+    };
+    let priority = run.processes().get(id).unwrap().current_priority();
+    run.blackboards_mut()
+        .get_mut(blackboard_id)
+        .unwrap()
+        .wait_read(id, priority);
+    Ok(SyscallReturn::Wait(id, until))
+}
+
+/// P1-5 3.7.2.2.4 - CLEAR_BLACKBOARD
+///
+/// The only parameter is the id of the blackboard.
+fn clear_blackboard(
+    _base: &Base,
+    run: &mut Run,
+    request: &SyscallRequest,
+) -> TypedResult<SyscallReturn> {
+    let blackboard_id = match param(request, 0) {
+        Ok(id) => id as BlackboardId,
+        Err(e) => return Ok(e.into()),
+    };
+    match run.blackboards_mut().get_mut(blackboard_id) {
+        Some(blackboard) => {
+            blackboard.clear();
+            Ok(SyscallOutput::default().into())
+        }
+        None => Ok(ErrorReturnCode::InvalidParam.into()),
+    }
+}
+
+/// P1-5 3.7.2.2.5 - GET_BLACKBOARD_ID
+///
+/// The data holds the name of the blackboard. Returns the id of the
+/// blackboard.
+fn get_blackboard_id(
+    _base: &Base,
+    run: &mut Run,
+    request: &SyscallRequest,
+) -> TypedResult<SyscallReturn> {
+    Ok(object_id(run.blackboards(), request))
+}
+
+/// P1-5 3.7.2.2.6 - GET_BLACKBOARD_STATUS
+///
+/// The only parameter is the id of the blackboard. Returns whether a message
+/// is displayed, the maximum message size and the number of waiting
+/// processes.
+fn get_blackboard_status(
+    _base: &Base,
+    run: &mut Run,
+    request: &SyscallRequest,
+) -> TypedResult<SyscallReturn> {
+    let blackboard_id = match param(request, 0) {
+        Ok(id) => id as BlackboardId,
+        Err(e) => return Ok(e.into()),
+    };
+    let Some(blackboard) = run.blackboards().get(blackboard_id) else {
+        return Ok(ErrorReturnCode::InvalidParam.into());
+    };
+
+    Ok(SyscallOutput {
+        values: vec![
+            blackboard.message().is_some() as u64,
+            blackboard.max_message_size() as u64,
+            blackboard.waiting_processes() as u64,
+        ],
+        data: Vec::new(),
+    }
+    .into())
+}
+
+/// P1-5 3.7.2.3.1 - CREATE_SEMAPHORE
+///
+/// Parameters are the current value, the maximum value and the queuing
+/// discipline. The data holds the name of the semaphore. Returns the id of the
+/// new semaphore.
+fn create_semaphore(
+    _base: &Base,
+    run: &mut Run,
+    request: &SyscallRequest,
+) -> TypedResult<SyscallReturn> {
+    let (name, current_value, maximum_value, discipline) = match (
+        name_param(request),
+        param(request, 0),
+        param(request, 1),
+        discipline_param(request, 2),
+    ) {
+        (Ok(name), Ok(current), Ok(maximum), Ok(discipline)) => (
+            name,
+            current as SemaphoreValue,
+            maximum as SemaphoreValue,
+            discipline,
+        ),
+        (Err(e), ..) | (_, Err(e), ..) | (.., Err(e), _) | (.., Err(e)) => return Ok(e.into()),
+    };
+    if current_value < 0 || maximum_value <= 0 || current_value > maximum_value {
+        return Ok(ErrorReturnCode::InvalidParam.into());
+    }
+    if run.mode() == OperatingMode::Normal {
+        return Ok(ErrorReturnCode::InvalidMode.into());
+    }
+
+    let semaphore = Semaphore::new(name, current_value, maximum_value, discipline);
+    match run.semaphores_mut().create(semaphore) {
+        Ok(id) => Ok(SyscallOutput {
+            values: vec![id as u64],
+            data: Vec::new(),
+        }
+        .into()),
+        Err(e) => Ok(e.into()),
+    }
+}
+
+/// P1-5 3.7.2.3.2 - WAIT_SEMAPHORE
+///
+/// Parameters are the id of the calling process, the id of the semaphore and
+/// the time out. A process waits while the value of the semaphore is zero.
+fn wait_semaphore(
+    _base: &Base,
+    run: &mut Run,
+    request: &SyscallRequest,
+) -> TypedResult<SyscallReturn> {
+    let (id, semaphore_id, time_out) =
+        match (param(request, 0), param(request, 1), time_param(request, 2)) {
+            (Ok(id), Ok(semaphore_id), Ok(time_out)) => {
+                (id as ProcessId, semaphore_id as SemaphoreId, time_out)
+            }
+            (Err(e), ..) | (_, Err(e), _) | (.., Err(e)) => return Ok(e.into()),
+        };
+    let Some(semaphore) = run.semaphores_mut().get_mut(semaphore_id) else {
+        return Ok(ErrorReturnCode::InvalidParam.into());
+    };
+    if semaphore.try_wait() {
+        return Ok(SyscallOutput::default().into());
+    }
+
+    let until = match wait_until(run, id, time_out) {
+        Ok(until) => until,
+        Err(e) => return Ok(e.into()),
+    };
+    let priority = run.processes().get(id).unwrap().current_priority();
+    run.semaphores_mut()
+        .get_mut(semaphore_id)
+        .unwrap()
+        .wait(id, priority);
+    Ok(SyscallReturn::Wait(id, until))
+}
+
+/// P1-5 3.7.2.3.3 - SIGNAL_SEMAPHORE
+///
+/// The only parameter is the id of the semaphore. The first waiting process
+/// takes the signal right away.
+fn signal_semaphore(
+    _base: &Base,
+    run: &mut Run,
+    request: &SyscallRequest,
+) -> TypedResult<SyscallReturn> {
+    let semaphore_id = match param(request, 0) {
+        Ok(id) => id as SemaphoreId,
+        Err(e) => return Ok(e.into()),
+    };
+    let Some(semaphore) = run.semaphores_mut().get_mut(semaphore_id) else {
+        return Ok(ErrorReturnCode::InvalidParam.into());
+    };
+
+    match semaphore.signal() {
+        Ok(Some(waiting)) => {
+            if let Some(waiting) = run.processes_mut().get_mut(waiting) {
+                waiting.wake(Ok(SyscallOutput::default()))?;
+            }
+            Ok(SyscallOutput::default().into())
+        }
+        Ok(None) => Ok(SyscallOutput::default().into()),
+        Err(e) => Ok(e.into()),
+    }
+}
+
+/// P1-5 3.7.2.3.4 - GET_SEMAPHORE_ID
+///
+/// The data holds the name of the semaphore. Returns the id of the semaphore.
+fn get_semaphore_id(
+    _base: &Base,
+    run: &mut Run,
+    request: &SyscallRequest,
+) -> TypedResult<SyscallReturn> {
+    Ok(object_id(run.semaphores(), request))
+}
+
+/// P1-5 3.7.2.3.5 - GET_SEMAPHORE_STATUS
+///
+/// The only parameter is the id of the semaphore. Returns the current value,
+/// the maximum value and the number of waiting processes.
+fn get_semaphore_status(
+    _base: &Base,
+    run: &mut Run,
+    request: &SyscallRequest,
+) -> TypedResult<SyscallReturn> {
+    let semaphore_id = match param(request, 0) {
+        Ok(id) => id as SemaphoreId,
+        Err(e) => return Ok(e.into()),
+    };
+    let Some(semaphore) = run.semaphores().get(semaphore_id) else {
+        return Ok(ErrorReturnCode::InvalidParam.into());
+    };
+
+    Ok(SyscallOutput {
+        values: vec![
+            semaphore.current_value() as u64,
+            semaphore.maximum_value() as u64,
+            semaphore.waiting_processes() as u64,
+        ],
+        data: Vec::new(),
+    }
+    .into())
+}
+
+/// P1-5 3.7.2.4.1 - CREATE_EVENT
+///
+/// The data holds the name of the event. Returns the id of the new event,
+/// which is down.
+fn create_event(
+    _base: &Base,
+    run: &mut Run,
+    request: &SyscallRequest,
+) -> TypedResult<SyscallReturn> {
+    let name = match name_param(request) {
+        Ok(name) => name,
+        Err(e) => return Ok(e.into()),
+    };
+    if run.mode() == OperatingMode::Normal {
+        return Ok(ErrorReturnCode::InvalidMode.into());
+    }
+
+    match run.events_mut().create(Event::new(name)) {
+        Ok(id) => Ok(SyscallOutput {
+            values: vec![id as u64],
+            data: Vec::new(),
+        }
+        .into()),
+        Err(e) => Ok(e.into()),
+    }
+}
+
+/// P1-5 3.7.2.4.2 - SET_EVENT
+///
+/// The only parameter is the id of the event. All waiting processes are woken
+/// up.
+fn set_event(_base: &Base, run: &mut Run, request: &SyscallRequest) -> TypedResult<SyscallReturn> {
+    let event_id = match param(request, 0) {
+        Ok(id) => id as EventId,
+        Err(e) => return Ok(e.into()),
+    };
+    let Some(event) = run.events_mut().get_mut(event_id) else {
+        return Ok(ErrorReturnCode::InvalidParam.into());
+    };
+
+    for waiting in event.set() {
+        if let Some(waiting) = run.processes_mut().get_mut(waiting) {
+            waiting.wake(Ok(SyscallOutput::default()))?;
+        }
+    }
+    Ok(SyscallOutput::default().into())
+}
+
+/// P1-5 3.7.2.4.3 - RESET_EVENT
+///
+/// The only parameter is the id of the event.
+fn reset_event(
+    _base: &Base,
+    run: &mut Run,
+    request: &SyscallRequest,
+) -> TypedResult<SyscallReturn> {
+    let event_id = match param(request, 0) {
+        Ok(id) => id as EventId,
+        Err(e) => return Ok(e.into()),
+    };
+    match run.events_mut().get_mut(event_id) {
+        Some(event) => {
+            event.reset();
+            Ok(SyscallOutput::default().into())
+        }
+        None => Ok(ErrorReturnCode::InvalidParam.into()),
+    }
+}
+
+/// P1-5 3.7.2.4.4 - WAIT_EVENT
+///
+/// Parameters are the id of the calling process, the id of the event and the
+/// time out. A process waits while the event is down.
+fn wait_event(_base: &Base, run: &mut Run, request: &SyscallRequest) -> TypedResult<SyscallReturn> {
+    let (id, event_id, time_out) =
+        match (param(request, 0), param(request, 1), time_param(request, 2)) {
+            (Ok(id), Ok(event_id), Ok(time_out)) => {
+                (id as ProcessId, event_id as EventId, time_out)
+            }
+            (Err(e), ..) | (_, Err(e), _) | (.., Err(e)) => return Ok(e.into()),
+        };
+    let Some(event) = run.events().get(event_id) else {
+        return Ok(ErrorReturnCode::InvalidParam.into());
+    };
+    if event.state() == EventState::Up {
+        return Ok(SyscallOutput::default().into());
+    }
+
+    let until = match wait_until(run, id, time_out) {
+        Ok(until) => until,
+        Err(e) => return Ok(e.into()),
+    };
+    let priority = run.processes().get(id).unwrap().current_priority();
+    run.events_mut()
+        .get_mut(event_id)
+        .unwrap()
+        .wait(id, priority);
+    Ok(SyscallReturn::Wait(id, until))
+}
+
+/// P1-5 3.7.2.4.5 - GET_EVENT_ID
+///
+/// The data holds the name of the event. Returns the id of the event.
+fn get_event_id(
+    _base: &Base,
+    run: &mut Run,
+    request: &SyscallRequest,
+) -> TypedResult<SyscallReturn> {
+    Ok(object_id(run.events(), request))
+}
+
+/// P1-5 3.7.2.4.6 - GET_EVENT_STATUS
+///
+/// The only parameter is the id of the event. Returns whether the event is up
+/// and the number of waiting processes.
+fn get_event_status(
+    _base: &Base,
+    run: &mut Run,
+    request: &SyscallRequest,
+) -> TypedResult<SyscallReturn> {
+    let event_id = match param(request, 0) {
+        Ok(id) => id as EventId,
+        Err(e) => return Ok(e.into()),
+    };
+    let Some(event) = run.events().get(event_id) else {
+        return Ok(ErrorReturnCode::InvalidParam.into());
+    };
+
+    Ok(SyscallOutput {
+        values: vec![
+            (event.state() == EventState::Up) as u64,
+            event.waiting_processes() as u64,
+        ],
+        data: Vec::new(),
+    }
+    .into())
+}
+
+/// P1-5 3.7.2.5.1 - CREATE_MUTEX
+///
+/// Parameters are the priority of the mutex and the queuing discipline. The
+/// data holds the name of the mutex. Returns the id of the new mutex.
+fn create_mutex(
+    _base: &Base,
+    run: &mut Run,
+    request: &SyscallRequest,
+) -> TypedResult<SyscallReturn> {
+    let (name, priority, discipline) = match (
+        name_param(request),
+        param(request, 0),
+        discipline_param(request, 1),
+    ) {
+        (Ok(name), Ok(priority), Ok(discipline)) => (name, priority as Priority, discipline),
+        (Err(e), ..) | (_, Err(e), _) | (.., Err(e)) => return Ok(e.into()),
+    };
+    if !(MIN_PRIORITY..=MAX_PRIORITY).contains(&priority) {
+        return Ok(ErrorReturnCode::InvalidParam.into());
+    }
+    if run.mode() == OperatingMode::Normal {
+        return Ok(ErrorReturnCode::InvalidMode.into());
+    }
+
+    match run
+        .mutexes_mut()
+        .create(Mutex::new(name, priority, discipline))
+    {
+        Ok(id) => Ok(SyscallOutput {
+            values: vec![id as u64],
+            data: Vec::new(),
+        }
+        .into()),
+        Err(e) => Ok(e.into()),
+    }
+}
+
+/// P1-5 3.7.2.5.2 - ACQUIRE_MUTEX
+///
+/// Parameters are the id of the calling process, the id of the mutex and the
+/// time out. A process waits while another process owns the mutex. The owner
+/// runs with the priority of the mutex.
+fn acquire_mutex(
+    _base: &Base,
+    run: &mut Run,
+    request: &SyscallRequest,
+) -> TypedResult<SyscallReturn> {
+    let (id, mutex_id, time_out) =
+        match (param(request, 0), param(request, 1), time_param(request, 2)) {
+            (Ok(id), Ok(mutex_id), Ok(time_out)) => {
+                (id as ProcessId, mutex_id as MutexId, time_out)
+            }
+            (Err(e), ..) | (_, Err(e), _) | (.., Err(e)) => return Ok(e.into()),
+        };
+    let Some(mutex) = run.mutexes().get(mutex_id) else {
+        return Ok(ErrorReturnCode::InvalidParam.into());
+    };
+    let priority = mutex.priority();
+    // Only processes may own a mutex, and only a single one at a time
+    let Some(process) = run.processes().get(id) else {
+        return Ok(ErrorReturnCode::InvalidMode.into());
+    };
+    if run
+        .mutexes()
+        .find(|m| m.owner() == Some(id))
+        .is_some_and(|owned| owned != mutex_id)
+    {
+        return Ok(ErrorReturnCode::InvalidMode.into());
+    }
+    if process.current_priority() > priority {
+        return Ok(ErrorReturnCode::InvalidConfig.into());
+    }
+
+    match run.mutexes_mut().get_mut(mutex_id).unwrap().try_acquire(id) {
+        Ok(true) => {
+            run.processes_mut()
+                .get_mut(id)
+                .unwrap()
+                .raise_priority(priority);
+            return Ok(SyscallOutput::default().into());
+        }
+        Ok(false) => {}
+        Err(e) => return Ok(e.into()),
+    }
+
+    // Another process owns the mutex
+    let until = match wait_until(run, id, time_out) {
+        Ok(until) => until,
+        Err(e) => return Ok(e.into()),
+    };
+    let current_priority = run.processes().get(id).unwrap().current_priority();
+    run.mutexes_mut()
+        .get_mut(mutex_id)
+        .unwrap()
+        .wait(id, current_priority);
+    Ok(SyscallReturn::Wait(id, until))
+}
+
+/// P1-5 3.7.2.5.3 - RELEASE_MUTEX
+///
+/// Parameters are the id of the calling process and the id of the mutex. Once
+/// the mutex is released completely, its owner returns to its previous
+/// priority and the first waiting process acquires the mutex.
+fn release_mutex(
+    _base: &Base,
+    run: &mut Run,
+    request: &SyscallRequest,
+) -> TypedResult<SyscallReturn> {
+    let (id, mutex_id) = match (param(request, 0), param(request, 1)) {
+        (Ok(id), Ok(mutex_id)) => (id as ProcessId, mutex_id as MutexId),
+        (Err(e), _) | (_, Err(e)) => return Ok(e.into()),
+    };
+    let Some(mutex) = run.mutexes_mut().get_mut(mutex_id) else {
+        return Ok(ErrorReturnCode::InvalidParam.into());
+    };
+
+    match mutex.release(id) {
+        Ok(true) => {
+            if let Some(process) = run.processes_mut().get_mut(id) {
+                process.restore_priority();
+            }
+            run.hand_over_mutex(mutex_id)?;
+            Ok(SyscallOutput::default().into())
+        }
+        Ok(false) => Ok(SyscallOutput::default().into()),
+        Err(e) => Ok(e.into()),
+    }
+}
+
+/// P1-5 3.7.2.5.4 - RESET_MUTEX
+///
+/// Parameters are the id of the mutex and the id of its owner. The mutex is
+/// released completely, as if its owner released it.
+fn reset_mutex(
+    _base: &Base,
+    run: &mut Run,
+    request: &SyscallRequest,
+) -> TypedResult<SyscallReturn> {
+    let (mutex_id, id) = match (param(request, 0), param(request, 1)) {
+        (Ok(mutex_id), Ok(id)) => (mutex_id as MutexId, id as ProcessId),
+        (Err(e), _) | (_, Err(e)) => return Ok(e.into()),
+    };
+    if run.processes().get(id).is_none() {
+        return Ok(ErrorReturnCode::InvalidParam.into());
+    }
+    let Some(mutex) = run.mutexes_mut().get_mut(mutex_id) else {
+        return Ok(ErrorReturnCode::InvalidParam.into());
+    };
+    if mutex.owner() != Some(id) {
+        return Ok(ErrorReturnCode::InvalidMode.into());
+    }
+
+    mutex.reset();
+    run.processes_mut().get_mut(id).unwrap().restore_priority();
+    run.hand_over_mutex(mutex_id)?;
+    Ok(SyscallOutput::default().into())
+}
+
+/// P1-5 3.7.2.5.5 - GET_MUTEX_ID
+///
+/// The data holds the name of the mutex. Returns the id of the mutex.
+fn get_mutex_id(
+    _base: &Base,
+    run: &mut Run,
+    request: &SyscallRequest,
+) -> TypedResult<SyscallReturn> {
+    Ok(object_id(run.mutexes(), request))
+}
+
+/// P1-5 3.7.2.5.6 - GET_MUTEX_STATUS
+///
+/// The only parameter is the id of the mutex. Returns whether the mutex is
+/// owned, its owner, its priority, its lock count and the number of waiting
+/// processes.
+fn get_mutex_status(
+    _base: &Base,
+    run: &mut Run,
+    request: &SyscallRequest,
+) -> TypedResult<SyscallReturn> {
+    let mutex_id = match param(request, 0) {
+        Ok(id) => id as MutexId,
+        Err(e) => return Ok(e.into()),
+    };
+    let Some(mutex) = run.mutexes().get(mutex_id) else {
+        return Ok(ErrorReturnCode::InvalidParam.into());
+    };
+
+    Ok(SyscallOutput {
+        values: vec![
+            mutex.owner().is_some() as u64,
+            mutex.owner().unwrap_or_default() as u64,
+            mutex.priority() as u64,
+            mutex.lock_count() as u64,
+            mutex.waiting_processes() as u64,
+        ],
+        data: Vec::new(),
+    }
+    .into())
+}
+
+/// P1-5 3.7.2.5.7 - GET_PROCESS_MUTEX_STATE
+///
+/// The only parameter is the id of the process. Returns the id of the mutex
+/// the process owns, or [PartitionConstants::NO_MUTEX_OWNED].
+fn get_process_mutex_state(
+    _base: &Base,
+    run: &mut Run,
+    request: &SyscallRequest,
+) -> TypedResult<SyscallReturn> {
+    let id = match param(request, 0) {
+        Ok(id) => id as ProcessId,
+        Err(e) => return Ok(e.into()),
+    };
+    if run.processes().get(id).is_none() {
+        return Ok(ErrorReturnCode::InvalidParam.into());
+    }
+
+    let mutex_id = run
+        .mutexes()
+        .find(|m| m.owner() == Some(id))
+        .unwrap_or(PartitionConstants::NO_MUTEX_OWNED);
+    Ok(SyscallOutput {
+        values: vec![mutex_id as u64],
+        data: Vec::new(),
+    }
+    .into())
+}
+
+/// P1-5 3.8.2.1 - REPORT_APPLICATION_MESSAGE
+fn report_application_message(
+    base: &Base,
+    _run: &mut Run,
+    request: &SyscallRequest,
+) -> TypedResult<SyscallReturn> {
+    let msg = String::from_utf8_lossy(&request.data).into_owned();
+    PartitionCall::Message(msg).print_partition_log(base.name());
+    Ok(SyscallOutput::default().into())
+}
+
+/// P1-5 3.8.2.2 - CREATE_ERROR_HANDLER
+///
+/// Returns the id and the priority of the error handler, whose thread the
+/// partition creates right away. The error handler remains dormant until an
+/// error of a process activates it.
+fn create_error_handler(
+    _base: &Base,
+    run: &mut Run,
+    _request: &SyscallRequest,
+) -> TypedResult<SyscallReturn> {
+    if run.mode() == OperatingMode::Normal {
+        return Ok(ErrorReturnCode::InvalidMode.into());
+    }
+
+    let id = match run.create_error_handler()? {
+        Ok(id) => id,
+        Err(e) => return Ok(e.into()),
+    };
+    Ok(SyscallOutput {
+        values: vec![id as u64, ERROR_HANDLER_PRIORITY as u64],
+        data: Vec::new(),
+    }
+    .into())
+}
+
+/// P1-5 3.8.2.3 - GET_ERROR_STATUS
+///
+/// The only parameter is the id of the calling process, which has to be the
+/// error handler. Returns the code of the oldest queued error and the id of
+/// the failed process. The data holds the message of the error.
+fn get_error_status(
+    _base: &Base,
+    run: &mut Run,
+    request: &SyscallRequest,
+) -> TypedResult<SyscallReturn> {
+    let id = match param(request, 0) {
+        Ok(id) => id as ProcessId,
+        Err(e) => return Ok(e.into()),
+    };
+    if run.error_handler() != Some(id) {
+        return Ok(ErrorReturnCode::InvalidConfig.into());
+    }
+    let Some(error) = run.next_error() else {
+        return Ok(ErrorReturnCode::NoAction.into());
+    };
+
+    Ok(SyscallOutput {
+        values: vec![error_code_to_u64(error.code), error.process as u64],
+        data: error.message,
+    }
+    .into())
+}
+
+/// P1-5 3.8.2.4 - RAISE_APPLICATION_ERROR
+///
+/// The only parameter is the id of the calling process. The data holds the
+/// message of the error. The error is delivered to the error handler, if
+/// there is one.
+fn raise_application_error(
+    base: &Base,
+    run: &mut Run,
+    request: &SyscallRequest,
+) -> TypedResult<SyscallReturn> {
+    let id = match param(request, 0) {
+        Ok(id) => id as ProcessId,
+        Err(e) => return Ok(e.into()),
+    };
+    if request.data.len() > MAX_ERROR_MESSAGE_SIZE {
+        return Ok(ErrorReturnCode::InvalidParam.into());
+    }
+
+    let error = ProcessError {
+        code: ErrorCode::ApplicationError,
+        process: id,
+        message: request.data.clone(),
+    };
+    run.report_error(base, error, SystemError::ApplicationError)?;
+    Ok(SyscallOutput::default().into())
+}
+
+/// P2-4 3.7.2.1 - SET_MODULE_SCHEDULE
+///
+/// The new schedule takes effect at the end of the current major frame.
+fn set_module_schedule(
+    base: &Base,
+    _run: &mut Run,
+    request: &SyscallRequest,
+) -> TypedResult<SyscallReturn> {
+    let id = match param(request, 0) {
+        Ok(id) => id as usize,
+        Err(e) => return Ok(e.into()),
+    };
+    if !base.privileged() {
+        return Ok(ErrorReturnCode::InvalidConfig.into());
+    }
+    if !base.schedules().borrow_mut().set_next(id) {
+        return Ok(ErrorReturnCode::InvalidParam.into());
+    }
+    info!(
+        "Partition \"{}\" requested module schedule {id}",
+        base.name()
+    );
+    Ok(SyscallOutput::default().into())
+}
+
+/// P2-4 3.7.2.2 - GET_MODULE_SCHEDULE_STATUS
+///
+/// The time of the last schedule switch is zero, if the schedule was never
+/// switched.
+fn get_module_schedule_status(
+    base: &Base,
+    _run: &mut Run,
+    _request: &SyscallRequest,
+) -> TypedResult<SyscallReturn> {
+    let schedules = base.schedules().borrow();
+    let last_switch = match schedules.last_switch() {
+        Some(instant) => system_time(Some(instant))?,
+        None => 0,
+    };
+    Ok(SyscallOutput {
+        values: vec![
+            last_switch as u64,
+            schedules.current() as u64,
+            schedules.next() as u64,
+        ],
+        data: Vec::new(),
+    }
+    .into())
+}
+
+/// P2-4 3.7.2.3 - GET_MODULE_SCHEDULE_ID
+fn get_module_schedule_id(
+    base: &Base,
+    _run: &mut Run,
+    request: &SyscallRequest,
+) -> TypedResult<SyscallReturn> {
+    let name = match name_param(request) {
+        Ok(name) => name,
+        Err(e) => return Ok(e.into()),
+    };
+    Ok(match base.schedules().borrow().id(&name) {
+        Some(id) => SyscallOutput {
+            values: vec![id as u64],
+            data: Vec::new(),
+        }
+        .into(),
+        None => ErrorReturnCode::InvalidConfig.into(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+    use std::net::{TcpStream, UdpSocket};
+    use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+    use std::os::unix::net::UnixDatagram;
+    use std::path::PathBuf;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use a653rs::bindings::{Deadline, INFINITE_TIME_VALUE};
+    use a653rs_linux_core::cgroup::{self, CGroup};
+    use a653rs_linux_core::channel::{PortConfig, QueuingChannelConfig};
+    use a653rs_linux_core::file::TempFile;
+    use a653rs_linux_core::health::{PartitionHMTable, ProcessHMTable};
+    use a653rs_linux_core::ipc::{bind_receiver, io_pair};
+    use a653rs_linux_core::mfd::Mfd;
+    use a653rs_linux_core::queuing::{Queuing, QueuingSource};
+    use a653rs_linux_core::syscall::{deadline_to_u64, SyscallResponse};
+    use bytesize::ByteSize;
+
+    use super::*;
+    use crate::hypervisor::intra::{
+        MAX_BLACKBOARDS, MAX_BUFFERS, MAX_EVENTS, MAX_LOCK_COUNT, MAX_MUTEXES, MAX_SEMAPHORES,
+    };
+    use crate::hypervisor::process::{Process, ProcessTable};
+    use crate::hypervisor::scheduler::Scheduler;
+
+    const PERIOD: Duration = Duration::from_millis(10);
+    /// Time out of a process, which waits until it is woken up
+    const INFINITE: u64 = INFINITE_TIME_VALUE as u64;
+    /// Time out of a process, which does not wait at all
+    const NO_WAIT: u64 = 0;
+    /// Time out of a process, which waits for a moment
+    const SHORT: u64 = 1_000_000;
+    const FIFO: u64 = 0;
+    const PRIORITY: u64 = 1;
+
+    /// A partition without a main process, whose processes only exist as
+    /// cgroups. Their system calls are dispatched right away.
+    struct Partition {
+        base: Base,
+        run: Run,
+    }
+
+    impl Partition {
+        fn new() -> Self {
+            static PARTITIONS: AtomicUsize = AtomicUsize::new(0);
+            let name = format!(
+                "apex-test-{}-{}",
+                std::process::id(),
+                PARTITIONS.fetch_add(1, Ordering::Relaxed)
+            );
+            let root = cgroup::mount_point()
+                .unwrap()
+                .join(cgroup::current_cgroup().unwrap());
+            let cgroup = CGroup::new_root(root, &name).unwrap();
+            let working_dir = tempfile::tempdir().unwrap();
+
+            let cgroup_processes = cgroup.new(PartitionConstants::PROCESSES_CGROUP).unwrap();
+            let mode_file = TempFile::create("operation_mode").unwrap();
+            let run = Run {
+                _cgroup_main: cgroup_processes
+                    .new_threaded(PartitionConstants::MAIN_PROCESS_CGROUP)
+                    .unwrap(),
+                cgroup_stopped: cgroup_processes
+                    .new_threaded(PartitionConstants::STOPPED_PROCESS_CGROUP)
+                    .unwrap(),
+                cgroup_processes,
+                main: nix::unistd::getpid(),
+                processes: ProcessTable::default(),
+                buffers: ObjectTable::new(MAX_BUFFERS),
+                blackboards: ObjectTable::new(MAX_BLACKBOARDS),
+                semaphores: ObjectTable::new(MAX_SEMAPHORES),
+                events: ObjectTable::new(MAX_EVENTS),
+                mutexes: ObjectTable::new(MAX_MUTEXES),
+                queuing_ports: HashMap::new(),
+                error_handler: None,
+                errors: VecDeque::new(),
+                module_error: false,
+                mode: OperatingMode::ColdStart,
+                _mode_file_fd: unsafe {
+                    OwnedFd::from_raw_fd(nix::unistd::dup(mode_file.as_raw_fd()).unwrap())
+                },
+                mode_file,
+                call_rx: bind_receiver(&working_dir.path().join("ipc")).unwrap(),
+                syscall_rx: UnixDatagram::unbound().unwrap(),
+                _io_udp_tx: io_pair::<UdpSocket>().unwrap().0,
+                _io_tcp_tx: io_pair::<TcpStream>().unwrap().0,
+            };
+            let base = Base {
+                name,
+                hm: PartitionHMTable::default(),
+                process_hm: ProcessHMTable::default(),
+                id: 1,
+                bin: PathBuf::new(),
+                mounts: Vec::new(),
+                cgroup,
+                sampling_channel: HashMap::new(),
+                queuing_channel: HashMap::new(),
+                duration: PERIOD,
+                period: PERIOD,
+                working_dir,
+                sockets: Vec::new(),
+                persistent_memory: None,
+                privileged: false,
+                tmpfs_size: ByteSize::kb(500),
+                schedules: Scheduler::new(Vec::new()).status(),
+            };
+            Self { base, run }
+        }
+
+        /// Dispatches a system call. Returns the memfd, from which its
+        /// response can be read once it was answered.
+        fn call(&mut self, id: ApexSyscall, params: Vec<u64>, data: &[u8]) -> Mfd {
+            let request = SyscallRequest {
+                id,
+                params,
+                data: data.to_vec(),
+            };
+            let (syscall, response) = Syscall::fake(request);
+            dispatch(&self.base, &mut self.run, syscall).unwrap();
+            response
+        }
+
+        /// Dispatches a system call, which has to be answered right away
+        fn call_done(
+            &mut self,
+            id: ApexSyscall,
+            params: Vec<u64>,
+            data: &[u8],
+        ) -> Result<SyscallResponse, ErrorReturnCode> {
+            let mut response = self.call(id, params, data);
+            answer(&mut response).expect("system call to be answered")
+        }
+
+        /// Creates a process in the cold start mode
+        fn create_process(
+            &mut self,
+            name: &str,
+            period: SystemTime,
+            capacity: SystemTime,
+            priority: Priority,
+        ) -> ProcessId {
+            let params = vec![
+                ApexSystemTime::from(period) as u64,
+                ApexSystemTime::from(capacity) as u64,
+                priority as u64,
+                deadline_to_u64(Deadline::Hard),
+            ];
+            let response = self
+                .call_done(ApexSyscall::CreateProcess, params, name.as_bytes())
+                .unwrap();
+            response.values[0] as ProcessId
+        }
+
+        /// Creates an aperiodic process without a deadline
+        fn aperiodic(&mut self, name: &str, priority: Priority) -> ProcessId {
+            self.create_process(name, SystemTime::Infinite, SystemTime::Infinite, priority)
+        }
+
+        /// Creates an intra-partition communication object and returns its id
+        fn create(&mut self, id: ApexSyscall, params: Vec<u64>, name: &str) -> u64 {
+            self.call_done(id, params, name.as_bytes()).unwrap().values[0]
+        }
+
+        /// Returns the values of a system call, which succeeds right away
+        fn values(&mut self, id: ApexSyscall, params: Vec<u64>) -> Vec<u64> {
+            self.call_done(id, params, &[]).unwrap().values
+        }
+
+        /// Returns the data of a system call, which is answered right away
+        fn data(
+            &mut self,
+            id: ApexSyscall,
+            params: Vec<u64>,
+            data: &[u8],
+        ) -> Result<Vec<u8>, ErrorReturnCode> {
+            self.call_done(id, params, data)
+                .map(|response| response.data)
+        }
+
+        /// Lets the short time outs of all waiting processes expire
+        fn expire_timeouts(&mut self) {
+            std::thread::sleep(Duration::from_nanos(SHORT) * 2);
+            self.run.expire_timeouts(&self.base).unwrap();
+        }
+
+        /// Enters the normal mode and starts all processes except for the
+        /// error handler
+        fn start(&mut self) {
+            self.run.mode = OperatingMode::Normal;
+            let ids: Vec<_> = self
+                .run
+                .processes()
+                .iter()
+                .map(|p| p.id())
+                .filter(|&id| self.run.error_handler() != Some(id))
+                .collect();
+            for id in ids {
+                self.call_done(ApexSyscall::Start, vec![id as u64], &[])
+                    .unwrap();
+            }
+        }
+
+        fn process(&self, id: ProcessId) -> &Process {
+            self.run.processes().get(id).unwrap()
+        }
+    }
+
+    impl Drop for Partition {
+        fn drop(&mut self) {
+            self.base.cgroup.rm().unwrap();
+        }
+    }
+
+    /// Reads the response of an answered system call
+    fn answer(response: &mut Mfd) -> Option<Result<SyscallResponse, ErrorReturnCode>> {
+        let response = response.read_all().unwrap();
+        if response.is_empty() {
+            return None;
+        }
+        let response = SyscallResponse::deserialize(&response).unwrap();
+        Some(match response.result().unwrap() {
+            Ok(()) => Ok(response),
+            Err(e) => Err(e),
+        })
+    }
+
+    /// Returns the data of the answer of a system call, which may still wait
+    fn woken(response: &mut Mfd) -> Option<Result<Vec<u8>, ErrorReturnCode>> {
+        answer(response).map(|result| result.map(|response| response.data))
+    }
+
+    #[test]
+    fn periodic_wait_deadline() {
+        let mut partition = Partition::new();
+        let capacity = Duration::from_millis(5);
+        let id = partition.create_process(
+            "periodic",
+            SystemTime::Normal(PERIOD * 2),
+            SystemTime::Normal(capacity),
+            10,
+        );
+        partition.start();
+        let release = partition.process(id).deadline_time().unwrap() - capacity;
+
+        // The process finishes its activation early
+        let mut response = partition.call(ApexSyscall::PeriodicWait, vec![id as u64], &[]);
+        assert!(answer(&mut response).is_none());
+        let next_release = release + PERIOD * 2;
+        assert_eq!(partition.process(id).timeout(), Some(next_release));
+        assert_eq!(
+            partition.process(id).deadline_time(),
+            Some(next_release + capacity)
+        );
+
+        // No deadline is missed while the process waits for its next release
+        let process = partition.run.processes_mut().get_mut(id).unwrap();
+        assert!(!process.miss_deadline(release + capacity));
+        assert!(!process.miss_deadline(next_release));
+        assert!(process.miss_deadline(next_release + capacity));
+    }
+
+    #[test]
+    fn suspend_waiting() {
+        use ApexSyscall::{Resume, SignalSemaphore, Suspend, WaitSemaphore};
+        let mut partition = Partition::new();
+        let semaphore = partition.create(ApexSyscall::CreateSemaphore, vec![0, 1, FIFO], "s");
+        let id = partition.aperiodic("waiting", 10);
+        partition.start();
+
+        // A process may be suspended while it waits
+        let mut response = partition.call(WaitSemaphore, vec![id as u64, semaphore, INFINITE], &[]);
+        assert_eq!(partition.data(Suspend, vec![id as u64], &[]), Ok(vec![]));
+        assert!(partition.process(id).suspended());
+
+        // It finishes waiting, but remains suspended until it is resumed
+        assert_eq!(
+            partition.data(SignalSemaphore, vec![semaphore], &[]),
+            Ok(vec![])
+        );
+        assert_eq!(woken(&mut response), Some(Ok(vec![])));
+        assert_eq!(partition.process(id).state(), ProcessState::Waiting);
+        assert_eq!(partition.data(Resume, vec![id as u64], &[]), Ok(vec![]));
+        assert_ne!(partition.process(id).state(), ProcessState::Waiting);
+        assert_eq!(
+            partition.data(Resume, vec![id as u64], &[]),
+            Err(ErrorReturnCode::NoAction)
+        );
+    }
+
+    #[test]
+    fn suspend_self_time_out() {
+        use ApexSyscall::{Resume, SuspendSelf};
+        let mut partition = Partition::new();
+        let id = partition.aperiodic("suspended", 10);
+        partition.start();
+
+        // Resuming a process ends its suspension before the time out
+        let mut response = partition.call(SuspendSelf, vec![id as u64, SHORT * 1000], &[]);
+        assert!(woken(&mut response).is_none());
+        assert!(partition.process(id).timeout().is_some());
+        assert_eq!(partition.data(Resume, vec![id as u64], &[]), Ok(vec![]));
+        assert_eq!(woken(&mut response), Some(Ok(vec![])));
+        assert_eq!(partition.process(id).timeout(), None);
+        assert!(!partition.process(id).suspended());
+
+        // Otherwise the process is resumed, once the time out expired
+        let mut response = partition.call(SuspendSelf, vec![id as u64, SHORT], &[]);
+        partition.expire_timeouts();
+        assert_eq!(woken(&mut response), Some(Err(ErrorReturnCode::TimedOut)));
+        assert!(!partition.process(id).suspended());
+        assert_eq!(
+            partition.data(Resume, vec![id as u64], &[]),
+            Err(ErrorReturnCode::NoAction)
+        );
+    }
+
+    #[test]
+    fn stop_mutex_owner() {
+        use ApexSyscall::{AcquireMutex, GetMutexStatus, Stop};
+        let mut partition = Partition::new();
+        let mutex = partition.create(ApexSyscall::CreateMutex, vec![30, FIFO], "mutex");
+        let owner = partition.aperiodic("owner", 10);
+        let waiting = partition.aperiodic("waiting", 20) as u64;
+        partition.start();
+
+        let acquire = vec![owner as u64, mutex, NO_WAIT];
+        assert_eq!(partition.data(AcquireMutex, acquire, &[]), Ok(vec![]));
+        let mut acquired = partition.call(AcquireMutex, vec![waiting, mutex, INFINITE], &[]);
+
+        // The mutex of a stopped process is handed over to the next waiting one
+        assert_eq!(partition.data(Stop, vec![owner as u64], &[]), Ok(vec![]));
+        assert_eq!(partition.process(owner).state(), ProcessState::Dormant);
+        assert_eq!(woken(&mut acquired), Some(Ok(vec![])));
+        assert_eq!(
+            partition.values(GetMutexStatus, vec![mutex]),
+            [1, waiting, 30, 1, 0]
+        );
+        assert_eq!(
+            partition.data(Stop, vec![owner as u64], &[]),
+            Err(ErrorReturnCode::NoAction)
+        );
+    }
+
+    #[test]
+    fn delayed_start() {
+        use ApexSyscall::DelayStart;
+        let mut partition = Partition::new();
+        let periodic = partition.create_process(
+            "periodic",
+            SystemTime::Normal(PERIOD),
+            SystemTime::Normal(PERIOD),
+            10,
+        );
+        let cold = partition.aperiodic("cold", 10);
+        let normal = partition.aperiodic("normal", 10);
+        assert_eq!(
+            partition.data(
+                DelayStart,
+                vec![periodic as u64, PERIOD.as_nanos() as u64],
+                &[]
+            ),
+            Err(ErrorReturnCode::InvalidParam)
+        );
+
+        // Before the normal mode, the delay only begins with the normal mode
+        assert_eq!(
+            partition.data(DelayStart, vec![cold as u64, SHORT], &[]),
+            Ok(vec![])
+        );
+        assert_eq!(partition.process(cold).state(), ProcessState::Waiting);
+        assert_eq!(partition.process(cold).timeout(), None);
+        assert_eq!(
+            partition.data(DelayStart, vec![cold as u64, SHORT], &[]),
+            Err(ErrorReturnCode::NoAction)
+        );
+        partition.run.mode = OperatingMode::Normal;
+        let begin = Instant::now();
+        partition
+            .run
+            .processes_mut()
+            .begin_normal_mode(None)
+            .unwrap();
+        let timeout = partition.process(cold).timeout().unwrap();
+        assert!(timeout >= begin + Duration::from_nanos(SHORT));
+
+        // In the normal mode, the delay begins right away
+        assert_eq!(
+            partition.data(DelayStart, vec![normal as u64, SHORT], &[]),
+            Ok(vec![])
+        );
+        assert_eq!(partition.process(normal).state(), ProcessState::Waiting);
+        assert!(partition.process(normal).timeout().is_some());
+
+        partition.expire_timeouts();
+        for id in [cold, normal] {
+            assert_ne!(partition.process(id).state(), ProcessState::Waiting);
+            assert_eq!(partition.process(id).timeout(), None);
+        }
+    }
+
+    #[test]
+    fn periodic_wait_release() {
+        let mut partition = Partition::new();
+        let id = partition.create_process(
+            "periodic",
+            SystemTime::Normal(PERIOD),
+            SystemTime::Normal(PERIOD),
+            10,
+        );
+        let aperiodic = partition.aperiodic("aperiodic", 10);
+
+        // Neither a periodic process before its first release nor an
+        // aperiodic process have a release point to wait for
+        let result = partition.call_done(ApexSyscall::PeriodicWait, vec![id as u64], &[]);
+        assert_eq!(result.unwrap_err(), ErrorReturnCode::InvalidMode);
+        partition.start();
+        let result = partition.call_done(ApexSyscall::PeriodicWait, vec![aperiodic as u64], &[]);
+        assert_eq!(result.unwrap_err(), ErrorReturnCode::InvalidMode);
+        let result = partition.call_done(ApexSyscall::PeriodicWait, vec![42], &[]);
+        assert_eq!(result.unwrap_err(), ErrorReturnCode::InvalidParam);
+
+        // The process is released again at its next release point
+        let next_release = partition.process(id).next_release().unwrap();
+        let mut response = partition.call(ApexSyscall::PeriodicWait, vec![id as u64], &[]);
+        assert!(woken(&mut response).is_none());
+        std::thread::sleep(next_release.saturating_duration_since(Instant::now()));
+        partition.run.expire_timeouts(&partition.base).unwrap();
+        assert_eq!(woken(&mut response), Some(Ok(Vec::new())));
+        assert_eq!(
+            partition.process(id).next_release(),
+            Some(next_release + PERIOD)
+        );
+        assert_eq!(
+            partition.process(id).deadline_time(),
+            Some(next_release + PERIOD)
+        );
+    }
+
+    #[test]
+    fn timed_wait() {
+        let mut partition = Partition::new();
+        let id = partition.aperiodic("waiting", 10) as u64;
+        partition.start();
+
+        // A delay of zero only yields to the processes of the same priority
+        let result = partition.call_done(ApexSyscall::TimedWait, vec![id, NO_WAIT], &[]);
+        assert!(result.is_ok());
+        let result = partition.call_done(ApexSyscall::TimedWait, vec![id, INFINITE], &[]);
+        assert_eq!(result.unwrap_err(), ErrorReturnCode::InvalidParam);
+
+        let mut response = partition.call(ApexSyscall::TimedWait, vec![id, SHORT], &[]);
+        assert!(woken(&mut response).is_none());
+        partition.expire_timeouts();
+        assert_eq!(woken(&mut response), Some(Ok(Vec::new())));
+    }
+
+    #[test]
+    fn queuing_port() {
+        use ApexSyscall::{ReceiveQueuingMessage, SendQueuingMessage};
+        let mut partition = Partition::new();
+        let name = partition.base.name.clone();
+        let channel = |source: &str, destination: &str| {
+            let port = |partition: &str| PortConfig {
+                partition: partition.to_string(),
+                port: format!("{source}_{destination}"),
+            };
+            Queuing::try_from(QueuingChannelConfig {
+                msg_size: ByteSize::b(8),
+                msg_num: 1,
+                source: port(source),
+                destination: port(destination),
+            })
+            .unwrap()
+        };
+        let mut incoming = channel("other", &name);
+        let mut outgoing = channel(&name, "other");
+        for channel in [&incoming, &outgoing] {
+            let constant = channel.constant(&name).unwrap();
+            partition
+                .base
+                .queuing_channel
+                .insert(channel.name(), constant);
+        }
+        let receive = format!("other_{name}");
+        let send = format!("{name}_other");
+        let mut other = QueuingSource::try_from(&incoming.constant("other").unwrap()).unwrap();
+        let mut own = QueuingSource::try_from(&outgoing.constant(&name).unwrap()).unwrap();
+
+        let low = partition.aperiodic("low", 10) as u64;
+        let high = partition.aperiodic("high", 20) as u64;
+        let timed = partition.aperiodic("timed", 30) as u64;
+        partition.start();
+
+        use ErrorReturnCode::*;
+        let params = |id, time_out| vec![id, time_out, PRIORITY];
+        assert_eq!(
+            partition.data(ReceiveQueuingMessage, params(low, INFINITE), b"unknown"),
+            Err(InvalidParam)
+        );
+        assert_eq!(
+            partition.data(
+                ReceiveQueuingMessage,
+                params(low, NO_WAIT),
+                receive.as_bytes()
+            ),
+            Err(NotAvailable)
+        );
+        assert_eq!(
+            partition.data(
+                ReceiveQueuingMessage,
+                vec![low, INFINITE, 2],
+                receive.as_bytes()
+            ),
+            Err(InvalidParam)
+        );
+
+        // Receivers are woken up by priority once a message arrived
+        let mut first = partition.call(
+            ReceiveQueuingMessage,
+            params(low, INFINITE),
+            receive.as_bytes(),
+        );
+        let mut second = partition.call(
+            ReceiveQueuingMessage,
+            params(high, INFINITE),
+            receive.as_bytes(),
+        );
+        let mut third = partition.call(
+            ReceiveQueuingMessage,
+            params(timed, SHORT),
+            receive.as_bytes(),
+        );
+        partition.expire_timeouts();
+        assert_eq!(woken(&mut third), Some(Err(TimedOut)));
+        partition.run.wake_queuing_ports().unwrap();
+        assert_eq!(woken(&mut second), None);
+
+        assert!(other.push(&[1]).unwrap());
+        assert_eq!(incoming.swap().unwrap(), 1);
+        partition.run.wake_queuing_ports().unwrap();
+        assert_eq!(woken(&mut second), Some(Ok(vec![])));
+        assert_eq!(woken(&mut first), None);
+
+        // The message is still there, so a receiver may try again right away
+        assert_eq!(
+            partition.data(
+                ReceiveQueuingMessage,
+                params(high, INFINITE),
+                receive.as_bytes()
+            ),
+            Ok(vec![])
+        );
+
+        // A sender waits while its queue is full
+        assert_eq!(
+            partition.data(SendQueuingMessage, params(high, INFINITE), send.as_bytes()),
+            Ok(vec![])
+        );
+        assert!(own.push(&[2]).unwrap());
+        let mut waiting =
+            partition.call(SendQueuingMessage, params(high, INFINITE), send.as_bytes());
+        partition.run.wake_queuing_ports().unwrap();
+        assert_eq!(woken(&mut waiting), None);
+        assert_eq!(outgoing.swap().unwrap(), 1);
+        partition.run.wake_queuing_ports().unwrap();
+        assert_eq!(woken(&mut waiting), Some(Ok(vec![])));
+
+        // Nobody received the message yet, so the last receiver may try as well
+        assert_eq!(woken(&mut first), Some(Ok(vec![])));
+    }
+
+    #[test]
+    fn create_limits() {
+        let mut partition = Partition::new();
+        let mut create = |size: i64, nb: i64| {
+            let params = vec![size as u64, nb as u64, 0];
+            partition
+                .call_done(ApexSyscall::CreateBuffer, params, b"buffer")
+                .map(|_| ())
+        };
+        let invalid = Err(ErrorReturnCode::InvalidParam);
+        assert_eq!(create(0, 1), invalid);
+        assert_eq!(create(1, 0), invalid);
+        assert_eq!(create(-1, 1), invalid);
+        assert_eq!(create(1, i32::MIN as i64), invalid);
+        assert_eq!(create(MAX_MESSAGE_SIZE as i64 + 1, 1), invalid);
+        assert_eq!(create(1, MAX_NB_MESSAGE as i64 + 1), invalid);
+        assert_eq!(
+            create(MAX_MESSAGE_SIZE as i64, MAX_NB_MESSAGE as i64),
+            Ok(())
+        );
+
+        let mut create = |size: u64| {
+            partition
+                .call_done(ApexSyscall::CreateBlackboard, vec![size], b"blackboard")
+                .map(|_| ())
+        };
+        assert_eq!(create(0), invalid);
+        assert_eq!(
+            create(MAX_MESSAGE_SIZE as u64 + 1),
+            Err(ErrorReturnCode::InvalidConfig)
+        );
+        assert_eq!(create(MAX_MESSAGE_SIZE as u64), Ok(()));
+    }
+
+    #[test]
+    fn buffer() {
+        use ApexSyscall::{GetBufferStatus, ReceiveBuffer, SendBuffer};
+        let mut partition = Partition::new();
+        let buffer = partition.create(ApexSyscall::CreateBuffer, vec![2, 1, FIFO], "buffer");
+        let low = partition.aperiodic("low", 10) as u64;
+        let high = partition.aperiodic("high", 20) as u64;
+        let sender = partition.aperiodic("sender", 30) as u64;
+        partition.start();
+
+        let invalid = buffer + 1;
+        let receive = vec![low, buffer, NO_WAIT];
+        use ErrorReturnCode::*;
+        assert_eq!(
+            partition.data(ReceiveBuffer, vec![low, invalid, NO_WAIT], &[]),
+            Err(InvalidParam)
+        );
+        assert_eq!(
+            partition.data(ReceiveBuffer, receive.clone(), &[]),
+            Err(NotAvailable)
+        );
+        let send = vec![sender, buffer, NO_WAIT];
+        assert_eq!(
+            partition.data(SendBuffer, send.clone(), &[1, 2, 3]),
+            Err(InvalidConfig)
+        );
+        assert_eq!(
+            partition.data(SendBuffer, send.clone(), &[]),
+            Err(InvalidParam)
+        );
+
+        // Receivers are woken up in the order in which they started to wait
+        let mut first = partition.call(ReceiveBuffer, vec![low, buffer, INFINITE], &[]);
+        let mut second = partition.call(ReceiveBuffer, vec![high, buffer, INFINITE], &[]);
+        assert_eq!(woken(&mut first), None);
+        assert_eq!(
+            partition.values(GetBufferStatus, vec![buffer]),
+            [0, 1, 2, 2]
+        );
+        assert_eq!(partition.data(SendBuffer, send.clone(), &[1]), Ok(vec![]));
+        assert_eq!(woken(&mut first), Some(Ok(vec![1])));
+        assert_eq!(woken(&mut second), None);
+        assert_eq!(partition.data(SendBuffer, send.clone(), &[2]), Ok(vec![]));
+        assert_eq!(woken(&mut second), Some(Ok(vec![2])));
+
+        // A sender waits while the buffer is full
+        assert_eq!(partition.data(SendBuffer, send.clone(), &[3]), Ok(vec![]));
+        assert_eq!(
+            partition.data(SendBuffer, send.clone(), &[4]),
+            Err(NotAvailable)
+        );
+        let mut waiting = partition.call(SendBuffer, vec![sender, buffer, INFINITE], &[4]);
+        assert_eq!(woken(&mut waiting), None);
+        assert_eq!(
+            partition.data(ReceiveBuffer, receive.clone(), &[]),
+            Ok(vec![3])
+        );
+        assert_eq!(woken(&mut waiting), Some(Ok(vec![])));
+        assert_eq!(
+            partition.values(GetBufferStatus, vec![buffer]),
+            [1, 1, 2, 0]
+        );
+        assert_eq!(partition.data(ReceiveBuffer, receive, &[]), Ok(vec![4]));
+
+        let mut timed_out = partition.call(ReceiveBuffer, vec![low, buffer, SHORT], &[]);
+        partition.expire_timeouts();
+        assert_eq!(woken(&mut timed_out), Some(Err(TimedOut)));
+        assert_eq!(
+            partition.values(GetBufferStatus, vec![buffer]),
+            [0, 1, 2, 0]
+        );
+    }
+
+    #[test]
+    fn buffer_priority() {
+        let mut partition = Partition::new();
+        let buffer = partition.create(ApexSyscall::CreateBuffer, vec![2, 1, PRIORITY], "buffer");
+        let low = partition.aperiodic("low", 10) as u64;
+        let high = partition.aperiodic("high", 20) as u64;
+        partition.start();
+
+        // The receiver with the highest priority is woken up first
+        let receive = ApexSyscall::ReceiveBuffer;
+        let mut first = partition.call(receive, vec![low, buffer, INFINITE], &[]);
+        let mut second = partition.call(receive, vec![high, buffer, INFINITE], &[]);
+        let send = vec![low, buffer, NO_WAIT];
+        partition.data(ApexSyscall::SendBuffer, send, &[1]).unwrap();
+        assert_eq!(woken(&mut first), None);
+        assert_eq!(woken(&mut second), Some(Ok(vec![1])));
+    }
+
+    #[test]
+    fn blackboard() {
+        use ApexSyscall::{
+            ClearBlackboard, DisplayBlackboard, GetBlackboardStatus, ReadBlackboard,
+        };
+        use ErrorReturnCode::*;
+        let mut partition = Partition::new();
+        let blackboard = partition.create(ApexSyscall::CreateBlackboard, vec![2], "blackboard");
+        let low = partition.aperiodic("low", 10) as u64;
+        let high = partition.aperiodic("high", 20) as u64;
+        partition.start();
+
+        let display = vec![blackboard];
+        assert_eq!(
+            partition.data(DisplayBlackboard, display.clone(), &[]),
+            Err(InvalidParam)
+        );
+        assert_eq!(
+            partition.data(DisplayBlackboard, display.clone(), &[1, 2, 3]),
+            Err(InvalidParam)
+        );
+        assert_eq!(
+            partition.data(DisplayBlackboard, vec![blackboard + 1], &[1]),
+            Err(InvalidParam)
+        );
+        let read = vec![low, blackboard, NO_WAIT];
+        assert_eq!(
+            partition.data(ReadBlackboard, read.clone(), &[]),
+            Err(NotAvailable)
+        );
+
+        // All waiting readers receive the displayed message
+        let mut first = partition.call(ReadBlackboard, vec![low, blackboard, INFINITE], &[]);
+        let mut second = partition.call(ReadBlackboard, vec![high, blackboard, INFINITE], &[]);
+        assert_eq!(woken(&mut first), None);
+        assert_eq!(
+            partition.values(GetBlackboardStatus, vec![blackboard]),
+            [0, 2, 2]
+        );
+        assert_eq!(
+            partition.data(DisplayBlackboard, display.clone(), &[1]),
+            Ok(vec![])
+        );
+        assert_eq!(woken(&mut first), Some(Ok(vec![1])));
+        assert_eq!(woken(&mut second), Some(Ok(vec![1])));
+
+        // The message stays displayed until it is replaced or cleared
+        assert_eq!(
+            partition.data(ReadBlackboard, read.clone(), &[]),
+            Ok(vec![1])
+        );
+        assert_eq!(
+            partition.data(DisplayBlackboard, display, &[2, 3]),
+            Ok(vec![])
+        );
+        assert_eq!(
+            partition.data(ReadBlackboard, read.clone(), &[]),
+            Ok(vec![2, 3])
+        );
+        assert_eq!(
+            partition.data(ClearBlackboard, vec![blackboard], &[]),
+            Ok(vec![])
+        );
+        assert_eq!(partition.data(ReadBlackboard, read, &[]), Err(NotAvailable));
+
+        let mut timed_out = partition.call(ReadBlackboard, vec![low, blackboard, SHORT], &[]);
+        partition.expire_timeouts();
+        assert_eq!(woken(&mut timed_out), Some(Err(TimedOut)));
+        assert_eq!(
+            partition.values(GetBlackboardStatus, vec![blackboard]),
+            [0, 2, 0]
+        );
+    }
+
+    #[test]
+    fn semaphore() {
+        use ApexSyscall::{CreateSemaphore, GetSemaphoreStatus, SignalSemaphore, WaitSemaphore};
+        use ErrorReturnCode::*;
+        let mut partition = Partition::new();
+        assert_eq!(
+            partition.data(CreateSemaphore, vec![3, 2, FIFO], b"invalid"),
+            Err(InvalidParam)
+        );
+        let semaphore = partition.create(CreateSemaphore, vec![1, 2, PRIORITY], "semaphore");
+        let low = partition.aperiodic("low", 10) as u64;
+        let high = partition.aperiodic("high", 20) as u64;
+        partition.start();
+
+        let wait = vec![low, semaphore, NO_WAIT];
+        let signal = vec![semaphore];
+        assert_eq!(partition.data(WaitSemaphore, wait.clone(), &[]), Ok(vec![]));
+        assert_eq!(
+            partition.data(WaitSemaphore, wait.clone(), &[]),
+            Err(NotAvailable)
+        );
+
+        // The waiting process with the highest priority takes the signal
+        let mut first = partition.call(WaitSemaphore, vec![low, semaphore, INFINITE], &[]);
+        let mut second = partition.call(WaitSemaphore, vec![high, semaphore, INFINITE], &[]);
+        assert_eq!(
+            partition.values(GetSemaphoreStatus, vec![semaphore]),
+            [0, 2, 2]
+        );
+        assert_eq!(
+            partition.data(SignalSemaphore, signal.clone(), &[]),
+            Ok(vec![])
+        );
+        assert_eq!(woken(&mut first), None);
+        assert_eq!(woken(&mut second), Some(Ok(vec![])));
+        assert_eq!(
+            partition.data(SignalSemaphore, signal.clone(), &[]),
+            Ok(vec![])
+        );
+        assert_eq!(woken(&mut first), Some(Ok(vec![])));
+
+        // The value never exceeds the maximum
+        assert_eq!(
+            partition.data(SignalSemaphore, signal.clone(), &[]),
+            Ok(vec![])
+        );
+        assert_eq!(
+            partition.data(SignalSemaphore, signal.clone(), &[]),
+            Ok(vec![])
+        );
+        assert_eq!(partition.data(SignalSemaphore, signal, &[]), Err(NoAction));
+        assert_eq!(
+            partition.values(GetSemaphoreStatus, vec![semaphore]),
+            [2, 2, 0]
+        );
+
+        partition.data(WaitSemaphore, wait.clone(), &[]).unwrap();
+        partition.data(WaitSemaphore, wait, &[]).unwrap();
+        let mut timed_out = partition.call(WaitSemaphore, vec![low, semaphore, SHORT], &[]);
+        partition.expire_timeouts();
+        assert_eq!(woken(&mut timed_out), Some(Err(TimedOut)));
+        assert_eq!(
+            partition.values(GetSemaphoreStatus, vec![semaphore]),
+            [0, 2, 0]
+        );
+    }
+
+    #[test]
+    fn event() {
+        use ApexSyscall::{GetEventStatus, ResetEvent, SetEvent, WaitEvent};
+        use ErrorReturnCode::*;
+        let mut partition = Partition::new();
+        let event = partition.create(ApexSyscall::CreateEvent, vec![], "event");
+        let low = partition.aperiodic("low", 10) as u64;
+        let high = partition.aperiodic("high", 20) as u64;
+        partition.start();
+        assert_eq!(
+            partition.data(ApexSyscall::CreateEvent, vec![], b"normal"),
+            Err(InvalidMode)
+        );
+
+        let wait = vec![low, event, NO_WAIT];
+        assert_eq!(
+            partition.data(SetEvent, vec![event + 1], &[]),
+            Err(InvalidParam)
+        );
+        assert_eq!(
+            partition.data(WaitEvent, wait.clone(), &[]),
+            Err(NotAvailable)
+        );
+
+        // Setting the event wakes up all waiting processes
+        let mut first = partition.call(WaitEvent, vec![low, event, INFINITE], &[]);
+        let mut second = partition.call(WaitEvent, vec![high, event, INFINITE], &[]);
+        assert_eq!(woken(&mut first), None);
+        assert_eq!(partition.values(GetEventStatus, vec![event]), [0, 2]);
+        assert_eq!(partition.data(SetEvent, vec![event], &[]), Ok(vec![]));
+        assert_eq!(woken(&mut first), Some(Ok(vec![])));
+        assert_eq!(woken(&mut second), Some(Ok(vec![])));
+        assert_eq!(partition.values(GetEventStatus, vec![event]), [1, 0]);
+        assert_eq!(partition.data(WaitEvent, wait.clone(), &[]), Ok(vec![]));
+
+        assert_eq!(partition.data(ResetEvent, vec![event], &[]), Ok(vec![]));
+        assert_eq!(partition.data(WaitEvent, wait, &[]), Err(NotAvailable));
+        let mut timed_out = partition.call(WaitEvent, vec![low, event, SHORT], &[]);
+        partition.expire_timeouts();
+        assert_eq!(woken(&mut timed_out), Some(Err(TimedOut)));
+        assert_eq!(partition.values(GetEventStatus, vec![event]), [0, 0]);
+    }
+
+    #[test]
+    fn mutex() {
+        use ApexSyscall::{AcquireMutex, GetMutexStatus, ReleaseMutex};
+        use ErrorReturnCode::*;
+        let mut partition = Partition::new();
+        let mutex = partition.create(ApexSyscall::CreateMutex, vec![30, FIFO], "mutex");
+        let owner = partition.aperiodic("owner", 10);
+        let waiting = partition.aperiodic("waiting", 20);
+        let high = partition.aperiodic("high", 40) as u64;
+        partition.start();
+        let (owner_id, waiting_id) = (owner, waiting);
+        let (owner, waiting) = (owner as u64, waiting as u64);
+
+        // A process may not acquire a mutex below its own priority
+        assert_eq!(
+            partition.data(AcquireMutex, vec![high, mutex, INFINITE], &[]),
+            Err(InvalidConfig)
+        );
+
+        // The owner runs with the priority of the mutex until it released it
+        let acquire = vec![owner, mutex, NO_WAIT];
+        assert_eq!(
+            partition.data(AcquireMutex, acquire.clone(), &[]),
+            Ok(vec![])
+        );
+        assert_eq!(
+            partition.data(AcquireMutex, acquire.clone(), &[]),
+            Ok(vec![])
+        );
+        assert_eq!(partition.process(owner_id).current_priority(), 30);
+        assert_eq!(
+            partition.data(AcquireMutex, vec![waiting, mutex, NO_WAIT], &[]),
+            Err(NotAvailable)
+        );
+        assert_eq!(
+            partition.data(ReleaseMutex, vec![waiting, mutex], &[]),
+            Err(InvalidMode)
+        );
+
+        // The mutex is handed over once it is released completely
+        let mut acquired = partition.call(AcquireMutex, vec![waiting, mutex, INFINITE], &[]);
+        assert_eq!(
+            partition.values(GetMutexStatus, vec![mutex]),
+            [1, owner, 30, 2, 1]
+        );
+        assert_eq!(
+            partition.data(ReleaseMutex, vec![owner, mutex], &[]),
+            Ok(vec![])
+        );
+        assert_eq!(woken(&mut acquired), None);
+        assert_eq!(
+            partition.data(ReleaseMutex, vec![owner, mutex], &[]),
+            Ok(vec![])
+        );
+        assert_eq!(woken(&mut acquired), Some(Ok(vec![])));
+        assert_eq!(partition.process(owner_id).current_priority(), 10);
+        assert_eq!(partition.process(waiting_id).current_priority(), 30);
+        assert_eq!(
+            partition.values(GetMutexStatus, vec![mutex]),
+            [1, waiting, 30, 1, 0]
+        );
+
+        let mut timed_out = partition.call(AcquireMutex, vec![owner, mutex, SHORT], &[]);
+        partition.expire_timeouts();
+        assert_eq!(woken(&mut timed_out), Some(Err(TimedOut)));
+
+        // The lock count of the owner is limited
+        let acquire = vec![waiting, mutex, NO_WAIT];
+        for _ in 1..MAX_LOCK_COUNT {
+            assert_eq!(
+                partition.data(AcquireMutex, acquire.clone(), &[]),
+                Ok(vec![])
+            );
+        }
+        assert_eq!(
+            partition.data(AcquireMutex, acquire, &[]),
+            Err(InvalidConfig)
+        );
+    }
+
+    #[test]
+    fn mutex_priority() {
+        use ApexSyscall::{AcquireMutex, ReleaseMutex};
+        let mut partition = Partition::new();
+        let mutex = partition.create(ApexSyscall::CreateMutex, vec![30, PRIORITY], "mutex");
+        let owner = partition.aperiodic("owner", 10) as u64;
+        let low = partition.aperiodic("low", 15) as u64;
+        let high = partition.aperiodic("high", 20) as u64;
+        partition.start();
+
+        // The waiting process with the highest priority acquires the mutex next
+        partition
+            .data(AcquireMutex, vec![owner, mutex, NO_WAIT], &[])
+            .unwrap();
+        let mut first = partition.call(AcquireMutex, vec![low, mutex, INFINITE], &[]);
+        let mut second = partition.call(AcquireMutex, vec![high, mutex, INFINITE], &[]);
+        partition
+            .data(ReleaseMutex, vec![owner, mutex], &[])
+            .unwrap();
+        assert_eq!(woken(&mut first), None);
+        assert_eq!(woken(&mut second), Some(Ok(vec![])));
+        partition
+            .data(ReleaseMutex, vec![high, mutex], &[])
+            .unwrap();
+        assert_eq!(woken(&mut first), Some(Ok(vec![])));
+    }
+
+    #[test]
+    fn error_handler() {
+        use ApexSyscall::{CreateErrorHandler, GetErrorStatus, RaiseApplicationError, StopSelf};
+        use ErrorReturnCode::*;
+        let mut partition = Partition::new();
+        let handler = partition.values(CreateErrorHandler, Vec::new());
+        assert_eq!(handler, [handler[0], MAX_PRIORITY as u64 + 1]);
+        assert_eq!(
+            partition.data(CreateErrorHandler, Vec::new(), &[]),
+            Err(NoAction)
+        );
+        let handler_id = handler[0] as ProcessId;
+        let failing = partition.aperiodic("failing", 10) as u64;
+        partition.start();
+        assert_eq!(partition.process(handler_id).state(), ProcessState::Dormant);
+
+        // Errors are queued and activate the error handler, which preempts all
+        // other processes
+        let raise = vec![failing];
+        for message in [b"first", b"other"] {
+            assert_eq!(
+                partition.data(RaiseApplicationError, raise.clone(), message),
+                Ok(vec![])
+            );
+        }
+        assert_eq!(partition.run.queued_errors(), 2);
+        partition.run.schedule().unwrap();
+        assert_eq!(partition.process(handler_id).state(), ProcessState::Running);
+        assert_eq!(
+            partition.process(handler_id).current_priority(),
+            MAX_PRIORITY + 1
+        );
+
+        // Only the error handler takes the queued errors, oldest first
+        assert_eq!(
+            partition.data(GetErrorStatus, vec![failing], &[]),
+            Err(InvalidConfig)
+        );
+        let application_error = error_code_to_u64(ErrorCode::ApplicationError);
+        for message in [b"first", b"other"] {
+            let response = partition
+                .call_done(GetErrorStatus, vec![handler[0]], &[])
+                .unwrap();
+            assert_eq!(response.values, [application_error, failing]);
+            assert_eq!(response.data, message);
+        }
+        assert_eq!(
+            partition.data(GetErrorStatus, vec![handler[0]], &[]),
+            Err(NoAction)
+        );
+
+        // Once the queue is empty, the error handler waits for the next error
+        let mut stopped = partition.call(StopSelf, vec![handler[0]], &[]);
+        assert_eq!(woken(&mut stopped), None);
+        assert_eq!(partition.process(handler_id).state(), ProcessState::Dormant);
+        assert_eq!(
+            partition.data(RaiseApplicationError, raise, b"again"),
+            Ok(vec![])
+        );
+        assert_eq!(woken(&mut stopped), Some(Ok(vec![])));
+        assert_ne!(partition.process(handler_id).state(), ProcessState::Dormant);
+    }
+
+    #[test]
+    fn error_handler_fallback() {
+        let mut partition = Partition::new();
+        let failing = partition.aperiodic("failing", 10) as u64;
+        partition.start();
+
+        // Without an error handler, the error is handled at partition level
+        let request = SyscallRequest {
+            id: ApexSyscall::RaiseApplicationError,
+            params: vec![failing],
+            data: b"failed".to_vec(),
+        };
+        let (syscall, _) = Syscall::fake(request);
+        let error = dispatch(&partition.base, &mut partition.run, syscall).unwrap_err();
+        assert!(matches!(error.err(), SystemError::ApplicationError));
+        assert_eq!(partition.run.queued_errors(), 0);
+    }
+
+    #[test]
+    fn clear_persistent_memory() {
+        use std::os::unix::fs::FileExt;
+
+        assert!(Base::create_persistent_memory(ByteSize::b(0)).is_err());
+
+        // The size is no multiple of the chunks, in which the memory is zeroed
+        let mut partition = Partition::new();
+        let size = ByteSize::kib(10).as_u64() + 1;
+        let mem = Base::create_persistent_memory(ByteSize::b(size)).unwrap();
+        mem.write_all_at(&vec![1; size as usize], 0).unwrap();
+        partition.base.persistent_memory = Some(mem);
+        partition.base.clear_persistent_memory().unwrap();
+
+        let mut content = vec![1; size as usize];
+        let mem = partition.base.persistent_memory.as_ref().unwrap();
+        mem.read_exact_at(&mut content, 0).unwrap();
+        assert!(content.iter().all(|b| *b == 0));
+        assert_eq!(mem.metadata().unwrap().len(), size);
+    }
+}
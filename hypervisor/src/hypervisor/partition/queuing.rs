@@ -0,0 +1,68 @@
+//! Processes waiting on the queuing ports of a partition
+//!
+//! The processes of a partition access the queues of their queuing ports in
+//! shared memory directly. Only a process, which finds its source queue full
+//! or its destination queue empty, asks the hypervisor to let it wait. As the
+//! queues only change once the hypervisor swaps a channel, the waiting
+//! processes are woken up afterwards, so that they try again.
+use a653rs::bindings::{PortDirection, Priority, ProcessId, QueuingDiscipline};
+
+use a653rs_linux_core::error::TypedResult;
+use a653rs_linux_core::partition::QueuingConstant;
+use a653rs_linux_core::queuing::{QueuingDestination, QueuingSource};
+
+use crate::hypervisor::intra::WaitQueue;
+
+#[derive(Debug)]
+enum Queue {
+    Source(QueuingSource),
+    Destination(QueuingDestination),
+}
+
+/// A queuing port with the processes waiting on it
+#[derive(Debug)]
+pub(crate) struct QueuingPort {
+    queue: Queue,
+    waiting: WaitQueue,
+}
+
+impl QueuingPort {
+    /// Maps the queue of the port, whose waiting processes are woken up in
+    /// the order of `discipline`
+    pub fn new(port: &QueuingConstant, discipline: QueuingDiscipline) -> TypedResult<Self> {
+        let queue = match port.dir {
+            PortDirection::Source => Queue::Source(QueuingSource::try_from(port)?),
+            PortDirection::Destination => Queue::Destination(QueuingDestination::try_from(port)?),
+        };
+        Ok(Self {
+            queue,
+            waiting: WaitQueue::new(discipline),
+        })
+    }
+
+    /// Number of processes, which may access the queue without waiting. These
+    /// are the free messages of a source queue and the stored messages of a
+    /// destination queue.
+    pub fn available(&mut self) -> usize {
+        match &mut self.queue {
+            Queue::Source(source) => source.free(),
+            Queue::Destination(destination) => destination.len(),
+        }
+    }
+
+    pub fn wait(&mut self, id: ProcessId, priority: Priority) {
+        self.waiting.push(id, priority, ())
+    }
+
+    /// Removes the waiting processes, which may now access the queue
+    pub fn ready(&mut self) -> Vec<ProcessId> {
+        let available = self.available();
+        std::iter::from_fn(|| self.waiting.pop().map(|(id, ())| id))
+            .take(available)
+            .collect()
+    }
+
+    pub fn cancel_wait(&mut self, id: ProcessId) {
+        self.waiting.remove(id)
+    }
+}
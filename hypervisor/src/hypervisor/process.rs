@@ -1,2 +1,640 @@
+//! Processes of a partition, as managed by the hypervisor
+//!
+//! Each process of a partition is a thread of the partition's main process.
+//! The thread is placed inside its own threaded cgroup below the partition's
+//! processes cgroup, so that the hypervisor can freeze and unfreeze every
+//! process on its own.
+//!
+//! Inside the windows of a partition, only the process chosen by
+//! [ProcessTable::schedule] is unfrozen.
+use std::cmp::Reverse;
+use std::time::{Duration, Instant};
+
+use a653rs::bindings::{
+    Deadline, ErrorCode, ErrorReturnCode, LockLevel, Priority, ProcessId, ProcessState,
+};
+use a653rs::prelude::SystemTime;
+
+use a653rs_linux_core::cgroup::CGroup;
+use a653rs_linux_core::error::{ResultExt, SystemError, TypedResult};
+use a653rs_linux_core::partition::PartitionConstants;
+use a653rs_linux_core::syscall::ApexSyscall;
+
+use crate::hypervisor::recorder;
+use crate::hypervisor::syscall::{Syscall, SyscallOutput};
+
+/// Lowest priority of a process
+pub(crate) const MIN_PRIORITY: Priority = 1;
+/// Highest priority of a process
+pub(crate) const MAX_PRIORITY: Priority = 239;
+/// Priority of the error handler, which preempts all other processes
+pub(crate) const ERROR_HANDLER_PRIORITY: Priority = MAX_PRIORITY + 1;
+/// Maximum preemption lock level of a partition
+pub(crate) const MAX_LOCK_LEVEL: LockLevel = 16;
+
+/// Attributes of a process, which are relevant to the hypervisor
+#[derive(Debug, Clone)]
+pub(crate) struct ProcessAttributes {
+    pub name: String,
+    pub period: SystemTime,
+    pub time_capacity: SystemTime,
+    pub base_priority: Priority,
+    pub deadline: Deadline,
+}
+
+/// An error of a process, which is queued for the error handler of its
+/// partition
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ProcessError {
+    pub code: ErrorCode,
+    /// The failed process
+    pub process: ProcessId,
+    pub message: Vec<u8>,
+}
+
 #[derive(Debug)]
-pub struct Process;
+pub(crate) struct Process {
+    id: ProcessId,
+    attr: ProcessAttributes,
+    current_priority: Priority,
+    /// Priority of a process owning a mutex, which it returns to once it
+    /// released the mutex
+    retained_priority: Option<Priority>,
+    state: ProcessState,
+    /// Point in time at which the process became ready. Orders processes of
+    /// the same priority.
+    ready_since: Instant,
+    cgroup: CGroup,
+    /// System call of a waiting process, which is answered once the process
+    /// is released
+    pending: Option<Syscall>,
+    /// Point in time at which the current wait of the process times out
+    timeout: Option<Instant>,
+    /// Delay of a process started before the partition entered the normal
+    /// mode, which only begins with the normal mode
+    start_delay: Option<Duration>,
+    /// Whether the process is suspended. A suspended process waits, until it
+    /// is resumed.
+    suspended: bool,
+    /// Current release point of a periodic process
+    release: Option<Instant>,
+    /// Point in time by which the process has to finish its current
+    /// activation. Processes with an infinite time capacity have none.
+    deadline_time: Option<Instant>,
+}
+
+impl Process {
+    pub fn id(&self) -> ProcessId {
+        self.id
+    }
+
+    pub fn name(&self) -> &str {
+        &self.attr.name
+    }
+
+    pub fn attributes(&self) -> &ProcessAttributes {
+        &self.attr
+    }
+
+    pub fn periodic(&self) -> bool {
+        self.attr.period != SystemTime::Infinite
+    }
+
+    pub fn current_priority(&self) -> Priority {
+        self.current_priority
+    }
+
+    pub fn state(&self) -> ProcessState {
+        self.state
+    }
+
+    /// Sets the current priority. The process becomes the newest one of its
+    /// priority.
+    ///
+    /// While the process owns a mutex, the priority only takes effect once
+    /// the process released the mutex.
+    pub fn set_priority(&mut self, priority: Priority) {
+        if let Some(retained) = self.retained_priority.as_mut() {
+            *retained = priority;
+            return;
+        }
+        self.current_priority = priority;
+        self.ready_since = Instant::now();
+    }
+
+    /// Raises the current priority to the priority of a mutex, which the
+    /// process acquired
+    pub fn raise_priority(&mut self, priority: Priority) {
+        self.retained_priority.get_or_insert(self.current_priority);
+        self.current_priority = priority;
+    }
+
+    /// Restores the priority the process had before it acquired a mutex
+    pub fn restore_priority(&mut self) {
+        if let Some(retained) = self.retained_priority.take() {
+            self.current_priority = retained;
+        }
+    }
+
+    pub fn set_state(&mut self, state: ProcessState) {
+        trace!(
+            "Process \"{}\": {:?} -> {state:?}",
+            self.attr.name,
+            self.state
+        );
+        // A preempted process keeps its position among the ready processes
+        if state == ProcessState::Ready
+            && !matches!(self.state, ProcessState::Ready | ProcessState::Running)
+        {
+            self.ready_since = Instant::now();
+        }
+        if (self.state == ProcessState::Running) != (state == ProcessState::Running) {
+            recorder::record(|r| {
+                r.process_running(self.id, &self.attr.name, state == ProcessState::Running)
+            });
+        }
+        self.state = state;
+    }
+
+    /// The system call the process is currently waiting in
+    pub fn waiting_in(&self) -> Option<ApexSyscall> {
+        self.pending.as_ref().map(|syscall| syscall.request().id)
+    }
+
+    pub fn suspended(&self) -> bool {
+        self.suspended
+    }
+
+    pub fn timeout(&self) -> Option<Instant> {
+        self.timeout
+    }
+
+    /// Returns the next release point of a released periodic process
+    pub fn next_release(&self) -> Option<Instant> {
+        match (self.release, &self.attr.period) {
+            (Some(release), SystemTime::Normal(period)) => Some(release + *period),
+            _ => None,
+        }
+    }
+
+    pub fn deadline_time(&self) -> Option<Instant> {
+        self.deadline_time
+    }
+
+    /// Releases the process at `release`, which becomes the current release
+    /// point of a periodic process. The deadline of the process is its time
+    /// capacity after the release.
+    pub fn release(&mut self, release: Instant) {
+        if self.periodic() {
+            self.release = Some(release);
+        }
+        self.deadline_time = self.deadline_after(release);
+    }
+
+    /// Lets a released periodic process wait for its next release point,
+    /// which is returned. As the current activation of the process is
+    /// finished, its deadline becomes its time capacity after the next
+    /// release point.
+    pub fn periodic_wait(&mut self) -> Option<Instant> {
+        let next_release = self.next_release()?;
+        self.deadline_time = self.deadline_after(next_release);
+        Some(next_release)
+    }
+
+    /// Returns the deadline of an activation released at `release`
+    fn deadline_after(&self, release: Instant) -> Option<Instant> {
+        match self.attr.time_capacity {
+            SystemTime::Normal(capacity) => Some(release + capacity),
+            SystemTime::Infinite => None,
+        }
+    }
+
+    /// Postpones the deadline of the process by `budget` from now
+    ///
+    /// The deadline of a periodic process may not pass its next release
+    /// point.
+    pub fn replenish(&mut self, budget: SystemTime) -> Result<(), ErrorReturnCode> {
+        let deadline_time = match budget {
+            SystemTime::Normal(budget) => Some(Instant::now() + budget),
+            SystemTime::Infinite => None,
+        };
+        match (deadline_time, self.next_release()) {
+            (None, Some(_)) => return Err(ErrorReturnCode::InvalidMode),
+            (Some(deadline_time), Some(next_release)) if deadline_time > next_release => {
+                return Err(ErrorReturnCode::InvalidMode)
+            }
+            _ => {}
+        }
+        self.deadline_time = deadline_time;
+        Ok(())
+    }
+
+    /// Returns whether the process missed its deadline. A missed deadline is
+    /// only reported once per release of the process.
+    pub fn miss_deadline(&mut self, now: Instant) -> bool {
+        if matches!(self.state, ProcessState::Dormant | ProcessState::Faulted) {
+            return false;
+        }
+        match self.deadline_time {
+            Some(deadline_time) if deadline_time <= now => {
+                self.deadline_time = None;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn freeze(&self) -> TypedResult<()> {
+        self.cgroup.freeze().typ(SystemError::CGroup)
+    }
+
+    pub fn unfreeze(&self) -> TypedResult<()> {
+        self.cgroup.unfreeze().typ(SystemError::CGroup)
+    }
+
+    /// Makes the process wait, deferring the response to `syscall` until the
+    /// process is woken up or `timeout` is reached
+    pub fn wait(&mut self, syscall: Syscall, timeout: Option<Instant>) -> TypedResult<()> {
+        if let Some(pending) = self.pending.replace(syscall) {
+            warn!(
+                "Process \"{}\" is already waiting in {:?}",
+                self.attr.name,
+                pending.request().id
+            );
+        }
+        self.timeout = timeout;
+        self.update_state()
+    }
+
+    /// Wakes up the waiting process by answering its pending system call
+    /// with `result`. The process is ready afterwards, unless it is
+    /// suspended.
+    pub fn wake(&mut self, result: Result<SyscallOutput, ErrorReturnCode>) -> TypedResult<()> {
+        if let Some(syscall) = self.pending.take() {
+            syscall.complete(result).typ(SystemError::Panic)?;
+        }
+        self.timeout = None;
+        self.update_state()
+    }
+
+    /// Lets a dormant process wait until `until`, before it becomes ready
+    pub fn delay(&mut self, until: Instant) -> TypedResult<()> {
+        self.set_state(ProcessState::Waiting);
+        self.timeout = Some(until);
+        self.update_state()
+    }
+
+    /// Lets a dormant process wait for `delay` after the partition entered
+    /// the normal mode
+    pub fn delay_start(&mut self, delay: Duration) -> TypedResult<()> {
+        self.set_state(ProcessState::Waiting);
+        self.start_delay = Some(delay);
+        self.update_state()
+    }
+
+    /// Wakes up the process, if its timeout was reached
+    ///
+    /// A timed out system call returns [ErrorReturnCode::TimedOut], except
+    /// for the expired delays of TIMED_WAIT, PERIODIC_WAIT and DELAYED_START.
+    pub fn expire(&mut self, now: Instant) -> TypedResult<()> {
+        let timeout = match self.timeout {
+            Some(timeout) if timeout <= now => timeout,
+            _ => return Ok(()),
+        };
+        trace!("Timeout of process \"{}\" expired", self.attr.name);
+
+        match self.waiting_in() {
+            // A process suspending itself is resumed once the time out expires
+            Some(ApexSyscall::SuspendSelf) => {
+                self.suspended = false;
+                self.wake(Err(ErrorReturnCode::TimedOut))
+            }
+            Some(ApexSyscall::TimedWait) => self.wake(Ok(SyscallOutput::default())),
+            // The process reached its next release point
+            Some(ApexSyscall::PeriodicWait) => {
+                self.release(timeout);
+                self.wake(Ok(SyscallOutput::default()))
+            }
+            Some(_) => self.wake(Err(ErrorReturnCode::TimedOut)),
+            // The delay of a delayed start is the first release point
+            None => {
+                self.timeout = None;
+                self.release(timeout);
+                self.update_state()
+            }
+        }
+    }
+
+    /// Places the ready process behind all other ready processes of the same
+    /// priority
+    pub fn yield_now(&mut self) {
+        self.ready_since = Instant::now();
+    }
+
+    pub fn suspend(&mut self) -> TypedResult<()> {
+        self.suspended = true;
+        self.update_state()
+    }
+
+    pub fn resume(&mut self) -> TypedResult<()> {
+        self.suspended = false;
+        if self.waiting_in() == Some(ApexSyscall::SuspendSelf) {
+            return self.wake(Ok(SyscallOutput::default()));
+        }
+        self.update_state()
+    }
+
+    /// Stops the process, which becomes dormant
+    ///
+    /// As a single thread can not be killed from the outside, the threads of
+    /// the process are moved to the frozen `stopped` cgroup, in which they
+    /// remain until the partition is restarted.
+    pub fn stop(&mut self, stopped: &CGroup) -> TypedResult<()> {
+        self.freeze()?;
+        for tid in self.cgroup.get_tids().typ(SystemError::CGroup)? {
+            stopped.mv_thread(tid).typ(SystemError::CGroup)?;
+        }
+
+        // The stopped thread never receives its response
+        self.pending = None;
+        self.timeout = None;
+        self.start_delay = None;
+        self.suspended = false;
+        self.release = None;
+        self.deadline_time = None;
+        self.retained_priority = None;
+        self.current_priority = self.attr.base_priority;
+        self.set_state(ProcessState::Dormant);
+        Ok(())
+    }
+
+    /// Lets the error handler become dormant without stopping its thread,
+    /// which remains frozen inside its cgroup until the handler is activated
+    /// again
+    pub fn deactivate(&mut self) -> TypedResult<()> {
+        self.freeze()?;
+        self.timeout = None;
+        self.suspended = false;
+        self.deadline_time = None;
+        self.retained_priority = None;
+        self.current_priority = self.attr.base_priority;
+        self.set_state(ProcessState::Dormant);
+        Ok(())
+    }
+
+    /// Lets the process wait while it is suspended, waits inside a system call
+    /// or is delayed. Otherwise a waiting process becomes ready.
+    fn update_state(&mut self) -> TypedResult<()> {
+        if matches!(self.state, ProcessState::Dormant | ProcessState::Faulted) {
+            return Ok(());
+        }
+
+        let waiting = self.suspended
+            || self.pending.is_some()
+            || self.timeout.is_some()
+            || self.start_delay.is_some();
+        match (waiting, self.state) {
+            (true, ProcessState::Waiting)
+            | (false, ProcessState::Ready | ProcessState::Running) => Ok(()),
+            (true, _) => {
+                self.set_state(ProcessState::Waiting);
+                self.freeze()
+            }
+            // The scheduler unfreezes the process once it is chosen to run
+            (false, _) => {
+                self.set_state(ProcessState::Ready);
+                Ok(())
+            }
+        }
+    }
+}
+
+/// All processes of a partition, indexed by their id
+#[derive(Debug, Default)]
+pub(crate) struct ProcessTable {
+    processes: Vec<Process>,
+    lock_level: LockLevel,
+}
+
+impl ProcessTable {
+    /// Creates a dormant process inside its own frozen cgroup below `parent`
+    pub fn create(
+        &mut self,
+        parent: &CGroup,
+        attr: ProcessAttributes,
+    ) -> TypedResult<Result<ProcessId, ErrorReturnCode>> {
+        if self.by_name(&attr.name).is_some() {
+            return Ok(Err(ErrorReturnCode::NoAction));
+        }
+        if self.processes.len() >= PartitionConstants::MAX_PROCESSES {
+            return Ok(Err(ErrorReturnCode::InvalidConfig));
+        }
+
+        let id = self.processes.len() as ProcessId + 1;
+        let cgroup = parent
+            .new_threaded(&id.to_string())
+            .typ(SystemError::CGroup)?;
+        cgroup.freeze().typ(SystemError::CGroup)?;
+
+        debug!("Created process \"{}\" with id {id}", attr.name);
+        recorder::record(|r| r.process(id, &attr.name));
+        self.processes.push(Process {
+            id,
+            current_priority: attr.base_priority,
+            retained_priority: None,
+            attr,
+            state: ProcessState::Dormant,
+            ready_since: Instant::now(),
+            cgroup,
+            pending: None,
+            timeout: None,
+            start_delay: None,
+            suspended: false,
+            release: None,
+            deadline_time: None,
+        });
+
+        Ok(Ok(id))
+    }
+
+    pub fn get(&self, id: ProcessId) -> Option<&Process> {
+        let index = usize::try_from(id).ok()?.checked_sub(1)?;
+        self.processes.get(index)
+    }
+
+    pub fn get_mut(&mut self, id: ProcessId) -> Option<&mut Process> {
+        let index = usize::try_from(id).ok()?.checked_sub(1)?;
+        self.processes.get_mut(index)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Process> {
+        self.processes.iter()
+    }
+
+    pub fn by_name(&self, name: &str) -> Option<&Process> {
+        self.processes.iter().find(|p| p.name() == name)
+    }
+
+    /// Returns the currently running process
+    pub fn running(&self) -> Option<&Process> {
+        self.processes
+            .iter()
+            .find(|p| p.state() == ProcessState::Running)
+    }
+
+    pub fn lock_level(&self) -> LockLevel {
+        self.lock_level
+    }
+
+    /// Increments the preemption lock level. Returns the previous one.
+    pub fn lock_preemption(&mut self) -> Result<LockLevel, ErrorReturnCode> {
+        if self.lock_level >= MAX_LOCK_LEVEL {
+            return Err(ErrorReturnCode::InvalidConfig);
+        }
+        self.lock_level += 1;
+        Ok(self.lock_level - 1)
+    }
+
+    /// Decrements the preemption lock level. Returns the previous one.
+    pub fn unlock_preemption(&mut self) -> Result<LockLevel, ErrorReturnCode> {
+        if self.lock_level == 0 {
+            return Err(ErrorReturnCode::NoAction);
+        }
+        self.lock_level -= 1;
+        Ok(self.lock_level + 1)
+    }
+
+    /// Freezes all processes
+    pub fn freeze(&self) -> TypedResult<()> {
+        self.processes.iter().try_for_each(Process::freeze)
+    }
+
+    /// Releases all periodic processes, which were started before the
+    /// partition entered the normal mode, and begins the delays of all
+    /// processes started with a delay
+    ///
+    /// Periodic processes are released at `periodic_start`, if the partition
+    /// has a periodic processing start. Otherwise they are released right
+    /// away.
+    pub fn begin_normal_mode(&mut self, periodic_start: Option<Instant>) -> TypedResult<()> {
+        let now = Instant::now();
+        for p in self.processes.iter_mut() {
+            let start = match periodic_start {
+                Some(start) if p.periodic() => start,
+                _ => now,
+            };
+            if let Some(delay) = p.start_delay.take() {
+                p.delay(start + delay)?;
+            } else if p.state() == ProcessState::Ready && start > now {
+                p.delay(start)?;
+            } else if p.state() == ProcessState::Ready {
+                p.release(now);
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the point in time at which the next wait of a process times
+    /// out or the next deadline passes
+    pub fn next_timeout(&self) -> Option<Instant> {
+        self.processes
+            .iter()
+            .flat_map(|p| [p.timeout(), p.deadline_time()])
+            .flatten()
+            .min()
+    }
+
+    /// Wakes up all processes, whose timeout was reached. Returns their ids.
+    pub fn expire(&mut self) -> TypedResult<Vec<ProcessId>> {
+        let now = Instant::now();
+        let mut expired = Vec::new();
+        for (i, p) in self
+            .processes
+            .iter_mut()
+            .enumerate()
+            .filter(|(_, p)| p.timeout().is_some_and(|t| t <= now))
+        {
+            p.expire(now)?;
+            expired.push(i as ProcessId + 1);
+        }
+        Ok(expired)
+    }
+
+    /// Returns the ids of all processes, which missed their deadline
+    pub fn missed_deadlines(&mut self) -> Vec<ProcessId> {
+        let now = Instant::now();
+        let mut missed = Vec::new();
+        for (i, p) in self.processes.iter_mut().enumerate() {
+            if p.miss_deadline(now) {
+                missed.push(i as ProcessId + 1);
+            }
+        }
+        missed
+    }
+
+    /// Resets the preemption lock, which is necessary if the process holding
+    /// the lock stops itself
+    pub fn reset_preemption(&mut self) {
+        self.lock_level = 0;
+    }
+
+    /// Lets the ready process with the highest priority run and freezes the
+    /// previously running one. Processes of the same priority run in the order
+    /// in which they became ready. While preemption is locked, the running
+    /// process keeps running, unless the error handler is ready.
+    pub fn schedule(&mut self) -> TypedResult<()> {
+        let running = self
+            .processes
+            .iter()
+            .position(|p| p.state() == ProcessState::Running);
+        let error_handler = self.processes.iter().any(|p| {
+            p.state() == ProcessState::Ready && p.current_priority == ERROR_HANDLER_PRIORITY
+        });
+        if self.lock_level > 0 && running.is_some() && !error_handler {
+            return Ok(());
+        }
+
+        let next = self
+            .processes
+            .iter()
+            .enumerate()
+            .filter(|(_, p)| matches!(p.state(), ProcessState::Ready | ProcessState::Running))
+            .max_by_key(|(_, p)| (p.current_priority, Reverse(p.ready_since)))
+            .map(|(i, _)| i);
+        if next == running {
+            return Ok(());
+        }
+
+        if let Some(running) = running {
+            let process = &mut self.processes[running];
+            process.freeze()?;
+            process.set_state(ProcessState::Ready);
+        }
+        if let Some(next) = next {
+            let process = &mut self.processes[next];
+            process.set_state(ProcessState::Running);
+            process.unfreeze()?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn preemption_lock() {
+        let mut table = ProcessTable::default();
+        assert_eq!(table.unlock_preemption(), Err(ErrorReturnCode::NoAction));
+
+        for level in 0..MAX_LOCK_LEVEL {
+            assert_eq!(table.lock_preemption(), Ok(level));
+        }
+        assert_eq!(table.lock_preemption(), Err(ErrorReturnCode::InvalidConfig));
+        assert_eq!(table.lock_level(), MAX_LOCK_LEVEL);
+
+        assert_eq!(table.unlock_preemption(), Ok(MAX_LOCK_LEVEL));
+        assert_eq!(table.lock_level(), MAX_LOCK_LEVEL - 1);
+    }
+}
@@ -0,0 +1,445 @@
+//! Opt-in recording of the runtime timing of the hypervisor
+//!
+//! Once [start]ed, the hypervisor records the windows of the partitions, the
+//! running processes, mode transitions, health monitor actions and channel
+//! swaps into a file in the Chrome trace event format. The file can be opened
+//! in Perfetto or `chrome://tracing` to inspect jitter and overruns.
+//!
+//! Every partition is shown as a process of the trace. Its first thread holds
+//! the windows and events of the partition, followed by one thread per ARINC
+//! process. The module itself is shown as an additional process containing
+//! the major frames and schedule switches.
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Instant;
+
+use a653rs::bindings::{PartitionId, ProcessId};
+use a653rs::prelude::OperatingMode;
+use once_cell::sync::OnceCell;
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use a653rs_linux_core::error::{ResultExt, SystemError, TypedResult};
+
+static RECORDER: OnceCell<Mutex<TraceRecorder<BufWriter<File>>>> = OnceCell::new();
+
+/// Trace process holding the events of the module
+const MODULE_PID: i64 = 0;
+/// Trace thread holding the windows and events of a partition
+const PARTITION_TID: i64 = 0;
+
+/// A single event of the Chrome trace event format
+#[derive(Debug, Serialize)]
+struct Event<'a> {
+    name: &'a str,
+    cat: &'a str,
+    ph: char,
+    /// Microseconds since the start of the recording
+    ts: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dur: Option<f64>,
+    pid: i64,
+    tid: i64,
+    /// Scope of instant events
+    #[serde(skip_serializing_if = "Option::is_none")]
+    s: Option<char>,
+    #[serde(skip_serializing_if = "Value::is_null")]
+    args: Value,
+}
+
+/// Writes trace events as a JSON array to `W`
+///
+/// The closing bracket of the array is optional in the Chrome trace event
+/// format, hence a trace stays readable even if the hypervisor is killed.
+pub(crate) struct TraceRecorder<W: Write> {
+    out: W,
+    t0: Instant,
+    events: usize,
+    /// Partition of the current window, to which process events belong
+    window: Option<PartitionId>,
+    /// Running process of each partition and its name. Its span is only
+    /// open during the windows of the partition.
+    running: HashMap<PartitionId, (ProcessId, String)>,
+}
+
+impl<W: Write> TraceRecorder<W> {
+    pub fn new(out: W) -> Self {
+        Self {
+            out,
+            t0: Instant::now(),
+            events: 0,
+            window: None,
+            running: HashMap::new(),
+        }
+    }
+
+    fn pid(partition: PartitionId) -> i64 {
+        partition + 1
+    }
+
+    fn ts(&self, at: Instant) -> f64 {
+        micros(at.saturating_duration_since(self.t0).as_nanos() as f64)
+    }
+
+    fn record(&mut self, event: Event) -> std::io::Result<()> {
+        let separator = if self.events == 0 { "[\n" } else { ",\n" };
+        self.out.write_all(separator.as_bytes())?;
+        serde_json::to_writer(&mut self.out, &event)?;
+        self.events += 1;
+        Ok(())
+    }
+
+    fn instant(
+        &mut self,
+        pid: i64,
+        tid: i64,
+        cat: &str,
+        name: &str,
+        args: Value,
+    ) -> std::io::Result<()> {
+        self.record(Event {
+            name,
+            cat,
+            ph: 'i',
+            ts: self.ts(Instant::now()),
+            dur: None,
+            pid,
+            tid,
+            s: Some('t'),
+            args,
+        })
+    }
+
+    fn metadata(&mut self, pid: i64, tid: i64, kind: &str, name: &str) -> std::io::Result<()> {
+        self.record(Event {
+            name: kind,
+            cat: "",
+            ph: 'M',
+            ts: 0.0,
+            dur: None,
+            pid,
+            tid,
+            s: None,
+            args: json!({ "name": name }),
+        })
+    }
+
+    /// Names the trace process of the module
+    pub fn module(&mut self) -> std::io::Result<()> {
+        self.metadata(MODULE_PID, 0, "process_name", "module")?;
+        self.metadata(MODULE_PID, 0, "thread_name", "major frames")
+    }
+
+    /// Names the trace process of a partition
+    pub fn partition(&mut self, partition: PartitionId, name: &str) -> std::io::Result<()> {
+        let pid = Self::pid(partition);
+        self.metadata(pid, PARTITION_TID, "process_name", name)?;
+        self.metadata(pid, PARTITION_TID, "thread_name", "windows")
+    }
+
+    pub fn major_frame(&mut self, schedule: &str, start: Instant) -> std::io::Result<()> {
+        let now = Instant::now();
+        self.record(Event {
+            name: schedule,
+            cat: "schedule",
+            ph: 'X',
+            ts: self.ts(start),
+            dur: Some(micros(
+                now.saturating_duration_since(start).as_nanos() as f64
+            )),
+            pid: MODULE_PID,
+            tid: 0,
+            s: None,
+            args: Value::Null,
+        })
+    }
+
+    pub fn schedule_switch(&mut self, schedule: &str) -> std::io::Result<()> {
+        let name = format!("switch to {schedule}");
+        self.instant(MODULE_PID, 0, "schedule", &name, Value::Null)
+    }
+
+    /// Marks the start of a window, to whose partition following process
+    /// events belong, and continues the span of its running process
+    pub fn begin_window(&mut self, partition: PartitionId) -> std::io::Result<()> {
+        self.window = Some(partition);
+        match self.running.get(&partition).cloned() {
+            Some((process, name)) => self.process_event(partition, process, &name, 'B'),
+            None => Ok(()),
+        }
+    }
+
+    /// Records the window of `partition` from `start` until now, together
+    /// with its deviation from the planned window
+    ///
+    /// The span of the running process ends with the window, as the
+    /// partition is frozen until its next window.
+    pub fn end_window(
+        &mut self,
+        partition: PartitionId,
+        name: &str,
+        start: Instant,
+        planned: (Instant, Instant),
+    ) -> std::io::Result<()> {
+        self.close_span(partition)?;
+        self.window = None;
+        let end = Instant::now();
+        let deviation =
+            |actual: Instant, planned: Instant| match actual.checked_duration_since(planned) {
+                Some(late) => micros(late.as_nanos() as f64),
+                None => -micros(planned.duration_since(actual).as_nanos() as f64),
+            };
+        self.record(Event {
+            name,
+            cat: "window",
+            ph: 'X',
+            ts: self.ts(start),
+            dur: Some(micros(
+                end.saturating_duration_since(start).as_nanos() as f64
+            )),
+            pid: Self::pid(partition),
+            tid: PARTITION_TID,
+            s: None,
+            args: json!({
+                "start_jitter_us": deviation(start, planned.0),
+                "overrun_us": deviation(end, planned.1),
+            }),
+        })
+    }
+
+    /// Records that a process of the partition of the current window was
+    /// created
+    pub fn process(&mut self, process: ProcessId, name: &str) -> std::io::Result<()> {
+        match self.window {
+            Some(partition) => self.metadata(Self::pid(partition), process, "thread_name", name),
+            None => Ok(()),
+        }
+    }
+
+    /// Records that a process of the partition of the current window entered
+    /// or left the running state
+    pub fn process_running(
+        &mut self,
+        process: ProcessId,
+        name: &str,
+        running: bool,
+    ) -> std::io::Result<()> {
+        let Some(partition) = self.window else {
+            return Ok(());
+        };
+        if running {
+            self.stop_running(partition)?;
+            self.running.insert(partition, (process, name.to_string()));
+            self.process_event(partition, process, name, 'B')
+        } else if self.running.get(&partition).map(|(id, _)| *id) == Some(process) {
+            self.stop_running(partition)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn process_event(
+        &mut self,
+        partition: PartitionId,
+        process: ProcessId,
+        name: &str,
+        ph: char,
+    ) -> std::io::Result<()> {
+        self.record(Event {
+            name,
+            cat: "process",
+            ph,
+            ts: self.ts(Instant::now()),
+            dur: None,
+            pid: Self::pid(partition),
+            tid: process,
+            s: None,
+            args: Value::Null,
+        })
+    }
+
+    /// Forgets the running process of `partition`, e.g. when the partition
+    /// is restarted
+    fn stop_running(&mut self, partition: PartitionId) -> std::io::Result<()> {
+        self.close_span(partition)?;
+        self.running.remove(&partition);
+        Ok(())
+    }
+
+    /// Closes the span of the running process of `partition`, which is only
+    /// open during the windows of the partition
+    fn close_span(&mut self, partition: PartitionId) -> std::io::Result<()> {
+        if self.window != Some(partition) {
+            return Ok(());
+        }
+        match self.running.get(&partition).cloned() {
+            Some((process, name)) => self.process_event(partition, process, &name, 'E'),
+            None => Ok(()),
+        }
+    }
+
+    pub fn mode(&mut self, partition: PartitionId, mode: OperatingMode) -> std::io::Result<()> {
+        if mode != OperatingMode::Normal {
+            self.stop_running(partition)?;
+        }
+        let name = format!("{mode:?}");
+        self.instant(
+            Self::pid(partition),
+            PARTITION_TID,
+            "mode",
+            &name,
+            Value::Null,
+        )
+    }
+
+    /// Records the recovery action of the health monitor for `error`. Module
+    /// level actions are recorded without a partition.
+    pub fn hm_action(
+        &mut self,
+        partition: Option<PartitionId>,
+        error: SystemError,
+        action: &dyn Debug,
+    ) -> std::io::Result<()> {
+        let pid = partition.map_or(MODULE_PID, Self::pid);
+        let name = format!("{action:?}");
+        let args = json!({ "error": format!("{error:?}") });
+        self.instant(pid, PARTITION_TID, "hm", &name, args)
+    }
+
+    pub fn channel_swap(&mut self, partition: PartitionId, channel: &str) -> std::io::Result<()> {
+        let name = format!("swap {channel}");
+        self.instant(
+            Self::pid(partition),
+            PARTITION_TID,
+            "channel",
+            &name,
+            Value::Null,
+        )
+    }
+
+    pub fn flush(&mut self) -> std::io::Result<()> {
+        self.out.flush()
+    }
+}
+
+fn micros(nanos: f64) -> f64 {
+    nanos / 1000.0
+}
+
+/// Starts recording a trace to `path`
+pub fn start(path: &Path) -> TypedResult<()> {
+    let file = File::create(path).typ(SystemError::Panic)?;
+    let mut recorder = TraceRecorder::new(BufWriter::new(file));
+    recorder.module().typ(SystemError::Panic)?;
+    RECORDER
+        .set(Mutex::new(recorder))
+        .map_err(|_| anyhow::anyhow!("trace recording was already started"))
+        .typ(SystemError::Panic)
+}
+
+/// Passes the recorder to `f`, if a trace is recorded
+pub(crate) fn record<F>(f: F)
+where
+    F: FnOnce(&mut TraceRecorder<BufWriter<File>>) -> std::io::Result<()>,
+{
+    let Some(recorder) = RECORDER.get() else {
+        return;
+    };
+    let Ok(mut recorder) = recorder.lock() else {
+        return;
+    };
+    if let Err(e) = f(&mut recorder) {
+        warn!("Failed to record trace event: {e}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn events() {
+        let mut recorder = TraceRecorder::new(Vec::new());
+        let start = Instant::now();
+        recorder.module().unwrap();
+        recorder.partition(0, "Foo").unwrap();
+
+        recorder.begin_window(0).unwrap();
+        recorder.process(1, "Main").unwrap();
+        recorder.process_running(1, "Main", true).unwrap();
+        // Only the running process may leave the running state
+        recorder.process_running(2, "Other", false).unwrap();
+        recorder.channel_swap(0, "Foo:Send").unwrap();
+        recorder
+            .end_window(0, "Foo", start, (start, start))
+            .unwrap();
+        // Process events outside of windows are ignored
+        recorder.process_running(1, "Main", false).unwrap();
+
+        recorder.mode(0, OperatingMode::ColdStart).unwrap();
+        recorder
+            .hm_action(Some(0), SystemError::Panic, &"ColdStart")
+            .unwrap();
+        recorder.major_frame("default", start).unwrap();
+
+        let trace = String::from_utf8(recorder.out).unwrap() + "]";
+        let events: Vec<Value> = serde_json::from_str(&trace).unwrap();
+        let phases = events
+            .iter()
+            .map(|e| e["ph"].as_str().unwrap())
+            .collect::<String>();
+        assert_eq!(phases, "MMMMMBiEXiiX");
+
+        let window = &events[8];
+        assert_eq!(window["name"], "Foo");
+        assert_eq!(window["pid"], 1);
+        assert!(window["args"]["overrun_us"].as_f64().unwrap() >= 0.0);
+        assert_eq!(events[5]["tid"], 1);
+        assert_eq!(events[10]["args"]["error"], "Panic");
+    }
+
+    #[test]
+    fn process_span_per_window() {
+        let mut recorder = TraceRecorder::new(Vec::new());
+        let start = Instant::now();
+
+        recorder.begin_window(0).unwrap();
+        recorder.process_running(1, "Main", true).unwrap();
+        recorder
+            .end_window(0, "Foo", start, (start, start))
+            .unwrap();
+        // The running process continues in the next window of its partition
+        recorder.begin_window(0).unwrap();
+        recorder
+            .end_window(0, "Foo", start, (start, start))
+            .unwrap();
+        // A restart outside of the windows of the partition closes no span
+        recorder.mode(0, OperatingMode::ColdStart).unwrap();
+        recorder.begin_window(0).unwrap();
+
+        let trace = String::from_utf8(recorder.out).unwrap() + "]";
+        let events: Vec<Value> = serde_json::from_str(&trace).unwrap();
+        let phases = events
+            .iter()
+            .map(|e| e["ph"].as_str().unwrap())
+            .collect::<String>();
+        assert_eq!(phases, "BEXBEXi");
+
+        // Each span of the process closes at the end of its window
+        for (span, window) in [(0, 2), (3, 5)] {
+            assert_eq!(events[span]["tid"], 1);
+            assert_eq!(events[span + 1]["tid"], 1);
+            assert_eq!(events[span + 1]["name"], "Main");
+            let end =
+                events[window]["ts"].as_f64().unwrap() + events[window]["dur"].as_f64().unwrap();
+            assert!(events[span + 1]["ts"].as_f64().unwrap() <= end);
+            assert!(
+                events[span + 1]["ts"].as_f64().unwrap() >= events[span]["ts"].as_f64().unwrap()
+            );
+        }
+    }
+}
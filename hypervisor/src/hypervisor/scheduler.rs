@@ -1,52 +1,236 @@
+use std::cell::RefCell;
 use std::collections::HashMap;
+use std::rc::Rc;
 use std::thread::sleep;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use a653rs::bindings::PartitionId;
 use a653rs::prelude::OperatingMode;
 
 use a653rs_linux_core::error::{LeveledResult, TypedResult};
+use a653rs_linux_core::queuing::Queuing;
 use a653rs_linux_core::sampling::Sampling;
 pub(crate) use schedule::{PartitionSchedule, ScheduledTimeframe};
 pub(crate) use timeout::Timeout;
 
 use crate::hypervisor::partition::Partition;
+use crate::hypervisor::recorder;
+use crate::hypervisor::shutdown;
 
 mod schedule;
 mod timeout;
 
+/// A named [PartitionSchedule] with its own major frame
+pub(crate) struct ModuleSchedule {
+    pub name: String,
+    pub major_frame: Duration,
+    pub schedule: PartitionSchedule,
+}
+
+/// Timing of a [ModuleSchedule], as far as the partitions need to know it
+#[derive(Debug)]
+struct ScheduleTiming {
+    name: String,
+    major_frame: Duration,
+    /// Offsets of the periodic processing starts of the partitions, sorted by
+    /// time
+    periodic_starts: Vec<(PartitionId, Duration)>,
+}
+
+/// Status of the module schedules, which is shared between the [Scheduler] and
+/// the partitions
+///
+/// Schedules are identified by their index in the list of module schedules.
+#[derive(Debug)]
+pub(crate) struct ModuleScheduleStatus {
+    schedules: Vec<ScheduleTiming>,
+    current: usize,
+    next: usize,
+    last_switch: Option<Instant>,
+    frame_start: Option<Instant>,
+}
+
+impl ModuleScheduleStatus {
+    fn new(schedules: &[ModuleSchedule]) -> Self {
+        let schedules = schedules
+            .iter()
+            .map(|s| ScheduleTiming {
+                name: s.name.clone(),
+                major_frame: s.major_frame,
+                periodic_starts: s
+                    .schedule
+                    .iter()
+                    .filter(|t| t.periodic_start)
+                    .map(|t| (t.partition, t.start))
+                    .collect(),
+            })
+            .collect();
+        Self {
+            schedules,
+            current: 0,
+            next: 0,
+            last_switch: None,
+            frame_start: None,
+        }
+    }
+
+    /// Returns the id of the schedule named `name`
+    pub fn id(&self, name: &str) -> Option<usize> {
+        self.schedules.iter().position(|s| s.name == name)
+    }
+
+    /// Schedule which is currently executed
+    pub fn current(&self) -> usize {
+        self.current
+    }
+
+    /// Schedule which is executed from the next major frame on
+    pub fn next(&self) -> usize {
+        self.next
+    }
+
+    /// Start of the first major frame of the current schedule, if the schedule
+    /// was ever switched
+    pub fn last_switch(&self) -> Option<Instant> {
+        self.last_switch
+    }
+
+    /// Requests a switch to the schedule `id` at the end of the current major
+    /// frame. Returns false if there is no such schedule.
+    pub fn set_next(&mut self, id: usize) -> bool {
+        if id >= self.schedules.len() {
+            return false;
+        }
+        self.next = id;
+        true
+    }
+
+    /// Returns the next periodic processing start of `partition` after `now`
+    ///
+    /// Returns `None` if none of the partition's windows in the current or
+    /// next major frame is a periodic processing start.
+    pub fn next_periodic_start(&self, partition: PartitionId, now: Instant) -> Option<Instant> {
+        let frame_start = self.frame_start?;
+        let starts = |id: usize| {
+            self.schedules[id]
+                .periodic_starts
+                .iter()
+                .filter(move |(p, _)| *p == partition)
+                .map(|(_, offset)| *offset)
+        };
+
+        starts(self.current)
+            .map(|offset| frame_start + offset)
+            .find(|start| *start >= now)
+            .or_else(|| {
+                // The following major frame may already use another schedule
+                let next_frame = frame_start + self.schedules[self.current].major_frame;
+                starts(self.next).next().map(|offset| next_frame + offset)
+            })
+    }
+
+    /// Begins a major frame at `frame_start`, switching to the requested
+    /// schedule. Returns whether the schedule changed.
+    fn begin_major_frame(&mut self, frame_start: Instant) -> bool {
+        self.frame_start = Some(frame_start);
+        if self.next == self.current {
+            return false;
+        }
+        self.current = self.next;
+        self.last_switch = Some(frame_start);
+        true
+    }
+}
+
 /// A scheduler that schedules the execution timeframes of partition according
-/// to a given [PartitionSchedule]. By calling [Scheduler::run_major_frame] a
+/// to the current [ModuleSchedule]. By calling [Scheduler::run_major_frame] a
 /// single major frame can be run.
 pub(crate) struct Scheduler {
-    schedule: PartitionSchedule,
+    schedules: Vec<ModuleSchedule>,
+    status: Rc<RefCell<ModuleScheduleStatus>>,
 }
 
 impl Scheduler {
-    pub fn new(schedule: PartitionSchedule) -> Self {
-        Self { schedule }
+    pub fn new(schedules: Vec<ModuleSchedule>) -> Self {
+        let status = ModuleScheduleStatus::new(&schedules);
+        Self {
+            schedules,
+            status: Rc::new(RefCell::new(status)),
+        }
+    }
+
+    /// Returns the status of the module schedules, through which partitions
+    /// may switch the schedule
+    pub fn status(&self) -> Rc<RefCell<ModuleScheduleStatus>> {
+        self.status.clone()
+    }
+
+    fn current(&self) -> &ModuleSchedule {
+        &self.schedules[self.status.borrow().current()]
+    }
+
+    /// Name of the current schedule
+    pub fn name(&self) -> &str {
+        &self.current().name
+    }
+
+    /// Name of the schedule used from the next major frame on
+    pub fn next_name(&self) -> &str {
+        &self.schedules[self.status.borrow().next()].name
     }
+
+    /// Duration of the major frame of the current schedule
+    pub fn major_frame(&self) -> Duration {
+        self.current().major_frame
+    }
+
     /// Takes &mut self for now because P4 limits scheduling to a single core
     pub fn run_major_frame(
         &mut self,
         current_frame_start: Instant,
         partitions: &mut HashMap<PartitionId, Partition>,
         sampling_channels_by_name: &mut HashMap<String, Sampling>,
+        queuing_channels_by_name: &mut HashMap<String, Queuing>,
     ) -> LeveledResult<()> {
-        for timeframe in self.schedule.iter() {
-            sleep(
-                timeframe
-                    .start
-                    .saturating_sub(current_frame_start.elapsed()),
-            );
+        // Schedules are only switched in between major frames
+        if self
+            .status
+            .borrow_mut()
+            .begin_major_frame(current_frame_start)
+        {
+            info!("Switching to module schedule \"{}\"", self.current().name);
+            recorder::record(|r| r.schedule_switch(&self.current().name));
+        }
+
+        for timeframe in self.current().schedule.iter() {
+            // A requested shutdown takes effect in between windows
+            if shutdown::sleep_until(current_frame_start + timeframe.start) {
+                return Ok(());
+            }
 
             let timeframe_timeout = Timeout::new(current_frame_start, timeframe.end);
             let partition = partitions
                 .get_mut(&timeframe.partition)
                 .expect("partition to exist because its name comes from `timeframe`");
-            PartitionTimeframeScheduler::new(partition, timeframe_timeout).run()?;
+            let start = Instant::now();
+            recorder::record(|r| r.begin_window(timeframe.partition));
+            let res = PartitionTimeframeScheduler::new(partition, timeframe_timeout).run();
+            recorder::record(|r| {
+                let planned = (
+                    current_frame_start + timeframe.start,
+                    current_frame_start + timeframe.end,
+                );
+                r.end_window(timeframe.partition, partition.name(), start, planned)
+            });
+            res?;
 
-            partition.run_post_timeframe(sampling_channels_by_name);
+            let errors =
+                partition.run_post_timeframe(sampling_channels_by_name, queuing_channels_by_name);
+            for (name, err) in errors {
+                if let Some(partition) = partitions.values_mut().find(|p| p.name() == name) {
+                    partition.handle_error(err)?;
+                }
+            }
         }
 
         Ok(())
@@ -65,35 +249,16 @@ impl<'a> PartitionTimeframeScheduler<'a> {
     }
 
     fn run(&mut self) -> LeveledResult<()> {
-        // Stop if the time is already over
-        if !self.timeout.has_time_left() {
-            return Ok(());
-        }
-
-        // If we are in the normal mode at the beginning of the time frame,
-        // only then we may schedule the periodic process inside a partition
-        if let OperatingMode::Normal = self.partition.get_base_run().1.mode() {
-            let res = self.partition.run_periodic_process(self.timeout);
-            if self.handle_partition_result(res)? == Some(false) {
-                // Periodic process was not run -> run aperiodic process
-                let res = self.partition.run_aperiodic_process(self.timeout);
-                if self.handle_partition_result(res)? == Some(false) {
-                    // Aperiodic process was also not run
-                    let part_name = self.partition.name();
-                    warn!("partition {part_name}: no process is scheduled")
-                }
-            }
-        }
-
-        // Only continue if we have time left
-        if self.timeout.has_time_left() {
-            let res = self.run_post_periodic();
+        // A partition may change its mode during the time frame, in which case
+        // the remaining time is spent in the new mode
+        while self.timeout.has_time_left() {
+            let res = self.run_mode().and_then(|_| self.partition.check_memory());
             self.handle_partition_result(res)?;
         }
         Ok(())
     }
 
-    fn run_post_periodic(&mut self) -> TypedResult<()> {
+    fn run_mode(&mut self) -> TypedResult<()> {
         // if we are in the idle mode, just sleep until the end of the frame
         match self.partition.get_base_run().1.mode() {
             OperatingMode::Idle => {
@@ -103,10 +268,13 @@ impl<'a> PartitionTimeframeScheduler<'a> {
             mode @ OperatingMode::ColdStart | mode @ OperatingMode::WarmStart => self
                 .partition
                 .run_start(self.timeout, mode == OperatingMode::WarmStart),
-            OperatingMode::Normal => self
-                .partition
-                .run_aperiodic_process(self.timeout)
-                .map(|_| ()),
+            OperatingMode::Normal => {
+                if !self.partition.run_processes(self.timeout)? {
+                    let part_name = self.partition.name();
+                    warn!("partition {part_name}: no process is scheduled")
+                }
+                Ok(())
+            }
         }
     }
 
@@ -119,3 +287,85 @@ impl<'a> PartitionTimeframeScheduler<'a> {
             .or_else(|err| self.partition.handle_error(err).map(|_| None))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schedule(
+        name: &str,
+        major_frame: u64,
+        windows: &[(PartitionId, u64, bool)],
+    ) -> ModuleSchedule {
+        let timeframes = windows
+            .iter()
+            .map(|(partition, start, periodic_start)| ScheduledTimeframe {
+                partition: *partition,
+                start: Duration::from_millis(*start),
+                end: Duration::from_millis(start + 10),
+                periodic_start: *periodic_start,
+            })
+            .collect();
+        ModuleSchedule {
+            name: name.to_string(),
+            major_frame: Duration::from_millis(major_frame),
+            schedule: PartitionSchedule::from_timeframes(timeframes).unwrap(),
+        }
+    }
+
+    #[test]
+    fn switch_schedule() {
+        let mut status = ModuleScheduleStatus::new(&[
+            schedule("default", 100, &[]),
+            schedule("Cruise", 100, &[]),
+        ]);
+        assert_eq!(status.id("Cruise"), Some(1));
+        assert_eq!(status.id("Landing"), None);
+
+        let start = Instant::now();
+        assert!(!status.begin_major_frame(start));
+        assert_eq!(status.last_switch(), None);
+
+        assert!(!status.set_next(2));
+        assert!(status.set_next(1));
+        // The switch only happens at the start of the next major frame
+        assert_eq!((status.current(), status.next()), (0, 1));
+        assert!(status.begin_major_frame(start));
+        assert_eq!((status.current(), status.next()), (1, 1));
+        assert_eq!(status.last_switch(), Some(start));
+        assert!(!status.begin_major_frame(start + Duration::from_secs(1)));
+        assert_eq!(status.last_switch(), Some(start));
+    }
+
+    #[test]
+    fn periodic_start() {
+        let mut status = ModuleScheduleStatus::new(&[
+            schedule(
+                "default",
+                100,
+                &[(0, 0, false), (0, 50, true), (1, 20, true)],
+            ),
+            schedule("Cruise", 200, &[(0, 30, true)]),
+        ]);
+        let ms = Duration::from_millis;
+        let start = Instant::now();
+        assert_eq!(status.next_periodic_start(0, start), None);
+
+        status.begin_major_frame(start);
+        assert_eq!(status.next_periodic_start(0, start), Some(start + ms(50)));
+        assert_eq!(status.next_periodic_start(1, start), Some(start + ms(20)));
+        assert_eq!(status.next_periodic_start(2, start), None);
+        // The next periodic processing start lies in the following major frame
+        assert_eq!(
+            status.next_periodic_start(1, start + ms(30)),
+            Some(start + ms(120))
+        );
+
+        status.set_next(1);
+        assert_eq!(
+            status.next_periodic_start(0, start + ms(60)),
+            Some(start + ms(130))
+        );
+        assert_eq!(status.next_periodic_start(1, start + ms(30)), None);
+    }
+}
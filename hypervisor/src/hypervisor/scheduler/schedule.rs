@@ -42,6 +42,9 @@ pub(crate) struct ScheduledTimeframe {
     pub partition: PartitionId,
     pub start: Duration,
     pub end: Duration,
+    /// Whether the periodic processes of the partition are released at the
+    /// start of this timeframe
+    pub periodic_start: bool,
 }
 
 impl PartialEq for ScheduledTimeframe {
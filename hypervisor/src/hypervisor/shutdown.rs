@@ -0,0 +1,131 @@
+//! Graceful shutdown on SIGINT and SIGTERM
+//!
+//! Instead of exiting from within a signal handler, both signals are blocked
+//! and received through a signalfd once [install]ed. The hypervisor checks for
+//! them in between windows, so the current window is always finished before
+//! the partitions are asked to exit and the cgroups are torn down.
+use std::os::fd::AsRawFd;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Instant;
+
+use nix::sys::signal::{SigSet, Signal};
+use nix::sys::signalfd::{SfdFlags, SignalFd};
+use once_cell::sync::OnceCell;
+use polling::{Event, Events, PollMode, Poller};
+
+use a653rs_linux_core::error::{ResultExt, SystemError, TypedResult};
+
+static SIGNALS: OnceCell<Signals> = OnceCell::new();
+
+/// Latches a received signal, as reading the signalfd consumes it
+static REQUESTED: AtomicBool = AtomicBool::new(false);
+
+struct Signals {
+    fd: Mutex<SignalFd>,
+    poller: Poller,
+}
+
+/// Receives SIGINT and SIGTERM through a signalfd from now on
+///
+/// Must be called before any threads are spawned, as only the calling thread
+/// and its future children block the signals. Partition binaries start with an
+/// empty signal mask again, as it is reset before their execution.
+pub fn install() -> TypedResult<()> {
+    let mut mask = SigSet::empty();
+    mask.add(Signal::SIGINT);
+    mask.add(Signal::SIGTERM);
+    mask.thread_block().typ(SystemError::Panic)?;
+
+    let fd = SignalFd::with_flags(&mask, SfdFlags::SFD_NONBLOCK | SfdFlags::SFD_CLOEXEC)
+        .typ(SystemError::Panic)?;
+    let poller = Poller::new().typ(SystemError::Panic)?;
+    // Level triggered, as the signal stays pending until it is read in
+    // [requested]
+    unsafe { poller.add_with_mode(fd.as_raw_fd(), Event::readable(0), PollMode::Level) }
+        .typ(SystemError::Panic)?;
+
+    SIGNALS
+        .set(Signals {
+            fd: Mutex::new(fd),
+            poller,
+        })
+        .map_err(|_| anyhow::anyhow!("signal handling was already installed"))
+        .typ(SystemError::Panic)
+}
+
+/// Whether a shutdown was requested by a signal
+pub fn requested() -> bool {
+    if REQUESTED.load(Ordering::Relaxed) {
+        return true;
+    }
+    let Some(signals) = SIGNALS.get() else {
+        return false;
+    };
+    let Ok(mut fd) = signals.fd.lock() else {
+        return false;
+    };
+    match fd.read_signal() {
+        Ok(Some(info)) => {
+            let signal = Signal::try_from(info.ssi_signo as i32)
+                .map_or_else(|_| info.ssi_signo.to_string(), |s| s.to_string());
+            info!("Received {signal}, shutting down after the current window");
+            REQUESTED.store(true, Ordering::Relaxed);
+            true
+        }
+        Ok(None) => false,
+        Err(e) => {
+            warn!("Failed to read signal: {e}");
+            false
+        }
+    }
+}
+
+/// Sleeps until `deadline`, but returns early once a shutdown was requested.
+/// Returns whether a shutdown was requested.
+pub fn sleep_until(deadline: Instant) -> bool {
+    let Some(signals) = SIGNALS.get() else {
+        std::thread::sleep(deadline.saturating_duration_since(Instant::now()));
+        return false;
+    };
+    loop {
+        if requested() {
+            return true;
+        }
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return false;
+        }
+        let mut events = Events::new();
+        if let Err(e) = signals.poller.wait(&mut events, Some(remaining)) {
+            warn!("Failed to wait for signals: {e}");
+            std::thread::sleep(remaining);
+            return requested();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn signal() {
+        install().unwrap();
+        assert!(!requested());
+        assert!(!sleep_until(Instant::now() + Duration::from_millis(10)));
+
+        // Directed at this thread, as the signals are only blocked in it
+        assert_eq!(
+            unsafe { libc::pthread_kill(libc::pthread_self(), libc::SIGTERM) },
+            0
+        );
+        let start = Instant::now();
+        assert!(sleep_until(start + Duration::from_secs(10)));
+        assert!(start.elapsed() < Duration::from_secs(1));
+        // The request is latched
+        assert!(requested());
+    }
+}
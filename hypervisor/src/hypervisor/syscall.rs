@@ -1,114 +1,145 @@
 //! Implementation of the mechanism to perform system calls
 
 use std::io::IoSliceMut;
-use std::num::NonZeroUsize;
 use std::os::fd::{AsRawFd, BorrowedFd, FromRawFd, OwnedFd, RawFd};
-use std::time::{Duration, Instant};
 
+use a653rs::bindings::ErrorReturnCode;
 use anyhow::{anyhow, bail, Result};
-use libc::EINTR;
+use nix::errno::Errno;
 use nix::sys::socket::{recvmsg, ControlMessageOwned, MsgFlags};
 use nix::{cmsg_space, unistd};
-use polling::{Event, Events, Poller};
 
 use a653rs_linux_core::mfd::{Mfd, Seals};
-use a653rs_linux_core::syscall::{SyscallRequest, SyscallResponse};
+use a653rs_linux_core::syscall::{
+    status_from_error, SyscallRequest, SyscallResponse, SYSCALL_SUCCESS,
+};
 
-/// Receives an FD triple from fd
-// TODO: Use generics here
-fn recv_fd_triple(fd: BorrowedFd) -> Result<[OwnedFd; 3]> {
-    let mut cmsg = cmsg_space!([RawFd; 3]);
-    let mut iobuf = [0u8];
-    let mut iov = [IoSliceMut::new(&mut iobuf)];
-    let res = recvmsg::<()>(fd.as_raw_fd(), &mut iov, Some(&mut cmsg), MsgFlags::empty())?;
-
-    let fds: Vec<RawFd> = match res.cmsgs().next().unwrap() {
-        ControlMessageOwned::ScmRights(fds) => fds,
-        _ => bail!("received an unknown cmsg"),
-    };
-    let fds = fds
-        .into_iter()
-        .map(|fd| unsafe { OwnedFd::from_raw_fd(fd) })
-        .collect::<Vec<_>>();
-    fds.try_into()
-        .map_err(|_| anyhow!("received fds but not a tripe"))
+/// Output parameters of a successfully executed system call
+#[derive(Debug, Default)]
+pub struct SyscallOutput {
+    pub values: Vec<u64>,
+    pub data: Vec<u8>,
 }
 
-/// Waits for readable data on fd
-fn wait_fds(fd: BorrowedFd, timeout: Option<Duration>) -> Result<bool> {
-    let poller = Poller::new()?;
-    let mut events = Events::with_capacity(NonZeroUsize::MIN);
-    unsafe { poller.add(fd.as_raw_fd(), Event::readable(0))? };
-    loop {
-        match poller.wait(&mut events, timeout) {
-            Ok(0) => return Ok(false),
-            Ok(1) => return Ok(true),
-            Err(e) => {
-                if e.raw_os_error() == Some(EINTR) {
-                    continue;
-                } else {
-                    panic!("poller failed with {:?}", e)
-                }
-            }
-            _ => panic!("unknown poller state"),
-        }
-    }
+/// A system call received from a partition, which still awaits its response
+pub struct Syscall {
+    request: SyscallRequest,
+    response_fd: Mfd,
+    event_fd: OwnedFd,
 }
 
-/// Handles an unlimited amount of system calls, until timeout is reached
-///
-/// Returns the amount of executed system calls
-pub fn handle(fd: BorrowedFd, timeout: Option<Duration>) -> Result<u32> {
-    let start = Instant::now();
-    let mut nsyscalls: u32 = 0;
-
-    // A loop in which each iteration resembles the execution of one syscall
-    loop {
-        if let Some(timeout) = timeout {
-            let remaining_time = timeout.saturating_sub(start.elapsed());
-            if remaining_time.is_zero() {
-                break;
-            }
-
-            if !wait_fds(fd, Some(remaining_time))? {
-                // Timeout was reached
-                break;
-            }
-        } else {
-            let res = wait_fds(fd, None)?;
-            assert!(res);
-        }
-
-        let [request_fd, resp_fd, event_fd] = recv_fd_triple(fd)?;
+impl Syscall {
+    /// Receives a single system call from fd without blocking
+    ///
+    /// Returns `Ok(None)` if no system call is pending.
+    pub fn try_recv(fd: BorrowedFd) -> Result<Option<Self>> {
+        let [request_fd, response_fd, event_fd] = match recv_fd_triple(fd, MsgFlags::MSG_DONTWAIT) {
+            Ok(fds) => fds,
+            Err(e) if e.downcast_ref::<Errno>() == Some(&Errno::EAGAIN) => return Ok(None),
+            Err(e) => return Err(e),
+        };
         let mut request_fd = Mfd::from_fd(request_fd)?;
-        let mut response_fd = Mfd::from_fd(resp_fd)?;
+        let response_fd = Mfd::from_fd(response_fd)?;
 
         // Fetch the request
         let request = SyscallRequest::deserialize(&request_fd.read_all()?)?;
         debug!("Received system call {:?}", request);
 
-        // Write the response (dummy response right now)
-        let response = SyscallResponse {
-            id: request.id,
-            status: 0,
-        };
-        response_fd.write(&response.serialize()?)?;
-        response_fd.finalize(Seals::Readable)?;
+        Ok(Some(Self {
+            request,
+            response_fd,
+            event_fd,
+        }))
+    }
+
+    pub fn request(&self) -> &SyscallRequest {
+        &self.request
+    }
+
+    /// Writes the response and wakes up the waiting partition process
+    pub fn respond(mut self, response: SyscallResponse) -> Result<()> {
+        self.response_fd.write(&response.serialize()?)?;
+        self.response_fd.finalize(Seals::Readable)?;
 
         // Trigger the event
         let buf = 1_u64.to_ne_bytes();
-        unistd::write(event_fd.as_raw_fd(), &buf)?;
+        unistd::write(self.event_fd.as_raw_fd(), &buf)?;
 
-        nsyscalls += 1;
+        Ok(())
     }
 
-    Ok(nsyscalls)
+    /// Responds with the outcome of the executed system call
+    pub fn complete(self, result: Result<SyscallOutput, ErrorReturnCode>) -> Result<()> {
+        let id = self.request.id;
+        let response = match result {
+            Ok(SyscallOutput { values, data }) => SyscallResponse {
+                id,
+                status: SYSCALL_SUCCESS,
+                values,
+                data,
+            },
+            Err(err) => SyscallResponse::new(id, status_from_error(err)),
+        };
+        self.respond(response)
+    }
+}
+
+#[cfg(test)]
+impl Syscall {
+    /// Creates a system call without a partition. Its response can be read
+    /// from the returned memfd once it was answered.
+    pub fn fake(request: SyscallRequest) -> (Self, Mfd) {
+        use std::os::fd::AsFd;
+
+        let response_fd = Mfd::create("response").unwrap();
+        let response = Mfd::from_fd(response_fd.as_fd().try_clone_to_owned().unwrap()).unwrap();
+        let event_fd =
+            nix::sys::eventfd::eventfd(0, nix::sys::eventfd::EfdFlags::EFD_CLOEXEC).unwrap();
+        let syscall = Self {
+            request,
+            response_fd,
+            event_fd,
+        };
+        (syscall, response)
+    }
+}
+
+impl std::fmt::Debug for Syscall {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Syscall")
+            .field("request", &self.request)
+            .field("response_fd", &self.response_fd.as_raw_fd())
+            .field("event_fd", &self.event_fd)
+            .finish()
+    }
+}
+
+/// Receives an FD triple from fd
+// TODO: Use generics here
+fn recv_fd_triple(fd: BorrowedFd, flags: MsgFlags) -> Result<[OwnedFd; 3]> {
+    let mut cmsg = cmsg_space!([RawFd; 3]);
+    let mut iobuf = [0u8];
+    let mut iov = [IoSliceMut::new(&mut iobuf)];
+    let res = recvmsg::<()>(fd.as_raw_fd(), &mut iov, Some(&mut cmsg), flags)?;
+
+    let fds: Vec<RawFd> = match res.cmsgs().next().unwrap() {
+        ControlMessageOwned::ScmRights(fds) => fds,
+        _ => bail!("received an unknown cmsg"),
+    };
+    let fds = fds
+        .into_iter()
+        .map(|fd| unsafe { OwnedFd::from_raw_fd(fd) })
+        .collect::<Vec<_>>();
+    fds.try_into()
+        .map_err(|_| anyhow!("received fds but not a tripe"))
 }
 
 #[cfg(test)]
 mod tests {
     use std::io::IoSlice;
+    use std::num::NonZeroUsize;
     use std::os::fd::{AsFd, AsRawFd};
+    use std::time::{Duration, Instant};
 
     use nix::sys::eventfd::{eventfd, EfdFlags};
     use nix::sys::socket::{
@@ -116,9 +147,72 @@ mod tests {
     };
 
     use a653rs_linux_core::syscall::ApexSyscall;
+    use libc::EINTR;
+    use polling::{Event, Events, Poller};
 
     use super::*;
 
+    /// Waits for readable data on fd
+    fn wait_fds(fd: BorrowedFd, timeout: Option<Duration>) -> Result<bool> {
+        let poller = Poller::new()?;
+        let mut events = Events::with_capacity(NonZeroUsize::MIN);
+        unsafe { poller.add(fd.as_raw_fd(), Event::readable(0))? };
+        loop {
+            match poller.wait(&mut events, timeout) {
+                Ok(0) => return Ok(false),
+                Ok(1) => return Ok(true),
+                Err(e) => {
+                    if e.raw_os_error() == Some(EINTR) {
+                        continue;
+                    } else {
+                        panic!("poller failed with {:?}", e)
+                    }
+                }
+                _ => panic!("unknown poller state"),
+            }
+        }
+    }
+
+    /// Handles an unlimited amount of system calls, until timeout is reached
+    ///
+    /// Each request is answered with the response produced by `handler`.
+    /// Returns the amount of executed system calls
+    pub fn handle<F>(fd: BorrowedFd, timeout: Option<Duration>, mut handler: F) -> Result<u32>
+    where
+        F: FnMut(&SyscallRequest) -> SyscallResponse,
+    {
+        let start = Instant::now();
+        let mut nsyscalls: u32 = 0;
+
+        // A loop in which each iteration resembles the execution of one syscall
+        loop {
+            if let Some(timeout) = timeout {
+                let remaining_time = timeout.saturating_sub(start.elapsed());
+                if remaining_time.is_zero() {
+                    break;
+                }
+
+                if !wait_fds(fd, Some(remaining_time))? {
+                    // Timeout was reached
+                    break;
+                }
+            } else {
+                let res = wait_fds(fd, None)?;
+                assert!(res);
+            }
+
+            let Some(syscall) = Syscall::try_recv(fd)? else {
+                continue;
+            };
+            let response = handler(syscall.request());
+            syscall.respond(response)?;
+
+            nsyscalls += 1;
+        }
+
+        Ok(nsyscalls)
+    }
+
     #[test]
     fn test_handle() {
         let (requester, responder) = socketpair(
@@ -140,6 +234,7 @@ mod tests {
                     &SyscallRequest {
                         id: ApexSyscall::Start,
                         params: vec![1, 2, 3],
+                        data: vec![],
                     }
                     .serialize()
                     .unwrap(),
@@ -177,7 +272,10 @@ mod tests {
         });
 
         let response_thread = std::thread::spawn(move || {
-            let n = handle(responder.as_fd(), Some(Duration::from_secs(1))).unwrap();
+            let n = handle(responder.as_fd(), Some(Duration::from_secs(1)), |request| {
+                SyscallResponse::new(request.id, 0)
+            })
+            .unwrap();
             assert_eq!(n, 1);
         });
 
@@ -0,0 +1,328 @@
+//! Rendering of the partition schedules of a [Config] as timelines
+//!
+//! The timelines are derived from the same [PartitionSchedule]s the
+//! hypervisor executes, so periods and explicit windows are already expanded.
+//! Each partition occupies one row across the major frame, followed by a row
+//! for the idle gaps in between.
+use std::fmt::Write;
+use std::time::Duration;
+
+use a653rs_linux_core::error::{ResultExt, SystemError, TypedResult};
+
+use crate::hypervisor::config::Config;
+use crate::hypervisor::scheduler::PartitionSchedule;
+
+/// Width of the column holding the row names in SVG timelines
+const SVG_LABEL_WIDTH: usize = 120;
+/// Width of the column holding the utilization in SVG timelines
+const SVG_SHARE_WIDTH: usize = 60;
+const SVG_ROW_HEIGHT: usize = 20;
+const SVG_COLORS: [&str; 6] = [
+    "#4e79a7", "#f28e2b", "#59a14f", "#e15759", "#76b7b2", "#b07aa1",
+];
+const SVG_IDLE_COLOR: &str = "#d3d3d3";
+
+/// Timeline of a single schedule
+#[derive(Debug, Clone, PartialEq)]
+pub struct Timeline {
+    pub schedule: String,
+    pub major_frame: Duration,
+    /// One row per scheduled partition in the order of the configuration
+    pub partitions: Vec<Row>,
+    /// Gaps in which no partition is scheduled
+    pub idle: Row,
+}
+
+/// Row of a [Timeline]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Row {
+    pub name: String,
+    /// Start and end of each timeframe relative to the start of the major
+    /// frame
+    pub timeframes: Vec<(Duration, Duration)>,
+    /// Share of the major frame covered by the timeframes, between 0 and 1
+    pub share: f64,
+}
+
+impl Row {
+    fn new(name: &str, timeframes: Vec<(Duration, Duration)>, major_frame: Duration) -> Self {
+        let busy: Duration = timeframes.iter().map(|(start, end)| *end - *start).sum();
+        Self {
+            name: name.to_string(),
+            timeframes,
+            share: busy.as_nanos() as f64 / major_frame.as_nanos() as f64,
+        }
+    }
+}
+
+impl Timeline {
+    /// Rows of all partitions followed by the idle row
+    fn rows(&self) -> impl Iterator<Item = &Row> {
+        self.partitions.iter().chain([&self.idle])
+    }
+
+    fn new(config: &Config, schedule: &str, major_frame: Duration, s: &PartitionSchedule) -> Self {
+        let partitions = config
+            .partitions
+            .iter()
+            .map(|p| {
+                let timeframes = s
+                    .iter()
+                    .filter(|t| t.partition == p.id)
+                    .map(|t| (t.start, t.end))
+                    .collect::<Vec<_>>();
+                (p, timeframes)
+            })
+            .filter(|(_, timeframes)| !timeframes.is_empty())
+            .map(|(p, timeframes)| Row::new(&p.name, timeframes, major_frame))
+            .collect();
+
+        let mut idle = Vec::new();
+        let mut cursor = Duration::ZERO;
+        for t in s.iter() {
+            if t.start > cursor {
+                idle.push((cursor, t.start));
+            }
+            cursor = cursor.max(t.end);
+        }
+        if cursor < major_frame {
+            idle.push((cursor, major_frame));
+        }
+
+        Self {
+            schedule: schedule.to_string(),
+            major_frame,
+            partitions,
+            idle: Row::new("idle", idle, major_frame),
+        }
+    }
+}
+
+/// Derives the timelines of all schedules of `config`
+pub fn timelines(config: &Config) -> TypedResult<Vec<Timeline>> {
+    Ok(config
+        .generate_schedules()?
+        .iter()
+        .map(|s| Timeline::new(config, &s.name, s.major_frame, &s.schedule))
+        .collect())
+}
+
+/// Renders `timelines` as a text Gantt chart with `width` columns per major
+/// frame
+///
+/// A column is marked as soon as any timeframe of the row touches it, so short
+/// timeframes stay visible. The exact timeframes are listed below each chart.
+pub fn render_text(timelines: &[Timeline], width: usize) -> TypedResult<String> {
+    if width == 0 {
+        return Err(anyhow::anyhow!("timeline width must not be zero")).typ(SystemError::Config);
+    }
+    let mut out = String::new();
+    for (i, timeline) in timelines.iter().enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        render_text_timeline(&mut out, timeline, width).typ(SystemError::Panic)?;
+    }
+    Ok(out)
+}
+
+fn render_text_timeline(out: &mut String, t: &Timeline, width: usize) -> std::fmt::Result {
+    let label = t.rows().map(|r| r.name.len()).max().unwrap_or(0);
+    let frame = t.major_frame.as_nanos();
+
+    writeln!(
+        out,
+        "schedule \"{}\" (major frame {:?})",
+        t.schedule, t.major_frame
+    )?;
+    for row in t.rows() {
+        let symbol = if row == &t.idle { '.' } else { '#' };
+        let cells: String = (0..width as u128)
+            .map(|i| {
+                let (start, end) = (i * frame / width as u128, (i + 1) * frame / width as u128);
+                let busy = row
+                    .timeframes
+                    .iter()
+                    .any(|(s, e)| s.as_nanos() < end && e.as_nanos() > start);
+                if busy {
+                    symbol
+                } else {
+                    ' '
+                }
+            })
+            .collect();
+        writeln!(
+            out,
+            "{:<label$} |{cells}| {:5.1}%",
+            row.name,
+            row.share * 100.0
+        )?;
+    }
+    let end = format!("{:?}", t.major_frame);
+    writeln!(
+        out,
+        "{:<label$} 0{end:>width$}",
+        "",
+        width = (width + 1).max(end.len() + 1)
+    )?;
+
+    for row in t.rows() {
+        let timeframes = row
+            .timeframes
+            .iter()
+            .map(|(start, end)| format!("{start:?}..{end:?}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        writeln!(out, "  {}: {timeframes}", row.name)?;
+    }
+    Ok(())
+}
+
+/// Renders `timelines` as an SVG image with the major frames scaled to `width`
+/// pixels
+pub fn render_svg(timelines: &[Timeline], width: usize) -> TypedResult<String> {
+    if width == 0 {
+        return Err(anyhow::anyhow!("timeline width must not be zero")).typ(SystemError::Config);
+    }
+    let mut body = String::new();
+    let mut y = 0;
+    for t in timelines {
+        y = render_svg_timeline(&mut body, t, width, y).typ(SystemError::Panic)?;
+    }
+    Ok(format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{y}\" \
+         font-family=\"monospace\" font-size=\"12\">\n{body}</svg>\n",
+        SVG_LABEL_WIDTH + width + SVG_SHARE_WIDTH
+    ))
+}
+
+/// Appends the elements of a single timeline starting at `y`, returning the
+/// `y` after it
+fn render_svg_timeline(
+    out: &mut String,
+    t: &Timeline,
+    width: usize,
+    mut y: usize,
+) -> Result<usize, std::fmt::Error> {
+    let frame = t.major_frame.as_nanos() as f64;
+    let x = |d: &Duration| SVG_LABEL_WIDTH as f64 + d.as_nanos() as f64 / frame * width as f64;
+    let text_y = |y: usize| y + SVG_ROW_HEIGHT * 3 / 4;
+
+    writeln!(
+        out,
+        "<text x=\"0\" y=\"{}\">schedule &quot;{}&quot; (major frame {:?})</text>",
+        text_y(y),
+        escape(&t.schedule),
+        t.major_frame
+    )?;
+    y += SVG_ROW_HEIGHT;
+
+    for (i, row) in t.rows().enumerate() {
+        let color = if row == &t.idle {
+            SVG_IDLE_COLOR
+        } else {
+            SVG_COLORS[i % SVG_COLORS.len()]
+        };
+        writeln!(
+            out,
+            "<text x=\"0\" y=\"{}\">{}</text>",
+            text_y(y),
+            escape(&row.name)
+        )?;
+        for (start, end) in &row.timeframes {
+            writeln!(
+                out,
+                "<rect x=\"{:.2}\" y=\"{}\" width=\"{:.2}\" height=\"{}\" fill=\"{color}\">\
+                 <title>{}: {start:?}..{end:?}</title></rect>",
+                x(start),
+                y + 2,
+                x(end) - x(start),
+                SVG_ROW_HEIGHT - 4,
+                escape(&row.name)
+            )?;
+        }
+        writeln!(
+            out,
+            "<text x=\"{}\" y=\"{}\">{:.1}%</text>",
+            SVG_LABEL_WIDTH + width + 4,
+            text_y(y),
+            row.share * 100.0
+        )?;
+        y += SVG_ROW_HEIGHT;
+    }
+
+    // Mark the start and end of the major frame
+    writeln!(
+        out,
+        "<text x=\"{SVG_LABEL_WIDTH}\" y=\"{}\">0</text>\n\
+         <text x=\"{}\" y=\"{}\" text-anchor=\"end\">{:?}</text>",
+        text_y(y),
+        text_y(y),
+        SVG_LABEL_WIDTH + width,
+        t.major_frame
+    )?;
+    Ok(y + SVG_ROW_HEIGHT * 2)
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = "
+major_frame: 100ms
+partitions:
+  - id: 0
+    name: Foo
+    duration: 20ms
+    offset: 0ms
+    period: 50ms
+    image: foo
+  - id: 1
+    name: Bar
+    duration: 10ms
+    offset: 80ms
+    period: 100ms
+    image: bar
+";
+
+    #[test]
+    fn text() {
+        let config: Config = serde_yaml::from_str(CONFIG).unwrap();
+        let timelines = timelines(&config).unwrap();
+        assert_eq!(timelines.len(), 1);
+        assert_eq!(timelines[0].idle.timeframes.len(), 3);
+        assert!((timelines[0].idle.share - 0.5).abs() < f64::EPSILON);
+
+        let text = render_text(&timelines, 10).unwrap();
+        let expected = "\
+schedule \"default\" (major frame 100ms)
+Foo  |##   ##   |  40.0%
+Bar  |        # |  10.0%
+idle |  ...  . .|  50.0%
+     0      100ms
+  Foo: 0ns..20ms, 50ms..70ms
+  Bar: 80ms..90ms
+  idle: 20ms..50ms, 70ms..80ms, 90ms..100ms
+";
+        assert_eq!(text, expected);
+    }
+
+    #[test]
+    fn svg() {
+        let config: Config = serde_yaml::from_str(CONFIG).unwrap();
+        let timelines = timelines(&config).unwrap();
+
+        let svg = render_svg(&timelines, 500).unwrap();
+        assert!(svg.starts_with("<svg"));
+        assert_eq!(svg.matches("<rect").count(), 6);
+        assert!(svg.contains("<title>Bar: 80ms..90ms</title>"));
+        assert!(svg.contains("width=\"100.00\""));
+    }
+}
@@ -0,0 +1,452 @@
+//! Offline validation of a [Config]
+//!
+//! Unlike [Hypervisor::new](crate::hypervisor::Hypervisor::new), which stops
+//! at the first problem, the validation collects all problems of a
+//! configuration. It neither touches cgroups nor spawns any partition.
+use std::collections::HashMap;
+use std::time::Duration;
+
+use a653rs::bindings::PartitionId;
+use itertools::Itertools;
+
+use crate::hypervisor::config::{Channel, Config, Partition, Window};
+use crate::hypervisor::scheduler::ScheduledTimeframe;
+use crate::hypervisor::HYPERVISOR_CGROUP;
+
+/// Share of a major frame, during which a partition is executed
+#[derive(Debug, Clone, PartialEq)]
+pub struct Utilization {
+    pub schedule: String,
+    pub partition: String,
+    /// Between 0 and 1
+    pub share: f64,
+}
+
+/// Outcome of the validation of a [Config]
+#[derive(Debug, Default)]
+pub struct Report {
+    /// Problems, which prevent the hypervisor from running the configuration
+    pub problems: Vec<String>,
+    /// CPU utilization of each scheduled partition per schedule
+    pub utilization: Vec<Utilization>,
+}
+
+impl Report {
+    pub fn is_valid(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+/// Validates `config`, reporting all problems at once
+pub fn validate(config: &Config) -> Report {
+    let mut validator = Validator {
+        config,
+        report: Report::default(),
+    };
+    validator.partitions();
+    validator.schedules();
+    validator.channels();
+    validator.report.problems.extend(config.hm_table_problems());
+    validator.report
+}
+
+struct Validator<'a> {
+    config: &'a Config,
+    report: Report,
+}
+
+impl<'a> Validator<'a> {
+    fn problem(&mut self, problem: String) {
+        self.report.problems.push(problem)
+    }
+
+    fn partition_name(&self, id: PartitionId) -> &str {
+        self.config
+            .partitions
+            .iter()
+            .find(|p| p.id == id)
+            .map_or("", |p| p.name.as_str())
+    }
+
+    fn partition_id(&mut self, schedule: &str, name: &str) -> Option<PartitionId> {
+        let id = self
+            .config
+            .partitions
+            .iter()
+            .find(|p| p.name == name)
+            .map(|p| p.id);
+        if id.is_none() {
+            self.problem(format!(
+                "schedule \"{schedule}\" contains unknown partition \"{name}\""
+            ));
+        }
+        id
+    }
+
+    fn partitions(&mut self) {
+        let config = self.config;
+        for (id, count) in config.partitions.iter().counts_by(|p| p.id) {
+            if count > 1 {
+                self.problem(format!("partition id {id} is used {count} times"));
+            }
+        }
+        for (name, count) in config.partitions.iter().counts_by(|p| &p.name) {
+            if count > 1 {
+                self.problem(format!("partition name \"{name}\" is used {count} times"));
+            }
+        }
+
+        for p in &config.partitions {
+            if let Err(e) = p.get_partition_bin() {
+                self.problem(format!("partition \"{}\": {}", p.name, e.source()));
+            }
+            if p.name == HYPERVISOR_CGROUP {
+                self.problem(format!(
+                    "partition name \"{}\" is reserved for the hypervisor",
+                    p.name
+                ));
+            }
+            self.limits(p);
+            for (source, target) in &p.mounts {
+                if !source.exists() {
+                    self.problem(format!(
+                        "partition \"{}\": mount source {source:?} does not exist",
+                        p.name
+                    ));
+                }
+                if !target.is_absolute() {
+                    self.problem(format!(
+                        "partition \"{}\": mount target {target:?} is not absolute",
+                        p.name
+                    ));
+                }
+            }
+        }
+    }
+
+    fn limits(&mut self, p: &Partition) {
+        if let (Some(high), Some(max)) = (p.memory_high, p.memory_max) {
+            if high > max {
+                self.problem(format!(
+                    "partition \"{}\": memory_high of {high} exceeds memory_max of {max}",
+                    p.name
+                ));
+            }
+        }
+        if p.persistent_memory.is_some_and(|size| size.as_u64() == 0) {
+            self.problem(format!(
+                "partition \"{}\": persistent_memory must not be zero",
+                p.name
+            ));
+        }
+        if p.pids_max == Some(0) {
+            self.problem(format!(
+                "partition \"{}\": pids_max must not be zero",
+                p.name
+            ));
+        }
+        if p.tmpfs_size.as_u64() == 0 {
+            self.problem(format!(
+                "partition \"{}\": tmpfs_size must not be zero",
+                p.name
+            ));
+        }
+    }
+
+    fn schedules(&mut self) {
+        let config = self.config;
+        let timing = match config.periodic_timing() {
+            Ok(timing) => timing
+                .into_iter()
+                .map(|(p, offset, period, duration)| (p.name.as_str(), offset, period, duration))
+                .collect(),
+            Err(problem) => {
+                self.problem(problem);
+                Vec::new()
+            }
+        };
+        self.schedule(
+            Config::DEFAULT_SCHEDULE,
+            config.major_frame,
+            timing,
+            &config.windows,
+        );
+
+        for (name, count) in config
+            .schedules
+            .iter()
+            .map(|s| s.name.as_str())
+            .chain([Config::DEFAULT_SCHEDULE])
+            .counts()
+        {
+            if count > 1 {
+                self.problem(format!("schedule name \"{name}\" is used {count} times"));
+            }
+        }
+        for s in &config.schedules {
+            let timing = s
+                .partitions
+                .iter()
+                .map(|p| (p.name.as_str(), p.offset, p.period, p.duration))
+                .collect();
+            self.schedule(&s.name, s.major_frame, timing, &s.windows);
+        }
+    }
+
+    fn schedule(
+        &mut self,
+        name: &str,
+        major_frame: Duration,
+        timing: Vec<(&str, Duration, Duration, Duration)>,
+        windows: &[Window],
+    ) {
+        if major_frame.is_zero() {
+            self.problem(format!("schedule \"{name}\": major frame is zero"));
+            return;
+        }
+
+        let mut timeframes = Vec::new();
+        let mut periods = Vec::new();
+        for (partition, offset, period, duration) in timing {
+            let Some(id) = self.partition_id(name, partition) else {
+                continue;
+            };
+            if period.is_zero() {
+                self.problem(format!(
+                    "schedule \"{name}\": period of partition \"{partition}\" is zero"
+                ));
+                continue;
+            }
+            periods.push(period);
+            timeframes.extend(Config::periodic_timeframes(
+                major_frame,
+                id,
+                offset,
+                period,
+                duration,
+            ));
+        }
+        if let Some(lcm) = Config::unfitting_periods(major_frame, periods.into_iter()) {
+            self.problem(format!(
+                "schedule \"{name}\": major frame {major_frame:?} is not a multiple of the \
+                 least-common-multiple of all partition periods {lcm:?}"
+            ));
+        }
+        for w in windows {
+            let Some(id) = self.partition_id(name, &w.partition) else {
+                continue;
+            };
+            timeframes.push(ScheduledTimeframe {
+                partition: id,
+                start: w.start,
+                end: w.start + w.duration,
+                periodic_start: w.periodic_processing_start,
+            });
+        }
+        timeframes.sort();
+
+        // Report every overlap, not only the first one
+        let mut latest: Option<&ScheduledTimeframe> = None;
+        for t in &timeframes {
+            if t.end > major_frame {
+                self.problem(format!(
+                    "schedule \"{name}\": window of partition \"{}\" ({:?}..{:?}) ends after the \
+                     major frame {major_frame:?}",
+                    self.partition_name(t.partition),
+                    t.start,
+                    t.end
+                ));
+            }
+            match latest {
+                Some(prev) if prev.end > t.start => {
+                    self.problem(format!(
+                        "schedule \"{name}\": windows of partition \"{}\" ({:?}..{:?}) and \
+                         partition \"{}\" ({:?}..{:?}) overlap",
+                        self.partition_name(prev.partition),
+                        prev.start,
+                        prev.end,
+                        self.partition_name(t.partition),
+                        t.start,
+                        t.end
+                    ));
+                    if t.end > prev.end {
+                        latest = Some(t);
+                    }
+                }
+                _ => latest = Some(t),
+            }
+        }
+
+        let mut busy: Vec<(PartitionId, Duration)> = Vec::new();
+        for t in &timeframes {
+            let duration = t.end.saturating_sub(t.start);
+            match busy.iter_mut().find(|(p, _)| *p == t.partition) {
+                Some((_, sum)) => *sum += duration,
+                None => busy.push((t.partition, duration)),
+            }
+        }
+        for (partition, busy) in busy {
+            let utilization = Utilization {
+                schedule: name.to_string(),
+                partition: self.partition_name(partition).to_string(),
+                share: busy.as_nanos() as f64 / major_frame.as_nanos() as f64,
+            };
+            self.report.utilization.push(utilization);
+        }
+    }
+
+    fn channels(&mut self) {
+        let config = self.config;
+        let mut ports = HashMap::<(&str, &str), usize>::new();
+        for c in &config.channel {
+            let (name, endpoints) = match c {
+                Channel::Sampling(s) => (
+                    s.name(),
+                    [&s.source].into_iter().chain(&s.destination).collect_vec(),
+                ),
+                Channel::Queuing(q) => {
+                    if q.msg_num == 0 {
+                        self.problem(format!(
+                            "queuing channel \"{}\" must hold at least one message",
+                            q.name()
+                        ));
+                    }
+                    (q.name(), vec![&q.source, &q.destination])
+                }
+            };
+            for port in endpoints {
+                if !config.partitions.iter().any(|p| p.name == port.partition) {
+                    self.problem(format!(
+                        "channel \"{name}\" names unknown partition \"{}\"",
+                        port.partition
+                    ));
+                }
+                *ports
+                    .entry((port.partition.as_str(), port.port.as_str()))
+                    .or_default() += 1;
+            }
+        }
+        for ((partition, port), count) in ports.into_iter().sorted() {
+            if count > 1 {
+                self.problem(format!("port \"{partition}:{port}\" is used {count} times"));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = "
+major_frame: 100ms
+partitions:
+  - id: 0
+    name: Foo
+    duration: 20ms
+    offset: 0ms
+    period: 50ms
+    image: /bin/sh
+  - id: 1
+    name: Bar
+    duration: 20ms
+    offset: 80ms
+    period: 100ms
+    image: /bin/sh
+channel:
+  - !Sampling
+    msg_size: 1KB
+    source:
+      partition: Foo
+      port: Send
+    destination:
+      - partition: Bar
+        port: Receive
+";
+
+    #[test]
+    fn valid() {
+        let config: Config = serde_yaml::from_str(CONFIG).unwrap();
+        let report = validate(&config);
+        assert!(report.is_valid(), "{:?}", report.problems);
+        assert_eq!(
+            report.utilization,
+            [
+                Utilization {
+                    schedule: Config::DEFAULT_SCHEDULE.into(),
+                    partition: "Foo".into(),
+                    share: 0.4,
+                },
+                Utilization {
+                    schedule: Config::DEFAULT_SCHEDULE.into(),
+                    partition: "Bar".into(),
+                    share: 0.2,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn all_problems() {
+        let config = CONFIG
+            .replace("id: 1", "id: 0")
+            .replace("offset: 80ms", "offset: 30ms")
+            .replace("period: 100ms", "period: 30ms")
+            .replace("image: /bin/sh\nchannel", "image: ./missing\nchannel")
+            .replace(
+                "partition: Bar\n        port: Receive",
+                "partition: Baz\n        port: Send",
+            )
+            + "
+  - !Queuing
+    msg_size: 1KB
+    msg_num: 2
+    source:
+      partition: Foo
+      port: Send
+    destination:
+      partition: Bar
+      port: Receive
+";
+        let config: Config = serde_yaml::from_str(&config).unwrap();
+        let problems = validate(&config).problems;
+
+        let expected = [
+            "partition id 0 is used 2 times",
+            "\"./missing\" does not exist",
+            "not a multiple of the least-common-multiple",
+            "ends after the major frame",
+            "overlap",
+            "unknown partition \"Baz\"",
+            "port \"Foo:Send\" is used 2 times",
+        ];
+        for e in expected {
+            assert!(
+                problems.iter().any(|p| p.contains(e)),
+                "missing \"{e}\" in {problems:#?}"
+            );
+        }
+    }
+
+    #[test]
+    fn limits() {
+        let limits =
+            "\n    memory_max: 64MB\n    memory_high: 128MB\n    persistent_memory: 0B\n    \
+                      pids_max: 0\n    tmpfs_size: 0B";
+        let config = CONFIG.replacen("name: Foo", &format!("name: Foo{limits}"), 1);
+        let config: Config = serde_yaml::from_str(&config).unwrap();
+        let problems = validate(&config).problems;
+        assert_eq!(problems.len(), 4, "{problems:#?}");
+        assert!(problems[0].contains("memory_high of 128.0 MB exceeds memory_max of 64.0 MB"));
+        assert!(problems[1].contains("persistent_memory"));
+        assert!(problems[2].contains("pids_max"));
+        assert!(problems[3].contains("tmpfs_size"));
+
+        let config = CONFIG.replacen("name: Foo", "name: hypervisor", 1);
+        let config: Config = serde_yaml::from_str(&config).unwrap();
+        assert!(validate(&config)
+            .problems
+            .iter()
+            .any(|p| p.contains("reserved for the hypervisor")));
+    }
+}
@@ -4,17 +4,20 @@
 extern crate log;
 
 use std::fs::File;
-use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use anyhow::anyhow;
-use clap::Parser;
-use nix::sys::signal::*;
+use clap::{Parser, Subcommand, ValueEnum};
 
 use a653rs_linux_core::cgroup;
 use a653rs_linux_core::error::{ErrorLevel, LeveledResult, ResultExt, SystemError, TypedResultExt};
 use a653rs_linux_core::health::ModuleRecoveryAction;
+use hypervisor::cleanup;
 use hypervisor::config::Config;
+use hypervisor::recorder;
+use hypervisor::shutdown;
+use hypervisor::timeline;
+use hypervisor::validate::validate;
 
 use crate::hypervisor::Hypervisor;
 
@@ -22,11 +25,14 @@ pub mod hypervisor;
 
 /// Hypervisor based on cgroups in Linux
 #[derive(Parser, Debug)]
-#[clap(author, version, about, long_about = None)]
+#[clap(author, version, about, long_about = None, subcommand_negates_reqs = true, args_conflicts_with_subcommands = true)]
 pub struct Args {
+    #[clap(subcommand)]
+    command: Option<Command>,
+
     /// Configuration file for the hypervisor
-    #[clap()]
-    config_file: PathBuf,
+    #[clap(required = true)]
+    config_file: Option<PathBuf>,
 
     /// Target cgroup to use
     #[clap(short = 'g', long)]
@@ -38,60 +44,120 @@ pub struct Args {
     /// frame is never interrupted.
     #[clap(short, long)]
     duration: Option<humantime::Duration>,
+
+    /// Record the timing of windows, processes, mode transitions, health
+    /// monitor actions and channel swaps to this file
+    ///
+    /// The trace uses the Chrome trace event format, which can be opened in
+    /// Perfetto or chrome://tracing.
+    #[clap(short, long)]
+    trace: Option<PathBuf>,
+
+    /// Listen for control requests on a Unix socket at this path
+    ///
+    /// The socket allows to inspect the partitions, schedule and channels, to
+    /// force partitions into another mode, to pause and resume the scheduling
+    /// and to shut the hypervisor down.
+    #[clap(short, long)]
+    control: Option<PathBuf>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Check a configuration file without running the hypervisor
+    ///
+    /// Neither cgroups are touched nor partitions spawned. All problems found
+    /// are reported at once.
+    Validate {
+        /// Configuration file to check
+        config_file: PathBuf,
+    },
+    /// Render the partition schedules of a configuration file as timelines
+    ///
+    /// Each schedule shows one row per partition across the major frame,
+    /// followed by the idle gaps, together with their utilization.
+    Timeline {
+        /// Configuration file to render
+        config_file: PathBuf,
+
+        /// Output format
+        #[clap(short, long, value_enum, default_value_t = TimelineFormat::Text)]
+        format: TimelineFormat,
+
+        /// Columns (text) or pixels (SVG) per major frame [default: 80 columns
+        /// or 800 pixels]
+        #[clap(short, long)]
+        width: Option<usize>,
+    },
+    /// Remove cgroups and working directories left behind by killed
+    /// hypervisors
+    ///
+    /// Only leftovers of hypervisors, whose process is dead, are removed. The
+    /// same happens whenever the hypervisor starts.
+    Cleanup {
+        /// Target cgroup the hypervisors used
+        #[clap(short = 'g', long)]
+        cgroup: Option<PathBuf>,
+    },
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum TimelineFormat {
+    Text,
+    Svg,
 }
 
 /// Hypervisor entrypoint
 pub fn run_hypervisor() -> LeveledResult<()> {
-    // Register Handler for SIGINT
-    // Maybe use https://crates.io/crates/signal-hook instead
-    let sig = SigAction::new(
-        SigHandler::Handler(sighdlr),
-        SaFlags::empty(),
-        SigSet::empty(),
-    );
-    unsafe { sigaction(SIGINT, &sig) }.lev_typ(SystemError::Panic, ErrorLevel::ModuleInit)?;
-    unsafe { sigaction(SIGTERM, &sig) }.lev_typ(SystemError::Panic, ErrorLevel::ModuleInit)?;
+    // SIGINT and SIGTERM are received by the scheduler loop, which shuts the
+    // module down in between windows
+    shutdown::install().lev(ErrorLevel::ModuleInit)?;
 
     trace!("parsing args");
     let mut args = Args::parse();
 
-    let my_pid =
-        procfs::process::Process::myself().lev_typ(SystemError::Panic, ErrorLevel::ModuleInit)?;
-    trace!("My pid is {}", my_pid.pid);
+    match &args.command {
+        Some(Command::Validate { config_file }) => return validate_config(config_file),
+        Some(Command::Timeline {
+            config_file,
+            format,
+            width,
+        }) => return render_timeline(config_file, *format, *width),
+        Some(Command::Cleanup { cgroup }) => return clean_up(cgroup.clone()),
+        None => {}
+    }
 
-    // assumes cgroupv2
-    let cgroups_mount_point = cgroup::mount_point()
-        .typ(SystemError::CGroup)
-        .lev(ErrorLevel::ModuleInit)?;
+    let cgroup = hypervisor_cgroup(args.cgroup.take())?;
 
-    let cgroup = args.cgroup.get_or_insert_with(|| {
-        let cgroups = my_pid
-            .cgroups()
-            .expect("unable to retrieve my parent cgroup");
-        let cgroups = cgroups.into_iter().find(|c| c.hierarchy == 0).unwrap();
-        let cgroup_path = cgroups.pathname.strip_prefix('/').unwrap(); // this can't fail, the cgroup reported will always start with a leading '/'
-        cgroups_mount_point.join(cgroup_path)
-    });
-    // Add Additional cgroup layer
-    let cgroup = cgroup.join("linux-hypervisor");
+    // Leftovers of killed hypervisors would otherwise accumulate forever
+    if let Err(e) = cleanup::clean(&cgroup) {
+        warn!("Failed to clean up after previous hypervisors: {e}");
+    }
 
     info!("parsing config");
-    let f = File::open(args.config_file).lev_typ(SystemError::Config, ErrorLevel::ModuleInit)?;
-    let mut config: Config =
-        serde_yaml::from_reader(&f).lev_typ(SystemError::Config, ErrorLevel::ModuleInit)?;
+    let config_file = args
+        .config_file
+        .ok_or_else(|| anyhow!("no configuration file given"))
+        .lev_typ(SystemError::Config, ErrorLevel::ModuleInit)?;
+    let mut config = parse_config(&config_file)?;
     config.cgroup = cgroup;
 
     let terminate_after = args.duration.map(|d| d.into());
 
+    if let Some(trace) = &args.trace {
+        info!("recording trace to {trace:?}");
+        recorder::start(trace).lev(ErrorLevel::ModuleInit)?;
+    }
+
+    let mut hypervisor = Hypervisor::new(config.clone(), terminate_after)?;
+    if let Some(control) = &args.control {
+        hypervisor.listen(control)?;
+    }
     loop {
         info!("Start Hypervisor");
-        match Hypervisor::new(config.clone(), terminate_after)?.run() {
-            Ok(_) => {
-                return Err(anyhow!(
-                    "Hypervisor Run is not supposed to exit with an OK variant"
-                ))
-                .lev_typ(SystemError::Panic, ErrorLevel::ModuleRun)
-            }
+        match hypervisor.run() {
+            // A shutdown was requested
+            Ok(_) => return Ok(()),
             Err(e) => {
                 let action = match e.level() {
                     // Partition Level is not expected here
@@ -105,21 +171,111 @@ pub fn run_hypervisor() -> LeveledResult<()> {
                         .try_action(e.err())
                         .unwrap_or(config.hm_run_table.panic),
                 };
+                recorder::record(|r| r.hm_action(None, e.err(), &action));
                 match action {
                     ModuleRecoveryAction::Ignore => {}
                     ModuleRecoveryAction::Shutdown => return Ok(()),
-                    ModuleRecoveryAction::Reset => {}
+                    ModuleRecoveryAction::Reset => hypervisor = hypervisor.reset()?,
                 }
             }
         }
     }
 }
 
-pub extern "C" fn sighdlr(_: i32) {
-    print!("\r");
-    std::io::stdout().flush().unwrap();
-    info!("Exiting");
-    quit::with_code(0)
+/// Returns the cgroup of the hypervisor below `cgroup`, which defaults to the
+/// cgroup of this process
+fn hypervisor_cgroup(cgroup: Option<PathBuf>) -> LeveledResult<PathBuf> {
+    let my_pid =
+        procfs::process::Process::myself().lev_typ(SystemError::Panic, ErrorLevel::ModuleInit)?;
+    trace!("My pid is {}", my_pid.pid);
+
+    // assumes cgroupv2
+    let cgroups_mount_point = cgroup::mount_point()
+        .typ(SystemError::CGroup)
+        .lev(ErrorLevel::ModuleInit)?;
+
+    let cgroup = cgroup.unwrap_or_else(|| {
+        let cgroups = my_pid
+            .cgroups()
+            .expect("unable to retrieve my parent cgroup");
+        let cgroups = cgroups.into_iter().find(|c| c.hierarchy == 0).unwrap();
+        let cgroup_path = cgroups.pathname.strip_prefix('/').unwrap(); // this can't fail, the cgroup reported will always start with a leading '/'
+        cgroups_mount_point.join(cgroup_path)
+    });
+    // Add Additional cgroup layer
+    Ok(cgroup.join("linux-hypervisor"))
+}
+
+/// Removes the leftovers of killed hypervisors
+fn clean_up(cgroup: Option<PathBuf>) -> LeveledResult<()> {
+    let cgroup = hypervisor_cgroup(cgroup)?;
+    let cleaned = cleanup::clean(&cgroup).lev(ErrorLevel::ModuleInit)?;
+    for path in cleaned.cgroups.iter().chain(&cleaned.working_dirs) {
+        println!("removed {path:?}");
+    }
+    if !cleaned.failed.is_empty() {
+        return Err(anyhow!(
+            "{} leftovers could not be removed",
+            cleaned.failed.len()
+        ))
+        .lev_typ(SystemError::CGroup, ErrorLevel::ModuleInit);
+    }
+    println!(
+        "removed {} cgroups and {} working directories",
+        cleaned.cgroups.len(),
+        cleaned.working_dirs.len()
+    );
+    Ok(())
+}
+
+fn parse_config(config_file: &Path) -> LeveledResult<Config> {
+    let f = File::open(config_file).lev_typ(SystemError::Config, ErrorLevel::ModuleInit)?;
+    serde_yaml::from_reader(&f).lev_typ(SystemError::Config, ErrorLevel::ModuleInit)
+}
+
+/// Prints all problems of a configuration file
+fn validate_config(config_file: &Path) -> LeveledResult<()> {
+    let config = parse_config(config_file)?;
+    let report = validate(&config);
+
+    for u in &report.utilization {
+        println!(
+            "schedule \"{}\": partition \"{}\" uses {:.1}% of the major frame",
+            u.schedule,
+            u.partition,
+            u.share * 100.0
+        );
+    }
+    for problem in &report.problems {
+        println!("error: {problem}");
+    }
+
+    if !report.is_valid() {
+        return Err(anyhow!(
+            "{} problems found in {config_file:?}",
+            report.problems.len()
+        ))
+        .lev_typ(SystemError::Config, ErrorLevel::ModuleInit);
+    }
+    println!("{config_file:?} is valid");
+    Ok(())
+}
+
+/// Prints the timelines of all schedules of a configuration file
+fn render_timeline(
+    config_file: &Path,
+    format: TimelineFormat,
+    width: Option<usize>,
+) -> LeveledResult<()> {
+    let config = parse_config(config_file)?;
+    let timelines = timeline::timelines(&config).lev(ErrorLevel::ModuleInit)?;
+    let rendered = match format {
+        TimelineFormat::Text => timeline::render_text(&timelines, width.unwrap_or(80)),
+        TimelineFormat::Svg => timeline::render_svg(&timelines, width.unwrap_or(800)),
+    }
+    .lev(ErrorLevel::ModuleInit)?;
+    print!("{rendered}");
+    Ok(())
 }
 
 /// Shorthand macro to return a new
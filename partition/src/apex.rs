@@ -1,25 +1,44 @@
+use std::cmp::min;
 use std::process::exit;
+use std::sync::atomic::Ordering;
 use std::thread::sleep;
 
 use a653rs::bindings::*;
 use a653rs::prelude::{Name, SystemTime};
-use a653rs_linux_core::error::SystemError;
+use a653rs_linux_core::error::TypedError;
+use a653rs_linux_core::partition::QueuingConstant;
+use a653rs_linux_core::queuing::QueuingDestination;
 use a653rs_linux_core::sampling::{SamplingDestination, SamplingSource};
-use nix::libc::EAGAIN;
+use a653rs_linux_core::syscall::{
+    error_code_from_u64, process_state_from_u64, queuing_discipline_to_u64, ApexSyscall,
+};
 
 use crate::partition::ApexLinuxPartition;
 use crate::process::Process as LinuxProcess;
+use crate::syscall::{apex_call, response_values};
 use crate::*;
 
 impl ApexPartitionP4 for ApexLinuxPartition {
     fn get_partition_status() -> ApexPartitionStatus {
         let operating_mode = PARTITION_MODE.read().unwrap();
+        // The hypervisor keeps track of the lock level. As this service cannot
+        // fail, an unlocked partition is reported if the hypervisor gave no
+        // valid answer.
+        let lock_level = apex_call(ApexSyscall::GetPartitionStatus, Vec::new(), Vec::new())
+            .and_then(|response| response_values(&response))
+            .map_or_else(
+                |e| {
+                    error!("Could not get the lock level: {e:?}");
+                    0
+                },
+                |[lock_level]| lock_level as LockLevel,
+            );
 
         ApexPartitionStatus {
             period: CONSTANTS.period.as_nanos() as i64,
             duration: CONSTANTS.duration.as_nanos() as i64,
             identifier: CONSTANTS.identifier,
-            lock_level: 0,
+            lock_level,
             operating_mode,
             start_condition: CONSTANTS.start_condition,
             num_assigned_cores: 1,
@@ -58,29 +77,161 @@ impl ApexPartitionP4 for ApexLinuxPartition {
 
 impl ApexProcessP4 for ApexLinuxPartition {
     fn create_process(attributes: &ApexProcessAttribute) -> Result<ProcessId, ErrorReturnCode> {
-        // TODO do not unwrap both
-        // Check current State (only allowed in warm and cold start)
-        let attr = attributes.clone().into();
-        Ok(LinuxProcess::create(attr).unwrap())
+        // The hypervisor checks the current mode (only allowed in warm and cold start)
+        LinuxProcess::create(attributes.clone().into())
     }
 
     fn start(process_id: ProcessId) -> Result<(), ErrorReturnCode> {
-        let proc = match process_id {
-            1 => APERIODIC_PROCESS.get(),
-            2 => PERIODIC_PROCESS.get(),
-            _ => None,
-        };
+        let proc = LinuxProcess::get(process_id).ok_or(ErrorReturnCode::InvalidParam)?;
+        apex_call(ApexSyscall::Start, vec![process_id as u64], Vec::new())?;
+
+        // TODO use a bigger result which contains both panic and non-panic errors
+        proc.start().unwrap();
+
+        Ok(())
+    }
+}
+
+impl ApexProcessP1 for ApexLinuxPartition {
+    fn set_priority(process_id: ProcessId, priority: Priority) -> Result<(), ErrorReturnCode> {
+        apex_call(
+            ApexSyscall::SetPriority,
+            vec![process_id as u64, priority as u64],
+            Vec::new(),
+        )?;
+        Ok(())
+    }
+
+    fn suspend_self(time_out: ApexSystemTime) -> Result<(), ErrorReturnCode> {
+        let proc = LinuxProcess::get_self().ok_or(ErrorReturnCode::InvalidMode)?;
+        // Returns once the process is resumed or the time out expires
+        apex_call(
+            ApexSyscall::SuspendSelf,
+            vec![proc.id() as u64, time_out as u64],
+            Vec::new(),
+        )?;
+        Ok(())
+    }
+
+    fn suspend(process_id: ProcessId) -> Result<(), ErrorReturnCode> {
+        if LinuxProcess::get_self().is_some_and(|p| p.id() == process_id) {
+            return Err(ErrorReturnCode::InvalidParam);
+        }
+        apex_call(ApexSyscall::Suspend, vec![process_id as u64], Vec::new())?;
+        Ok(())
+    }
+
+    fn resume(process_id: ProcessId) -> Result<(), ErrorReturnCode> {
+        apex_call(ApexSyscall::Resume, vec![process_id as u64], Vec::new())?;
+        Ok(())
+    }
 
-        let proc = match proc {
-            Some(proc) => proc,
-            None => return Err(ErrorReturnCode::InvalidParam),
+    fn stop_self() {
+        let Some(proc) = LinuxProcess::get_self() else {
+            warn!("Only processes may stop themselves");
+            return;
         };
+        // The hypervisor never answers, as the thread of a stopped process is frozen
+        // forever. Only the error handler keeps its thread, which returns from here
+        // once the error handler is activated again.
+        if let Err(e) = apex_call(ApexSyscall::StopSelf, vec![proc.id() as u64], Vec::new()) {
+            warn!("Could not stop the current process: {e:?}");
+        }
+    }
+
+    fn stop(process_id: ProcessId) -> Result<(), ErrorReturnCode> {
+        if LinuxProcess::get_self().is_some_and(|p| p.id() == process_id) {
+            return Err(ErrorReturnCode::InvalidParam);
+        }
+        apex_call(ApexSyscall::Stop, vec![process_id as u64], Vec::new())?;
+        Ok(())
+    }
+
+    fn delayed_start(
+        process_id: ProcessId,
+        delay_time: ApexSystemTime,
+    ) -> Result<(), ErrorReturnCode> {
+        let proc = LinuxProcess::get(process_id).ok_or(ErrorReturnCode::InvalidParam)?;
+        apex_call(
+            ApexSyscall::DelayStart,
+            vec![process_id as u64, delay_time as u64],
+            Vec::new(),
+        )?;
 
         // TODO use a bigger result which contains both panic and non-panic errors
         proc.start().unwrap();
 
         Ok(())
     }
+
+    fn lock_preemption() -> Result<LockLevel, ErrorReturnCode> {
+        let response = apex_call(ApexSyscall::LockPreemption, Vec::new(), Vec::new())?;
+        let [lock_level] = response_values(&response)?;
+        Ok(lock_level as LockLevel)
+    }
+
+    fn unlock_preemption() -> Result<LockLevel, ErrorReturnCode> {
+        let response = apex_call(ApexSyscall::UnlockPreemption, Vec::new(), Vec::new())?;
+        let [lock_level] = response_values(&response)?;
+        Ok(lock_level as LockLevel)
+    }
+
+    fn get_my_id() -> Result<ProcessId, ErrorReturnCode> {
+        LinuxProcess::get_self()
+            .map(|p| p.id())
+            .ok_or(ErrorReturnCode::InvalidMode)
+    }
+
+    fn get_process_id(process_name: ProcessName) -> Result<ProcessId, ErrorReturnCode> {
+        let name = Name::new(process_name);
+        let name = name.to_str().map_err(|_| ErrorReturnCode::InvalidConfig)?;
+        LinuxProcess::by_name(name)
+            .map(|p| p.id())
+            .ok_or(ErrorReturnCode::InvalidConfig)
+    }
+
+    fn get_process_status(process_id: ProcessId) -> Result<ApexProcessStatus, ErrorReturnCode> {
+        let proc = LinuxProcess::get(process_id).ok_or(ErrorReturnCode::InvalidParam)?;
+        let response = apex_call(
+            ApexSyscall::GetProcessStatus,
+            vec![process_id as u64],
+            Vec::new(),
+        )?;
+        let [state, current_priority, deadline_time] = response_values(&response)?;
+
+        Ok(ApexProcessStatus {
+            deadline_time: deadline_time as ApexSystemTime,
+            current_priority: current_priority as Priority,
+            process_state: process_state_from_u64(state)
+                .map_err(|_| ErrorReturnCode::InvalidConfig)?,
+            attributes: proc.attributes().clone().into(),
+        })
+    }
+
+    fn initialize_process_core_affinity(
+        process_id: ProcessId,
+        processor_core_id: ProcessorCoreId,
+    ) -> Result<(), ErrorReturnCode> {
+        LinuxProcess::get(process_id).ok_or(ErrorReturnCode::InvalidParam)?;
+        // Partitions are limited to a single core
+        if processor_core_id != 0 {
+            return Err(ErrorReturnCode::InvalidConfig);
+        }
+        if let OperatingMode::Normal = PARTITION_MODE.read().unwrap() {
+            return Err(ErrorReturnCode::InvalidMode);
+        }
+        Ok(())
+    }
+
+    fn get_my_processor_core_id() -> ProcessorCoreId {
+        0
+    }
+
+    fn get_my_index() -> Result<ProcessIndex, ErrorReturnCode> {
+        LinuxProcess::get_self()
+            .map(|p| p.id() as ProcessIndex - 1)
+            .ok_or(ErrorReturnCode::InvalidMode)
+    }
 }
 
 impl ApexSamplingPortP4 for ApexLinuxPartition {
@@ -195,15 +346,263 @@ impl ApexSamplingPortP4 for ApexLinuxPartition {
     }
 }
 
+/// A created queuing port of this partition
+struct QueuingPort {
+    /// Index of the port among the created queuing ports
+    index: usize,
+    /// Queuing discipline of the port as system call value
+    discipline: u64,
+    constant: &'static QueuingConstant,
+    queue: &'static Mutex<QueuingQueue>,
+}
+
+/// Looks up a created queuing port
+fn queuing_port(queuing_port_id: QueuingPortId) -> Result<QueuingPort, ErrorReturnCode> {
+    let index = (queuing_port_id as usize).wrapping_sub(1);
+    let (port, discipline) = QUEUING_PORTS
+        .read()
+        .map_err(|_| ErrorReturnCode::NotAvailable)?
+        .get(index)
+        .copied()
+        .ok_or(ErrorReturnCode::InvalidParam)?;
+    let constant = CONSTANTS
+        .queuing
+        .get(port)
+        .ok_or(ErrorReturnCode::InvalidParam)?;
+    let queue = QUEUING_QUEUES
+        .get(port)
+        .ok_or(ErrorReturnCode::InvalidParam)?;
+    Ok(QueuingPort {
+        index,
+        discipline,
+        constant,
+        queue,
+    })
+}
+
+/// Yields InvalidConfig for a queue, whose header this partition corrupted
+fn corrupted_queue(e: TypedError) -> ErrorReturnCode {
+    error!("yielding InvalidConfig, because the queue is corrupted: {e}");
+    ErrorReturnCode::InvalidConfig
+}
+
+/// Removes the oldest message of a destination queue
+///
+/// Yields InvalidParam without removing the message, if `message` can not hold
+/// the largest message of the port.
+fn pop_queuing_message(
+    port: &QueuingConstant,
+    destination: &mut QueuingDestination,
+    message: &mut [ApexByte],
+) -> Result<Option<(MessageSize, QueueOverflow)>, ErrorReturnCode> {
+    if message.is_empty() || message.len() < port.msg_size {
+        return Err(ErrorReturnCode::InvalidParam);
+    }
+    let popped = destination.pop(message).map_err(corrupted_queue)?;
+    Ok(popped.map(|(len, overflow)| (len as MessageSize, overflow)))
+}
+
+/// Calls `f` on the queue of `port` until it yields a value or `time_out`
+/// expires.
+///
+/// In between, the calling process waits in the hypervisor by means of
+/// `syscall`, until the hypervisor swapped the channel of the port. The
+/// process is counted as waiting on the port while it waits.
+fn wait_queuing_port<T>(
+    port: &QueuingPort,
+    syscall: ApexSyscall,
+    time_out: ApexSystemTime,
+    mut f: impl FnMut(&mut QueuingQueue) -> Result<Option<T>, ErrorReturnCode>,
+) -> Result<T, ErrorReturnCode> {
+    let mut try_once = || f(&mut port.queue.lock().unwrap_or_else(|e| e.into_inner()));
+
+    if let Some(t) = try_once()? {
+        return Ok(t);
+    }
+
+    let until = match SystemTime::new(time_out) {
+        SystemTime::Infinite => None,
+        SystemTime::Normal(d) if d.is_zero() => return Err(ErrorReturnCode::NotAvailable),
+        SystemTime::Normal(d) => Some(Instant::now() + d),
+    };
+
+    QUEUING_WAITING[port.index].fetch_add(1, Ordering::SeqCst);
+    let res = loop {
+        let time_out = match until {
+            Some(until) => match until.checked_duration_since(Instant::now()) {
+                Some(remaining) if !remaining.is_zero() => SystemTime::Normal(remaining),
+                _ => break Err(ErrorReturnCode::TimedOut),
+            },
+            None => SystemTime::Infinite,
+        };
+        // Returns once the process may try again
+        if let Err(e) = apex_call(
+            syscall,
+            vec![
+                caller_id(),
+                ApexSystemTime::from(time_out) as u64,
+                port.discipline,
+            ],
+            port.constant.name.as_bytes().to_vec(),
+        ) {
+            break Err(e);
+        }
+        match try_once() {
+            Ok(Some(t)) => break Ok(t),
+            Ok(None) => {}
+            Err(e) => break Err(e),
+        }
+    };
+    QUEUING_WAITING[port.index].fetch_sub(1, Ordering::SeqCst);
+
+    res
+}
+
+impl ApexQueuingPortP4 for ApexLinuxPartition {
+    fn create_queuing_port(
+        queuing_port_name: QueuingPortName,
+        max_message_size: MessageSize,
+        max_nb_message: MessageRange,
+        port_direction: PortDirection,
+        queuing_discipline: QueuingDiscipline,
+    ) -> Result<QueuingPortId, ErrorReturnCode> {
+        let name = Name::new(queuing_port_name);
+        let name = name.to_str().map_err(|e| {
+            trace!("yielding InvalidConfig, because queuing port is not valid UTF-8:\n{e}");
+            ErrorReturnCode::InvalidConfig
+        })?;
+        if let Some((i, q)) = CONSTANTS
+            .queuing
+            .iter()
+            .enumerate()
+            .find(|(_, q)| q.name.eq(name))
+        {
+            if q.dir != port_direction {
+                trace!("yielding InvalidConfig, because mismatching port direction:\nexpected {:?}, got {port_direction:?}", q.dir);
+                return Err(ErrorReturnCode::InvalidConfig);
+            }
+            if q.msg_size != max_message_size as usize || q.msg_num != max_nb_message as usize {
+                trace!(
+                    "yielding InvalidConfig, because mismatching queue size:\nexpected {} messages of {} bytes, got {max_nb_message} messages of {max_message_size} bytes",
+                    q.msg_num,
+                    q.msg_size
+                );
+                return Err(ErrorReturnCode::InvalidConfig);
+            }
+
+            let mut channels = QUEUING_PORTS.read().unwrap();
+            if channels.iter().any(|(port, _)| *port == i) {
+                trace!("yielding NoAction, because queuing port {name} was already created");
+                return Err(ErrorReturnCode::NoAction);
+            }
+            if channels
+                .try_push((i, queuing_discipline_to_u64(queuing_discipline)))
+                .is_some()
+            {
+                trace!(
+                    "yielding InvalidConfig, maximum number of queuing ports already reached: {}",
+                    channels.len()
+                );
+                return Err(ErrorReturnCode::InvalidConfig);
+            }
+            QUEUING_PORTS.write(&channels).unwrap();
+
+            return Ok(channels.len() as QueuingPortId);
+        }
+
+        trace!("yielding InvalidConfig, configuration does not declare queuing port {name}");
+        Err(ErrorReturnCode::InvalidConfig)
+    }
+
+    fn send_queuing_message(
+        queuing_port_id: QueuingPortId,
+        message: &[ApexByte],
+        time_out: ApexSystemTime,
+    ) -> Result<(), ErrorReturnCode> {
+        let port = queuing_port(queuing_port_id)?;
+        if message.len() > port.constant.msg_size {
+            return Err(ErrorReturnCode::InvalidConfig);
+        } else if message.is_empty() {
+            return Err(ErrorReturnCode::InvalidParam);
+        }
+
+        wait_queuing_port(
+            &port,
+            ApexSyscall::SendQueuingMessage,
+            time_out,
+            |queue| match queue {
+                QueuingQueue::Source(source) => {
+                    Ok(source.push(message).map_err(corrupted_queue)?.then_some(()))
+                }
+                QueuingQueue::Destination(_) => Err(ErrorReturnCode::InvalidMode),
+            },
+        )
+    }
+
+    unsafe fn receive_queuing_message(
+        queuing_port_id: QueuingPortId,
+        time_out: ApexSystemTime,
+        message: &mut [ApexByte],
+    ) -> Result<(MessageSize, QueueOverflow), ErrorReturnCode> {
+        let port = queuing_port(queuing_port_id)?;
+
+        wait_queuing_port(
+            &port,
+            ApexSyscall::ReceiveQueuingMessage,
+            time_out,
+            |queue| match queue {
+                QueuingQueue::Source(_) => Err(ErrorReturnCode::InvalidMode),
+                QueuingQueue::Destination(destination) => {
+                    pop_queuing_message(port.constant, destination, message)
+                }
+            },
+        )
+    }
+
+    fn get_queuing_port_status(
+        queuing_port_id: QueuingPortId,
+    ) -> Result<QueuingPortStatus, ErrorReturnCode> {
+        let port = queuing_port(queuing_port_id)?;
+
+        let nb_message = match &mut *port.queue.lock().unwrap_or_else(|e| e.into_inner()) {
+            QueuingQueue::Source(source) => source.len(),
+            QueuingQueue::Destination(destination) => destination.len(),
+        };
+
+        Ok(QueuingPortStatus {
+            nb_message: nb_message as MessageRange,
+            max_nb_message: port.constant.msg_num as MessageRange,
+            max_message_size: port.constant.msg_size as MessageSize,
+            port_direction: port.constant.dir,
+            waiting_processes: QUEUING_WAITING[port.index].load(Ordering::SeqCst),
+        })
+    }
+
+    fn clear_queuing_port(queuing_port_id: QueuingPortId) -> Result<(), ErrorReturnCode> {
+        let port = queuing_port(queuing_port_id)?;
+        match &mut *port.queue.lock().unwrap_or_else(|e| e.into_inner()) {
+            QueuingQueue::Source(_) => Err(ErrorReturnCode::InvalidMode),
+            QueuingQueue::Destination(destination) => {
+                destination.clear();
+                Ok(())
+            }
+        }
+    }
+}
+
 impl ApexTimeP4 for ApexLinuxPartition {
     fn periodic_wait() -> Result<(), ErrorReturnCode> {
-        // TODO do not unwrap() (Maybe raise an error?);
-        let proc = LinuxProcess::get_self().unwrap();
+        let proc = LinuxProcess::get_self().ok_or(ErrorReturnCode::InvalidMode)?;
         if !proc.periodic() {
             return Err(ErrorReturnCode::InvalidMode);
         }
 
-        proc.cg().unwrap().freeze().unwrap();
+        // Returns once the hypervisor releases the process again
+        apex_call(
+            ApexSyscall::PeriodicWait,
+            vec![proc.id() as u64],
+            Vec::new(),
+        )?;
         Ok(())
     }
 
@@ -215,24 +614,449 @@ impl ApexTimeP4 for ApexLinuxPartition {
     }
 }
 
+impl ApexTimeP1 for ApexLinuxPartition {
+    fn timed_wait(delay_time: ApexSystemTime) -> Result<(), ErrorReturnCode> {
+        let proc = LinuxProcess::get_self().ok_or(ErrorReturnCode::InvalidMode)?;
+
+        // Returns once the delay expired
+        apex_call(
+            ApexSyscall::TimedWait,
+            vec![proc.id() as u64, delay_time as u64],
+            Vec::new(),
+        )?;
+        Ok(())
+    }
+
+    fn replenish(budget_time: ApexSystemTime) -> Result<(), ErrorReturnCode> {
+        // The main thread has no deadline
+        let proc = LinuxProcess::get_self().ok_or(ErrorReturnCode::NoAction)?;
+
+        apex_call(
+            ApexSyscall::Replenish,
+            vec![proc.id() as u64, budget_time as u64],
+            Vec::new(),
+        )?;
+        Ok(())
+    }
+}
+
+/// Id of the calling process, or zero for the main thread of the partition
+fn caller_id() -> u64 {
+    LinuxProcess::get_self().map_or(0, |p| p.id() as u64)
+}
+
+/// Copies a received message into `message`
+///
+/// Yields InvalidParam if `message` is too small to hold the message, which is
+/// lost in that case.
+fn copy_message(message: &mut [ApexByte], data: &[u8]) -> Result<MessageSize, ErrorReturnCode> {
+    message
+        .get_mut(..data.len())
+        .ok_or(ErrorReturnCode::InvalidParam)?
+        .copy_from_slice(data);
+    MessageSize::try_from(data.len()).map_err(|_| ErrorReturnCode::InvalidParam)
+}
+
+impl ApexBufferP1 for ApexLinuxPartition {
+    fn create_buffer(
+        buffer_name: BufferName,
+        max_message_size: MessageSize,
+        max_nb_message: MessageRange,
+        queuing_discipline: QueuingDiscipline,
+    ) -> Result<BufferId, ErrorReturnCode> {
+        let name = Name::new(buffer_name);
+        let name = name.to_str().map_err(|_| ErrorReturnCode::InvalidParam)?;
+        let response = apex_call(
+            ApexSyscall::CreateBuffer,
+            vec![
+                max_message_size as u64,
+                max_nb_message as u64,
+                queuing_discipline_to_u64(queuing_discipline),
+            ],
+            name.as_bytes().to_vec(),
+        )?;
+        let [id] = response_values(&response)?;
+        Ok(id as BufferId)
+    }
+
+    fn send_buffer(
+        buffer_id: BufferId,
+        message: &[ApexByte],
+        time_out: ApexSystemTime,
+    ) -> Result<(), ErrorReturnCode> {
+        // Returns once the message was placed in the buffer
+        apex_call(
+            ApexSyscall::SendBuffer,
+            vec![caller_id(), buffer_id as u64, time_out as u64],
+            message.to_vec(),
+        )?;
+        Ok(())
+    }
+
+    unsafe fn receive_buffer(
+        buffer_id: BufferId,
+        time_out: ApexSystemTime,
+        message: &mut [ApexByte],
+    ) -> Result<MessageSize, ErrorReturnCode> {
+        // Returns once there is a message
+        let response = apex_call(
+            ApexSyscall::ReceiveBuffer,
+            vec![caller_id(), buffer_id as u64, time_out as u64],
+            Vec::new(),
+        )?;
+        copy_message(message, &response.data)
+    }
+
+    fn get_buffer_id(buffer_name: BufferName) -> Result<BufferId, ErrorReturnCode> {
+        let name = Name::new(buffer_name);
+        let name = name.to_str().map_err(|_| ErrorReturnCode::InvalidConfig)?;
+        let response = apex_call(
+            ApexSyscall::GetBufferId,
+            Vec::new(),
+            name.as_bytes().to_vec(),
+        )?;
+        let [id] = response_values(&response)?;
+        Ok(id as BufferId)
+    }
+
+    fn get_buffer_status(buffer_id: BufferId) -> Result<BufferStatus, ErrorReturnCode> {
+        let response = apex_call(
+            ApexSyscall::GetBufferStatus,
+            vec![buffer_id as u64],
+            Vec::new(),
+        )?;
+        let [nb_message, max_nb_message, max_message_size, waiting_processes] =
+            response_values(&response)?;
+
+        Ok(BufferStatus {
+            nb_message: nb_message as MessageRange,
+            max_nb_message: max_nb_message as MessageRange,
+            max_message_size: max_message_size as MessageSize,
+            waiting_processes: waiting_processes as WaitingRange,
+        })
+    }
+}
+
+impl ApexBlackboardP1 for ApexLinuxPartition {
+    fn create_blackboard(
+        blackboard_name: BlackboardName,
+        max_message_size: MessageSize,
+    ) -> Result<BlackboardId, ErrorReturnCode> {
+        let name = Name::new(blackboard_name);
+        let name = name.to_str().map_err(|_| ErrorReturnCode::InvalidParam)?;
+        let response = apex_call(
+            ApexSyscall::CreateBlackboard,
+            vec![max_message_size as u64],
+            name.as_bytes().to_vec(),
+        )?;
+        let [id] = response_values(&response)?;
+        Ok(id as BlackboardId)
+    }
+
+    fn display_blackboard(
+        blackboard_id: BlackboardId,
+        message: &[ApexByte],
+    ) -> Result<(), ErrorReturnCode> {
+        apex_call(
+            ApexSyscall::DisplayBlackboard,
+            vec![blackboard_id as u64],
+            message.to_vec(),
+        )?;
+        Ok(())
+    }
+
+    unsafe fn read_blackboard(
+        blackboard_id: BlackboardId,
+        time_out: ApexSystemTime,
+        message: &mut [ApexByte],
+    ) -> Result<MessageSize, ErrorReturnCode> {
+        // Returns once a message is displayed
+        let response = apex_call(
+            ApexSyscall::ReadBlackboard,
+            vec![caller_id(), blackboard_id as u64, time_out as u64],
+            Vec::new(),
+        )?;
+        copy_message(message, &response.data)
+    }
+
+    fn clear_blackboard(blackboard_id: BlackboardId) -> Result<(), ErrorReturnCode> {
+        apex_call(
+            ApexSyscall::ClearBlackboard,
+            vec![blackboard_id as u64],
+            Vec::new(),
+        )?;
+        Ok(())
+    }
+
+    fn get_blackboard_id(blackboard_name: BlackboardName) -> Result<BlackboardId, ErrorReturnCode> {
+        let name = Name::new(blackboard_name);
+        let name = name.to_str().map_err(|_| ErrorReturnCode::InvalidConfig)?;
+        let response = apex_call(
+            ApexSyscall::GetBlackboardId,
+            Vec::new(),
+            name.as_bytes().to_vec(),
+        )?;
+        let [id] = response_values(&response)?;
+        Ok(id as BlackboardId)
+    }
+
+    fn get_blackboard_status(
+        blackboard_id: BlackboardId,
+    ) -> Result<BlackboardStatus, ErrorReturnCode> {
+        let response = apex_call(
+            ApexSyscall::GetBlackboardStatus,
+            vec![blackboard_id as u64],
+            Vec::new(),
+        )?;
+        let [occupied, max_message_size, waiting_processes] = response_values(&response)?;
+
+        Ok(BlackboardStatus {
+            empty_indicator: match occupied {
+                0 => EmptyIndicator::Empty,
+                _ => EmptyIndicator::Occupied,
+            },
+            max_message_size: max_message_size as MessageSize,
+            waiting_processes: waiting_processes as WaitingRange,
+        })
+    }
+}
+
+impl ApexSemaphoreP1 for ApexLinuxPartition {
+    fn create_semaphore(
+        semaphore_name: SemaphoreName,
+        current_value: SemaphoreValue,
+        maximum_value: SemaphoreValue,
+        queuing_discipline: QueuingDiscipline,
+    ) -> Result<SemaphoreId, ErrorReturnCode> {
+        let name = Name::new(semaphore_name);
+        let name = name.to_str().map_err(|_| ErrorReturnCode::InvalidParam)?;
+        let response = apex_call(
+            ApexSyscall::CreateSemaphore,
+            vec![
+                current_value as u64,
+                maximum_value as u64,
+                queuing_discipline_to_u64(queuing_discipline),
+            ],
+            name.as_bytes().to_vec(),
+        )?;
+        let [id] = response_values(&response)?;
+        Ok(id as SemaphoreId)
+    }
+
+    fn wait_semaphore(
+        semaphore_id: SemaphoreId,
+        time_out: ApexSystemTime,
+    ) -> Result<(), ErrorReturnCode> {
+        // Returns once the semaphore was taken
+        apex_call(
+            ApexSyscall::WaitSemaphore,
+            vec![caller_id(), semaphore_id as u64, time_out as u64],
+            Vec::new(),
+        )?;
+        Ok(())
+    }
+
+    fn signal_semaphore(semaphore_id: SemaphoreId) -> Result<(), ErrorReturnCode> {
+        apex_call(
+            ApexSyscall::SignalSemaphore,
+            vec![semaphore_id as u64],
+            Vec::new(),
+        )?;
+        Ok(())
+    }
+
+    fn get_semaphore_id(semaphore_name: SemaphoreName) -> Result<SemaphoreId, ErrorReturnCode> {
+        let name = Name::new(semaphore_name);
+        let name = name.to_str().map_err(|_| ErrorReturnCode::InvalidConfig)?;
+        let response = apex_call(
+            ApexSyscall::GetSemaphoreId,
+            Vec::new(),
+            name.as_bytes().to_vec(),
+        )?;
+        let [id] = response_values(&response)?;
+        Ok(id as SemaphoreId)
+    }
+
+    fn get_semaphore_status(semaphore_id: SemaphoreId) -> Result<SemaphoreStatus, ErrorReturnCode> {
+        let response = apex_call(
+            ApexSyscall::GetSemaphoreStatus,
+            vec![semaphore_id as u64],
+            Vec::new(),
+        )?;
+        let [current_value, maximum_value, waiting_processes] = response_values(&response)?;
+
+        Ok(SemaphoreStatus {
+            current_value: current_value as SemaphoreValue,
+            maximum_value: maximum_value as SemaphoreValue,
+            waiting_processes: waiting_processes as WaitingRange,
+        })
+    }
+}
+
+impl ApexEventP1 for ApexLinuxPartition {
+    fn create_event(event_name: EventName) -> Result<EventId, ErrorReturnCode> {
+        let name = Name::new(event_name);
+        let name = name.to_str().map_err(|_| ErrorReturnCode::InvalidParam)?;
+        let response = apex_call(
+            ApexSyscall::CreateEvent,
+            Vec::new(),
+            name.as_bytes().to_vec(),
+        )?;
+        let [id] = response_values(&response)?;
+        Ok(id as EventId)
+    }
+
+    fn set_event(event_id: EventId) -> Result<(), ErrorReturnCode> {
+        apex_call(ApexSyscall::SetEvent, vec![event_id as u64], Vec::new())?;
+        Ok(())
+    }
+
+    fn reset_event(event_id: EventId) -> Result<(), ErrorReturnCode> {
+        apex_call(ApexSyscall::ResetEvent, vec![event_id as u64], Vec::new())?;
+        Ok(())
+    }
+
+    fn wait_event(event_id: EventId, time_out: ApexSystemTime) -> Result<(), ErrorReturnCode> {
+        // Returns once the event is up
+        apex_call(
+            ApexSyscall::WaitEvent,
+            vec![caller_id(), event_id as u64, time_out as u64],
+            Vec::new(),
+        )?;
+        Ok(())
+    }
+
+    fn get_event_id(event_name: EventName) -> Result<EventId, ErrorReturnCode> {
+        let name = Name::new(event_name);
+        let name = name.to_str().map_err(|_| ErrorReturnCode::InvalidConfig)?;
+        let response = apex_call(
+            ApexSyscall::GetEventId,
+            Vec::new(),
+            name.as_bytes().to_vec(),
+        )?;
+        let [id] = response_values(&response)?;
+        Ok(id as EventId)
+    }
+
+    fn get_event_status(event_id: EventId) -> Result<EventStatus, ErrorReturnCode> {
+        let response = apex_call(
+            ApexSyscall::GetEventStatus,
+            vec![event_id as u64],
+            Vec::new(),
+        )?;
+        let [up, waiting_processes] = response_values(&response)?;
+
+        Ok(EventStatus {
+            event_state: match up {
+                0 => EventState::Down,
+                _ => EventState::Up,
+            },
+            waiting_processes: waiting_processes as WaitingRange,
+        })
+    }
+}
+
+impl ApexMutexP1 for ApexLinuxPartition {
+    fn create_mutex(
+        mutex_name: MutexName,
+        mutex_priority: Priority,
+        queuing_discipline: QueuingDiscipline,
+    ) -> Result<MutexId, ErrorReturnCode> {
+        let name = Name::new(mutex_name);
+        let name = name.to_str().map_err(|_| ErrorReturnCode::InvalidParam)?;
+        let response = apex_call(
+            ApexSyscall::CreateMutex,
+            vec![
+                mutex_priority as u64,
+                queuing_discipline_to_u64(queuing_discipline),
+            ],
+            name.as_bytes().to_vec(),
+        )?;
+        let [id] = response_values(&response)?;
+        Ok(id as MutexId)
+    }
+
+    fn acquire_mutex(mutex_id: MutexId, time_out: ApexSystemTime) -> Result<(), ErrorReturnCode> {
+        // Returns once the mutex was acquired
+        apex_call(
+            ApexSyscall::AcquireMutex,
+            vec![caller_id(), mutex_id as u64, time_out as u64],
+            Vec::new(),
+        )?;
+        Ok(())
+    }
+
+    fn release_mutex(mutex_id: MutexId) -> Result<(), ErrorReturnCode> {
+        apex_call(
+            ApexSyscall::ReleaseMutex,
+            vec![caller_id(), mutex_id as u64],
+            Vec::new(),
+        )?;
+        Ok(())
+    }
+
+    fn reset_mutex(mutex_id: MutexId, process_id: ProcessId) -> Result<(), ErrorReturnCode> {
+        apex_call(
+            ApexSyscall::ResetMutex,
+            vec![mutex_id as u64, process_id as u64],
+            Vec::new(),
+        )?;
+        Ok(())
+    }
+
+    fn get_mutex_id(mutex_name: MutexName) -> Result<MutexId, ErrorReturnCode> {
+        let name = Name::new(mutex_name);
+        let name = name.to_str().map_err(|_| ErrorReturnCode::InvalidConfig)?;
+        let response = apex_call(
+            ApexSyscall::GetMutexId,
+            Vec::new(),
+            name.as_bytes().to_vec(),
+        )?;
+        let [id] = response_values(&response)?;
+        Ok(id as MutexId)
+    }
+
+    fn get_mutex_status(mutex_id: MutexId) -> Result<MutexStatus, ErrorReturnCode> {
+        let response = apex_call(
+            ApexSyscall::GetMutexStatus,
+            vec![mutex_id as u64],
+            Vec::new(),
+        )?;
+        let [owned, owner, priority, lock_count, waiting_processes] = response_values(&response)?;
+
+        Ok(MutexStatus {
+            mutex_owner: owner as ProcessId,
+            mutex_state: match owned {
+                0 => MutexState::Available,
+                _ => MutexState::Owned,
+            },
+            mutex_priority: priority as Priority,
+            lock_count: lock_count as LockCount,
+            waiting_processes: waiting_processes as WaitingRange,
+        })
+    }
+
+    fn get_process_mutex_state(process_id: ProcessId) -> Result<MutexId, ErrorReturnCode> {
+        let response = apex_call(
+            ApexSyscall::GetProciessMutexState,
+            vec![process_id as u64],
+            Vec::new(),
+        )?;
+        let [id] = response_values(&response)?;
+        Ok(id as MutexId)
+    }
+}
+
 impl ApexErrorP4 for ApexLinuxPartition {
     fn report_application_message(message: &[ApexByte]) -> Result<(), ErrorReturnCode> {
         if message.len() > MAX_ERROR_MESSAGE_SIZE {
             return Err(ErrorReturnCode::InvalidParam);
         }
-        if let Ok(msg) = std::str::from_utf8(message) {
-            // Logging may fail temporarily, because the resource can not be written to
-            // (e.g. queue is full), but the API does not allow us any other
-            // return code than INVALID_PARAM.
-            if let Err(e) = SENDER.try_send(&PartitionCall::Message(msg.to_string())) {
-                if let Some(e) = e.source().downcast_ref::<std::io::Error>() {
-                    if e.raw_os_error() == Some(EAGAIN) {
-                        return Ok(());
-                    }
-                }
-                panic!("Failed to report application message: {}", e);
-            }
-        }
+        apex_call(
+            ApexSyscall::ReportApplicationMessage,
+            Vec::new(),
+            message.to_vec(),
+        )?;
         Ok(())
     }
 
@@ -240,12 +1064,118 @@ impl ApexErrorP4 for ApexLinuxPartition {
         error_code: ErrorCode,
         message: &[ApexByte],
     ) -> Result<(), ErrorReturnCode> {
-        if let ErrorCode::ApplicationError = error_code {
-            Self::report_application_message(message).unwrap();
-            Self::raise_system_error(SystemError::ApplicationError);
-            Ok(())
-        } else {
-            Err(ErrorReturnCode::InvalidParam)
+        if error_code != ErrorCode::ApplicationError || message.len() > MAX_ERROR_MESSAGE_SIZE {
+            return Err(ErrorReturnCode::InvalidParam);
         }
+        // The hypervisor delivers the error to the error handler or to the health
+        // monitor
+        apex_call(
+            ApexSyscall::RaiseApplicationError,
+            vec![caller_id()],
+            message.to_vec(),
+        )?;
+        Ok(())
+    }
+}
+
+impl ApexErrorP1 for ApexLinuxPartition {
+    fn create_error_handler(
+        entry_point: SystemAddress,
+        stack_size: StackSize,
+    ) -> Result<(), ErrorReturnCode> {
+        // The hypervisor checks the current mode (only allowed in warm and cold start)
+        LinuxProcess::create_error_handler(entry_point, stack_size)?;
+        Ok(())
+    }
+
+    fn get_error_status() -> Result<ErrorStatus, ErrorReturnCode> {
+        let response = apex_call(ApexSyscall::GetErrorStatus, vec![caller_id()], Vec::new())?;
+        let [error_code, failed_process_id] = response_values(&response)?;
+        let failed_process_id = failed_process_id as ProcessId;
+        // The address at which the error occurred is unknown, hence the entry point
+        // of the failed process is reported instead
+        let failed_process =
+            LinuxProcess::get(failed_process_id).ok_or(ErrorReturnCode::InvalidConfig)?;
+        let mut message = [0; MAX_ERROR_MESSAGE_SIZE];
+        let length = min(response.data.len(), MAX_ERROR_MESSAGE_SIZE);
+        message[..length].copy_from_slice(&response.data[..length]);
+        let length =
+            ErrorMessageSize::try_from(length).map_err(|_| ErrorReturnCode::InvalidConfig)?;
+
+        Ok(ErrorStatus {
+            error_code: error_code_from_u64(error_code)
+                .map_err(|_| ErrorReturnCode::InvalidConfig)?,
+            length,
+            failed_process_id,
+            failed_address: failed_process.attributes().entry_point,
+            message,
+        })
+    }
+
+    fn configure_error_handler(
+        _concurrency_control: ErrorHandlerConcurrencyControl,
+        processor_core_id: ProcessorCoreId,
+    ) -> Result<(), ErrorReturnCode> {
+        // Partitions are limited to a single core, on which the error handler always
+        // preempts all other processes
+        if processor_core_id != 0
+            || LinuxProcess::by_name(PartitionConstants::ERROR_HANDLER_NAME).is_none()
+        {
+            return Err(ErrorReturnCode::InvalidConfig);
+        }
+        if let OperatingMode::Normal = PARTITION_MODE.read().unwrap() {
+            return Err(ErrorReturnCode::InvalidMode);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use a653rs_linux_core::channel::{PortConfig, QueuingChannelConfig};
+    use a653rs_linux_core::queuing::{Queuing, QueuingSource};
+
+    use super::*;
+
+    #[test]
+    fn test_pop_queuing_message() {
+        let port = |partition: &str| PortConfig {
+            partition: partition.to_string(),
+            port: "port".to_string(),
+        };
+        let mut channel = Queuing::try_from(QueuingChannelConfig {
+            msg_size: "8".parse().unwrap(),
+            msg_num: 2,
+            source: port("Foo"),
+            destination: port("Bar"),
+        })
+        .unwrap();
+        let mut source = QueuingSource::try_from(&channel.constant("Foo").unwrap()).unwrap();
+        let constant = channel.constant("Bar").unwrap();
+        let mut destination = QueuingDestination::try_from(&constant).unwrap();
+
+        let mut message = [0; 8];
+        assert_eq!(
+            pop_queuing_message(&constant, &mut destination, &mut message),
+            Ok(None)
+        );
+        assert!(source.push(&[1, 2, 3]).unwrap());
+        channel.swap().unwrap();
+
+        // A slice, which can not hold the largest message, leaves the queue alone
+        for short in [&mut [][..], &mut message[..3]] {
+            assert_eq!(
+                pop_queuing_message(&constant, &mut destination, short),
+                Err(ErrorReturnCode::InvalidParam)
+            );
+        }
+        assert_eq!(destination.len(), 1);
+
+        assert_eq!(
+            pop_queuing_message(&constant, &mut destination, &mut message),
+            Ok(Some((3, false)))
+        );
+        assert_eq!(message[..3], [1, 2, 3]);
+        assert!(destination.is_empty());
     }
 }
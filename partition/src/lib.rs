@@ -17,15 +17,19 @@ use std::os::fd::FromRawFd;
 use a653rs_linux_core::ipc::IoReceiver;
 
 use std::os::fd::{AsRawFd, OwnedFd};
-use std::sync::Arc;
+use std::sync::atomic::AtomicI32;
+use std::sync::Mutex;
 use std::time::{Duration, Instant};
 
+use a653rs::bindings::PortDirection;
 use a653rs::prelude::OperatingMode;
 use a653rs_linux_core::file::{get_memfd, TempFile};
 use a653rs_linux_core::health_event::PartitionCall;
 use a653rs_linux_core::ipc::{self, IpcSender};
 use a653rs_linux_core::partition::*;
+use a653rs_linux_core::queuing::{QueuingDestination, QueuingSource};
 use a653rs_linux_core::syscall::SYSCALL_SOCKET_PATH;
+use memmap2::MmapMut;
 use nix::sys::socket::{self, connect, AddressFamily, SockFlag, SockType, UnixAddr};
 use once_cell::sync::{Lazy, OnceCell};
 use process::Process;
@@ -39,6 +43,8 @@ pub(crate) mod process;
 
 const SAMPLING_PORTS_FILE: &str = "sampling_channels";
 // const MAX_SAMPLING_PORTS: usize = 32;
+const QUEUING_PORTS_FILE: &str = "queuing_channels";
+pub(crate) const MAX_QUEUING_PORTS: usize = 32;
 
 pub(crate) static CONSTANTS: Lazy<PartitionConstants> =
     Lazy::new(|| PartitionConstants::open().unwrap());
@@ -50,11 +56,19 @@ pub(crate) static SYSTEM_TIME: Lazy<Instant> = Lazy::new(|| {
         .unwrap()
 });
 
+/// Memory of the partition retained across warm starts
+pub(crate) static PERSISTENT_MEMORY: Lazy<Option<Mutex<MmapMut>>> = Lazy::new(|| {
+    CONSTANTS
+        .persistent_memory_fd
+        .map(|fd| Mutex::new(unsafe { MmapMut::map_mut(fd).unwrap() }))
+});
+
 pub(crate) static PARTITION_MODE: Lazy<TempFile<OperatingMode>> =
     Lazy::new(|| TempFile::<OperatingMode>::try_from(CONSTANTS.partition_mode_fd).unwrap());
 
-pub(crate) static PERIODIC_PROCESS: OnceCell<Arc<Process>> = OnceCell::new();
-pub(crate) static APERIODIC_PROCESS: OnceCell<Arc<Process>> = OnceCell::new();
+/// Processes of this partition, indexed by their id minus one
+pub(crate) static PROCESSES: [OnceCell<Process>; PartitionConstants::MAX_PROCESSES] =
+    [const { OnceCell::new() }; PartitionConstants::MAX_PROCESSES];
 
 pub(crate) type SamplingPortsType = (usize, Duration);
 pub(crate) static SAMPLING_PORTS: Lazy<TempFile<ArrayVec<[SamplingPortsType; 32]>>> =
@@ -68,6 +82,50 @@ pub(crate) static SAMPLING_PORTS: Lazy<TempFile<ArrayVec<[SamplingPortsType; 32]
         }
     });
 
+/// Index of a created queuing port in [PartitionConstants::queuing] and its
+/// queuing discipline as system call value
+pub(crate) type QueuingPortsType = (usize, u64);
+pub(crate) static QUEUING_PORTS: Lazy<TempFile<ArrayVec<[QueuingPortsType; MAX_QUEUING_PORTS]>>> =
+    Lazy::new(|| {
+        if let Ok(fd) = get_memfd(QUEUING_PORTS_FILE) {
+            TempFile::try_from(fd).unwrap()
+        } else {
+            let file = TempFile::create(QUEUING_PORTS_FILE).unwrap();
+            file.write(&Default::default()).unwrap();
+            file
+        }
+    });
+
+/// Queue of a queuing port in the shared memory of its channel
+pub(crate) enum QueuingQueue {
+    Source(QueuingSource),
+    Destination(QueuingDestination),
+}
+
+/// Queues of the queuing ports, indexed like [PartitionConstants::queuing].
+/// Each queue is mapped once and its mutex serializes the accesses of the
+/// processes of this partition.
+pub(crate) static QUEUING_QUEUES: Lazy<Vec<Mutex<QueuingQueue>>> = Lazy::new(|| {
+    CONSTANTS
+        .queuing
+        .iter()
+        .map(|port| {
+            Mutex::new(match port.dir {
+                PortDirection::Source => {
+                    QueuingQueue::Source(QueuingSource::try_from(port).unwrap())
+                }
+                PortDirection::Destination => {
+                    QueuingQueue::Destination(QueuingDestination::try_from(port).unwrap())
+                }
+            })
+        })
+        .collect()
+});
+
+/// Number of processes currently waiting on each created queuing port
+pub(crate) static QUEUING_WAITING: [AtomicI32; MAX_QUEUING_PORTS] =
+    [const { AtomicI32::new(0) }; MAX_QUEUING_PORTS];
+
 pub(crate) static SENDER: Lazy<IpcSender<PartitionCall>> =
     Lazy::new(|| ipc::connect_sender(PartitionConstants::IPC_SENDER.as_ref()).unwrap());
 
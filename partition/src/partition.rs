@@ -7,16 +7,30 @@ use std::{
     net::{TcpStream, UdpSocket},
 };
 
+use a653rs::bindings::{ApexLongInteger, ApexSystemTime, ErrorReturnCode};
 use a653rs::prelude::{ApexErrorP4Ext, MAX_ERROR_MESSAGE_SIZE};
-use a653rs_linux_core::error::SystemError;
-use a653rs_linux_core::health_event::PartitionCall;
+use a653rs_linux_core::syscall::ApexSyscall;
 use log::{set_logger, set_max_level, LevelFilter, Record, SetLoggerError};
 
-use crate::{CONSTANTS, SENDER};
+use crate::process::Process;
+use crate::syscall::{apex_call, response_values};
+use crate::{CONSTANTS, PERSISTENT_MEMORY};
 
 #[cfg(feature = "socket")]
 use crate::{TCP_SOCKETS, UDP_SOCKETS};
 
+/// Identifier of a module schedule
+pub type ScheduleId = ApexLongInteger;
+
+/// P2-4 3.7.2.2 - Status of the module schedules
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ModuleScheduleStatus {
+    /// Zero if the schedule was never switched
+    pub time_of_last_schedule_switch: ApexSystemTime,
+    pub current_schedule: ScheduleId,
+    pub next_schedule: ScheduleId,
+}
+
 /// Static functions for within a partition
 #[derive(Debug, Clone, Copy)]
 pub struct ApexLinuxPartition;
@@ -26,6 +40,58 @@ impl ApexLinuxPartition {
         CONSTANTS.name.clone()
     }
 
+    /// Runs `f` on the persistent memory of this partition
+    ///
+    /// The persistent memory keeps its content across warm starts, while it is
+    /// zeroed on cold starts. Returns `None` if the partition has no persistent
+    /// memory configured.
+    pub fn with_persistent_memory<R>(f: impl FnOnce(&mut [u8]) -> R) -> Option<R> {
+        let mut mem = PERSISTENT_MEMORY
+            .as_ref()?
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        Some(f(&mut mem))
+    }
+
+    /// P2-4 3.7.2.1 - SET_MODULE_SCHEDULE
+    ///
+    /// Only privileged partitions may switch the schedule, which takes effect
+    /// at the end of the current major frame.
+    pub fn set_module_schedule(schedule_id: ScheduleId) -> Result<(), ErrorReturnCode> {
+        if schedule_id < 0 {
+            return Err(ErrorReturnCode::InvalidParam);
+        }
+        apex_call(
+            ApexSyscall::SetModuleSchedule,
+            vec![schedule_id as u64],
+            Vec::new(),
+        )?;
+        Ok(())
+    }
+
+    /// P2-4 3.7.2.2 - GET_MODULE_SCHEDULE_STATUS
+    pub fn get_module_schedule_status() -> Result<ModuleScheduleStatus, ErrorReturnCode> {
+        let response = apex_call(ApexSyscall::GetModuleScheduleStatus, Vec::new(), Vec::new())?;
+        let [last_switch, current, next] = response_values(&response)?;
+
+        Ok(ModuleScheduleStatus {
+            time_of_last_schedule_switch: last_switch as ApexSystemTime,
+            current_schedule: current as ScheduleId,
+            next_schedule: next as ScheduleId,
+        })
+    }
+
+    /// P2-4 3.7.2.3 - GET_MODULE_SCHEDULE_ID
+    pub fn get_module_schedule_id(schedule_name: &str) -> Result<ScheduleId, ErrorReturnCode> {
+        let response = apex_call(
+            ApexSyscall::GetModuleScheduleId,
+            Vec::new(),
+            schedule_name.as_bytes().to_vec(),
+        )?;
+        let [id] = response_values(&response)?;
+        Ok(id as ScheduleId)
+    }
+
     #[cfg(feature = "socket")]
     pub fn get_udp_socket(sockaddr: &str) -> Result<Option<UdpSocket>, ApexLinuxError> {
         for stored in UDP_SOCKETS.iter() {
@@ -47,12 +113,6 @@ impl ApexLinuxPartition {
         }
         Ok(None)
     }
-
-    pub(crate) fn raise_system_error(error: SystemError) {
-        if let Err(e) = SENDER.try_send(&PartitionCall::Error(error)) {
-            panic!("Could not send SystemError event {error:?}. {e:?}")
-        };
-    }
 }
 
 #[cfg(feature = "socket")]
@@ -87,8 +147,22 @@ impl ApexLogger {
         set_logger(&APEX_LOGGER).map(|()| set_max_level(level))
     }
 
+    /// Logs panics. The panic of a process is also raised as an application
+    /// error, which the error handler of the partition receives.
     pub fn install_panic_hook() {
-        std::panic::set_hook(Box::new(|panic_info| error!("{panic_info:#?}")));
+        std::panic::set_hook(Box::new(|panic_info| {
+            error!("{panic_info:#?}");
+            if let Some(process) = Process::get_self() {
+                let msg = panic_info.to_string();
+                let max = min(MAX_ERROR_MESSAGE_SIZE, msg.len());
+                apex_call(
+                    ApexSyscall::RaiseApplicationError,
+                    vec![process.id() as u64],
+                    msg.as_bytes()[0..max].to_vec(),
+                )
+                .ok();
+            }
+        }));
     }
 }
 
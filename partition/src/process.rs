@@ -1,10 +1,10 @@
-use std::sync::atomic::{AtomicBool, AtomicI32, Ordering};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicI32, Ordering};
 use std::sync::{Arc, Mutex};
 use std::thread::Builder;
 
 use a653rs::bindings::*;
-use a653rs::prelude::{ProcessAttribute, SystemTime};
-use anyhow::anyhow;
+use a653rs::prelude::{Name, ProcessAttribute, SystemTime};
 use nix::unistd::{gettid, Pid};
 
 use a653rs_linux_core::cgroup;
@@ -13,75 +13,127 @@ use a653rs_linux_core::error::{
     ErrorLevel, LeveledResult, ResultExt, SystemError, TypedResult, TypedResultExt,
 };
 use a653rs_linux_core::partition::PartitionConstants;
+use a653rs_linux_core::syscall::{deadline_to_u64, ApexSyscall};
 
-use crate::{APERIODIC_PROCESS, PERIODIC_PROCESS};
+use crate::syscall::{apex_call, response_values};
+use crate::PROCESSES;
 
-#[repr(C)]
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub(crate) struct Process {
-    id: i32,
+    id: ProcessId,
     attr: ProcessAttribute,
-    activated: Arc<AtomicBool>,
-    pid: Arc<AtomicI32>,
-    periodic: bool,
+    pid: AtomicI32,
     stack_size: usize,
 }
 
 impl Process {
-    pub fn create(attr: ProcessAttribute) -> LeveledResult<ProcessId> {
+    /// Registers a new process at the hypervisor, which assigns its id
+    pub fn create(attr: ProcessAttribute) -> Result<ProcessId, ErrorReturnCode> {
         let name = attr
             .name
             .to_str()
-            .lev_typ(SystemError::Panic, ErrorLevel::Partition)?
+            .map_err(|_| ErrorReturnCode::InvalidParam)?
             .to_string();
         trace!("Create New Process: {name:?}");
         let stack_size: usize = attr
             .stack_size
             .try_into()
-            .lev_typ(SystemError::Panic, ErrorLevel::Partition)?;
-
-        let periodic = attr.period != SystemTime::Infinite;
-        let id = periodic as i32 + 1;
+            .map_err(|_| ErrorReturnCode::InvalidParam)?;
+
+        let period: ApexSystemTime = attr.period.clone().into();
+        let time_capacity: ApexSystemTime = attr.time_capacity.clone().into();
+        let response = apex_call(
+            ApexSyscall::CreateProcess,
+            vec![
+                period as u64,
+                time_capacity as u64,
+                attr.base_priority as u64,
+                deadline_to_u64(attr.deadline),
+            ],
+            name.as_bytes().to_vec(),
+        )?;
+        let [id] = response_values(&response)?;
+        let id = id as ProcessId;
+
+        Self::register(id, attr, stack_size);
+        trace!("Created process \"{name}\" with id: {id}");
+        Ok(id)
+    }
 
-        let proc_file = if periodic {
-            &PERIODIC_PROCESS
-        } else {
-            &APERIODIC_PROCESS
+    /// Registers the error handler at the hypervisor and spawns its thread
+    ///
+    /// The thread remains frozen inside the cgroup of the error handler, until
+    /// the hypervisor activates the error handler.
+    pub fn create_error_handler(
+        entry_point: SystemAddress,
+        stack_size: StackSize,
+    ) -> Result<ProcessId, ErrorReturnCode> {
+        let stack_size: usize = stack_size
+            .try_into()
+            .map_err(|_| ErrorReturnCode::InvalidConfig)?;
+        let response = apex_call(ApexSyscall::CreateErrorHandler, Vec::new(), Vec::new())?;
+        let [id, base_priority] = response_values(&response)?;
+        let id = id as ProcessId;
+
+        let attr = ProcessAttribute {
+            period: SystemTime::Infinite,
+            time_capacity: SystemTime::Infinite,
+            entry_point,
+            stack_size: stack_size as StackSize,
+            base_priority: base_priority as Priority,
+            deadline: Deadline::Soft,
+            name: Name::from_str(PartitionConstants::ERROR_HANDLER_NAME).unwrap(),
         };
+        let process = Self::register(id, attr, stack_size);
+        // TODO use a bigger result which contains both panic and non-panic errors
+        process.start().unwrap();
+        trace!("Created error handler with id: {id}");
+        Ok(id)
+    }
 
-        let res = proc_file.try_insert(Arc::new(Self {
+    /// Stores a process, whose id the hypervisor assigned
+    fn register(id: ProcessId, attr: ProcessAttribute, stack_size: usize) -> &'static Self {
+        let res = PROCESSES[id as usize - 1].set(Self {
             id,
             attr,
-            activated: Arc::new(AtomicBool::new(false)),
-            pid: Arc::new(AtomicI32::new(0)),
-            periodic,
+            pid: AtomicI32::new(0),
             stack_size,
-        }));
-        if res.is_ok() {
-            trace!("Created process \"{name}\" with id: {id}");
-            Ok(id as ProcessId)
-        } else {
-            Err(anyhow!("Process type already exists. Periodic: {periodic}"))
-                .lev_typ(SystemError::Panic, ErrorLevel::Partition)
+        });
+        if res.is_err() {
+            panic!("Process id {id} was assigned twice");
         }
+        PROCESSES[id as usize - 1].get().unwrap()
     }
 
-    pub(crate) fn get_self() -> Option<Arc<Self>> {
-        if let Some(p) = APERIODIC_PROCESS.get() {
-            let id = p.pid.load(Ordering::SeqCst);
-            if id == nix::unistd::gettid().as_raw() {
-                return Some(p.clone());
-            }
-        }
+    /// Returns the process with the given id
+    pub(crate) fn get(id: ProcessId) -> Option<&'static Self> {
+        let index = usize::try_from(id).ok()?.checked_sub(1)?;
+        PROCESSES.get(index)?.get()
+    }
 
-        if let Some(p) = PERIODIC_PROCESS.get() {
-            let id = p.pid.load(Ordering::SeqCst);
-            if id == nix::unistd::gettid().as_raw() {
-                return Some(p.clone());
-            }
-        }
+    /// Returns the process with the given name
+    pub(crate) fn by_name(name: &str) -> Option<&'static Self> {
+        PROCESSES
+            .iter()
+            .map_while(|p| p.get())
+            .find(|p| p.attr.name.to_str() == Ok(name))
+    }
+
+    /// Returns the process executing the calling thread
+    pub(crate) fn get_self() -> Option<&'static Self> {
+        let tid = gettid().as_raw();
+        PROCESSES
+            .iter()
+            .map_while(|p| p.get())
+            .find(|p| p.pid.load(Ordering::SeqCst) == tid)
+    }
 
-        None
+    pub fn id(&self) -> ProcessId {
+        self.id
+    }
+
+    pub fn attributes(&self) -> &ProcessAttribute {
+        &self.attr
     }
 
     pub fn name(&self) -> LeveledResult<&str> {
@@ -96,9 +148,6 @@ impl Process {
         trace!("Start Process \"{name}\"");
 
         let cg = self.cg().lev(ErrorLevel::Partition)?;
-        cg.freeze()
-            .typ(SystemError::CGroup)
-            .lev(ErrorLevel::Partition)?;
 
         let entry = self.attr.entry_point;
 
@@ -137,22 +186,17 @@ impl Process {
         Ok(())
     }
 
+    /// The cgroup of this process, which the hypervisor created frozen
     pub(crate) fn cg(&self) -> TypedResult<CGroup> {
-        let cg_name = if self.periodic {
-            PartitionConstants::PERIODIC_PROCESS_CGROUP
-        } else {
-            PartitionConstants::APERIODIC_PROCESS_CGROUP
-        };
-
         let path = cgroup::mount_point().typ(SystemError::CGroup)?;
         let path = path
             .join(PartitionConstants::PROCESSES_CGROUP)
-            .join(cg_name);
+            .join(self.id.to_string());
 
         CGroup::import_root(path).typ(SystemError::CGroup)
     }
 
     pub fn periodic(&self) -> bool {
-        self.periodic
+        self.attr.period != SystemTime::Infinite
     }
 }
@@ -6,6 +6,7 @@ use std::io::IoSlice;
 use std::num::NonZeroUsize;
 use std::os::fd::{AsFd, AsRawFd, BorrowedFd};
 
+use a653rs::bindings::ErrorReturnCode;
 use anyhow::Result;
 use nix::libc::EINTR;
 use nix::sys::eventfd::{self, EfdFlags};
@@ -13,7 +14,7 @@ use nix::sys::socket::{sendmsg, ControlMessage, MsgFlags};
 use polling::{Event, Events, Poller};
 
 use a653rs_linux_core::mfd::{Mfd, Seals};
-use a653rs_linux_core::syscall::{SyscallRequest, SyscallResponse};
+use a653rs_linux_core::syscall::{ApexSyscall, SyscallRequest, SyscallResponse};
 
 use crate::SYSCALL;
 
@@ -79,6 +80,39 @@ pub fn execute(request: SyscallRequest) -> Result<SyscallResponse> {
     execute_fd(SYSCALL.as_fd(), request)
 }
 
+/// Executes an APEX service in the hypervisor
+///
+/// Returns the response of the hypervisor, if the service succeeded.
+/// Panics if the system call could not be transmitted to the hypervisor.
+pub(crate) fn apex_call(
+    id: ApexSyscall,
+    params: Vec<u64>,
+    data: Vec<u8>,
+) -> Result<SyscallResponse, ErrorReturnCode> {
+    let response = execute(SyscallRequest { id, params, data })
+        .unwrap_or_else(|e| panic!("Failed to execute system call {id:?}: {e}"));
+    response
+        .result()
+        .unwrap_or_else(|e| panic!("Invalid response to system call {id:?}: {e}"))?;
+    Ok(response)
+}
+
+/// Extracts the `N` output values of a successful system call
+///
+/// Yields `InvalidConfig`, if the hypervisor answered with a different number
+/// of values.
+pub(crate) fn response_values<const N: usize>(
+    response: &SyscallResponse,
+) -> Result<[u64; N], ErrorReturnCode> {
+    response.values.as_slice().try_into().map_err(|_| {
+        error!(
+            "Invalid response to system call {:?}: {response:?}",
+            response.id
+        );
+        ErrorReturnCode::InvalidConfig
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use std::io::IoSliceMut;
@@ -109,6 +143,7 @@ mod tests {
                 SyscallRequest {
                     id: ApexSyscall::Start,
                     params: vec![1, 2, 42],
+                    data: vec![],
                 },
             )
             .unwrap();
@@ -149,12 +184,9 @@ mod tests {
             // Write the response
             response_fd
                 .write(
-                    &SyscallResponse {
-                        id: ApexSyscall::Start,
-                        status: 42,
-                    }
-                    .serialize()
-                    .unwrap(),
+                    &SyscallResponse::new(ApexSyscall::Start, 42)
+                        .serialize()
+                        .unwrap(),
                 )
                 .unwrap();
             response_fd.finalize(Seals::Readable).unwrap();
@@ -167,4 +199,20 @@ mod tests {
         request_thread.join().unwrap();
         response_thread.join().unwrap();
     }
+
+    #[test]
+    fn test_response_values() {
+        let mut response = SyscallResponse::new(ApexSyscall::GetEventStatus, 0);
+        response.values = vec![1, 2];
+
+        assert_eq!(response_values(&response), Ok([1, 2]));
+        assert_eq!(
+            response_values::<3>(&response),
+            Err(ErrorReturnCode::InvalidConfig)
+        );
+        assert_eq!(
+            response_values::<1>(&response),
+            Err(ErrorReturnCode::InvalidConfig)
+        );
+    }
 }